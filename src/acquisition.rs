@@ -0,0 +1,142 @@
+//! Acquisition functions scoring candidates from the estimated densities.
+//!
+//! [`TpeOptimizer::ask`](crate::TpeOptimizer::ask) returns the candidate with the highest score,
+//! and the acquisition function used can be changed via
+//! [`TpeOptimizerBuilder::acquisition`](crate::TpeOptimizerBuilder::acquisition).
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::acquisition::LowerConfidenceRatio;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut optim = tpe::TpeOptimizerBuilder::new()
+//!     .acquisition(LowerConfidenceRatio::new(1.0))
+//!     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+//! let mut rng = rand::thread_rng();
+//! for _ in 0..30 {
+//!     let x = optim.ask(&mut rng)?;
+//!     optim.tell(x, x.powi(2))?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+/// This trait allows defining the score of a candidate (the higher, the better).
+pub trait AcquisitionFunction: std::fmt::Debug + Send + Sync {
+    /// Returns the score of a candidate.
+    ///
+    /// `superior_log_density` and `inferior_log_density` are the log densities `log(l(x))` and `log(g(x))`
+    /// of the candidate in the warped space.
+    ///
+    /// Note that the returned score is added by the log local penalization terms
+    /// (see [`TpeOptimizerBuilder::local_penalization`](crate::TpeOptimizerBuilder::local_penalization)),
+    /// so it should be in a log scale.
+    fn score(
+        &self,
+        superior_log_density: f64,
+        inferior_log_density: f64,
+        context: &AcquisitionContext,
+    ) -> f64;
+}
+
+/// Information about the current model passed to [`AcquisitionFunction::score`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcquisitionContext {
+    /// Ratio of the superior trials.
+    pub gamma: f64,
+
+    /// Number of the superior trials.
+    pub superiors: usize,
+
+    /// Number of the inferior trials.
+    pub inferiors: usize,
+
+    /// Width of the warped parameter range.
+    pub width: f64,
+}
+
+/// Log density ratio `log(l(x) / g(x))` (the default).
+///
+/// This is proportional to the expected improvement under the TPE model.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogDensityRatio;
+
+impl AcquisitionFunction for LogDensityRatio {
+    fn score(
+        &self,
+        superior_log_density: f64,
+        inferior_log_density: f64,
+        _context: &AcquisitionContext,
+    ) -> f64 {
+        superior_log_density - inferior_log_density
+    }
+}
+
+/// Log probability of improvement `log(γl(x) / (γl(x) + (1 - γ)g(x)))`.
+///
+/// The ranking of candidates is the same as [`LogDensityRatio`],
+/// but the score saturates at zero, which weakens the preference for extreme ratios
+/// when it's combined with local penalization.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProbabilityOfImprovement;
+
+impl AcquisitionFunction for ProbabilityOfImprovement {
+    fn score(
+        &self,
+        superior_log_density: f64,
+        inferior_log_density: f64,
+        context: &AcquisitionContext,
+    ) -> f64 {
+        let l = superior_log_density + context.gamma.ln();
+        let g = inferior_log_density + (1.0 - context.gamma).ln();
+        let max = l.max(g);
+        if max == f64::NEG_INFINITY {
+            return f64::NAN;
+        }
+        l - (max + ((l - max).exp() + (g - max).exp()).ln())
+    }
+}
+
+/// Log density ratio penalized by its uncertainty: `log(l(x) / g(x)) - kappa * stderr`.
+///
+/// The standard error is approximated by `sqrt(1 / (s + 1) + 1 / (f + 1))`,
+/// where `s` and `f` are the expected numbers of the superior and inferior trials
+/// in a bin of width `width / sqrt(n)` around the candidate (`n` is the number of the trials).
+/// So candidates supported by few trials are discounted.
+#[derive(Debug, Clone, Copy)]
+pub struct LowerConfidenceRatio {
+    kappa: f64,
+}
+
+impl LowerConfidenceRatio {
+    /// Makes a new [`LowerConfidenceRatio`] instance.
+    ///
+    /// Negative `kappa` is regarded as zero.
+    pub fn new(kappa: f64) -> Self {
+        Self {
+            kappa: kappa.max(0.0),
+        }
+    }
+
+    /// Returns the weight of the standard error.
+    pub fn kappa(&self) -> f64 {
+        self.kappa
+    }
+}
+
+impl AcquisitionFunction for LowerConfidenceRatio {
+    fn score(
+        &self,
+        superior_log_density: f64,
+        inferior_log_density: f64,
+        context: &AcquisitionContext,
+    ) -> f64 {
+        let n = (context.superiors + context.inferiors).max(1) as f64;
+        let bin = context.width / n.sqrt();
+        let s = context.superiors as f64 * superior_log_density.exp() * bin;
+        let f = context.inferiors as f64 * inferior_log_density.exp() * bin;
+        let stderr = (1.0 / (s + 1.0) + 1.0 / (f + 1.0)).sqrt();
+        superior_log_density - inferior_log_density - self.kappa * stderr
+    }
+}
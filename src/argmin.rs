@@ -0,0 +1,84 @@
+//! Adapter for the [`argmin`](https://argmin-rs.org/) optimization framework.
+//!
+//! # Examples
+//!
+//! ```
+//! use argmin::core::{CostFunction, Error, Executor};
+//! use tpe::argmin::TpeSolver;
+//! use tpe::study::Study;
+//!
+//! struct Sphere;
+//!
+//! impl CostFunction for Sphere {
+//!     type Param = Vec<f64>;
+//!     type Output = f64;
+//!
+//!     fn cost(&self, p: &Self::Param) -> Result<Self::Output, Error> {
+//!         Ok(p.iter().map(|x| x * x).sum())
+//!     }
+//! }
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let study = Study::with_seed(
+//!     vec![
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!     ],
+//!     0,
+//! );
+//! let result = Executor::new(Sphere, TpeSolver::new(study))
+//!     .configure(|state| state.max_iters(100))
+//!     .run()?;
+//! assert!(result.state().best_cost < 1.0);
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::study::Study;
+use ::argmin::core::{CostFunction, Error, IterState, Problem, Solver, KV};
+
+/// [`Solver`] that evaluates the parameters asked by a [`Study`] at each iteration.
+///
+/// The parameter of the problem is a vector holding one value for each optimizer of the study.
+/// Note that checkpointing is not supported because the study is not serializable.
+#[derive(Debug)]
+pub struct TpeSolver<T = DefaultEstimatorBuilder> {
+    study: Study<T>,
+}
+
+impl<T: BuildDensityEstimator> TpeSolver<T> {
+    /// Makes a new [`TpeSolver`] instance.
+    pub fn new(study: Study<T>) -> Self {
+        Self { study }
+    }
+
+    /// Returns the underlying study.
+    pub fn study(&self) -> &Study<T> {
+        &self.study
+    }
+
+    /// Takes the underlying study.
+    pub fn into_study(self) -> Study<T> {
+        self.study
+    }
+}
+
+impl<O, T> Solver<O, IterState<Vec<f64>, (), (), (), (), f64>> for TpeSolver<T>
+where
+    O: CostFunction<Param = Vec<f64>, Output = f64>,
+    T: BuildDensityEstimator,
+    T::Error: Send + Sync + 'static,
+{
+    const NAME: &'static str = "TPE";
+
+    fn next_iter(
+        &mut self,
+        problem: &mut Problem<O>,
+        state: IterState<Vec<f64>, (), (), (), (), f64>,
+    ) -> Result<(IterState<Vec<f64>, (), (), (), (), f64>, Option<KV>), Error> {
+        let params = self.study.ask().map_err(Error::new)?;
+        let cost = problem.cost(&params)?;
+        self.study.tell(params.clone(), cost)?;
+        Ok((state.param(params).cost(cost), None))
+    }
+}
@@ -0,0 +1,43 @@
+use crate::Observation;
+use arrow_array::{Float64Array, RecordBatch, StringArray, TimestampMillisecondArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+
+pub(crate) fn record_batch(trials: &[&Observation]) -> RecordBatch {
+    let schema = Schema::new(vec![
+        Field::new("trial_id", DataType::UInt64, false),
+        Field::new("param", DataType::Float64, false),
+        Field::new("value", DataType::Float64, false),
+        Field::new(
+            "timestamp",
+            DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+            true,
+        ),
+        Field::new("metadata", DataType::Utf8, true),
+    ]);
+    let timestamps = trials.iter().map(|t| {
+        t.told_at
+            .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+    });
+    RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(UInt64Array::from_iter_values(
+                trials.iter().map(|t| t.seq as u64),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                trials.iter().map(|t| t.param),
+            )),
+            Arc::new(Float64Array::from_iter_values(
+                trials.iter().map(|t| t.value),
+            )),
+            Arc::new(TimestampMillisecondArray::from_iter(timestamps).with_timezone("UTC")),
+            Arc::new(StringArray::from_iter(
+                trials.iter().map(|t| t.metadata.as_deref()),
+            )),
+        ],
+    )
+    .expect("unreachable")
+}
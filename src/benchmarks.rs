@@ -0,0 +1,370 @@
+//! Synthetic objective functions and a small harness for benchmarking optimizers.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::benchmarks::{self, Objective, Sphere};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut objective = Sphere::new(2);
+//! let mut study = benchmarks::default_study(&objective, 0)?;
+//! let result = benchmarks::run(&mut objective, &mut study, 100)?;
+//! assert_eq!(result.best_values.len(), 100);
+//! assert!(result.regret().unwrap_or(f64::INFINITY) < 1.0);
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::BuildDensityEstimator;
+use crate::range::{Range, RangeError};
+use crate::sampler::{GridError, GridSampler, Sampler};
+use crate::study::Study;
+use crate::{parzen_estimator, range, TpeOptimizer};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use rand_distr::{Distribution, Normal};
+use std::f64::consts::PI;
+
+/// Objective function to be minimized.
+pub trait Objective {
+    /// Returns the name of the function.
+    fn name(&self) -> String;
+
+    /// Returns the lower and upper bounds of each dimension.
+    fn bounds(&self) -> Vec<(f64, f64)>;
+
+    /// Evaluates the function at `x`.
+    fn evaluate(&mut self, x: &[f64]) -> f64;
+
+    /// Returns the global minimum value if it's known.
+    fn optimal_value(&self) -> Option<f64> {
+        None
+    }
+}
+
+/// Sphere function `sum(x_i^2)` on `[-5, 5]^dim`.
+#[derive(Debug, Clone)]
+pub struct Sphere {
+    dim: usize,
+}
+
+impl Sphere {
+    /// Makes a new [`Sphere`] instance.
+    pub fn new(dim: usize) -> Self {
+        Self { dim }
+    }
+}
+
+impl Objective for Sphere {
+    fn name(&self) -> String {
+        format!("sphere-{}d", self.dim)
+    }
+
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(-5.0, 5.0); self.dim]
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        x.iter().map(|x| x * x).sum()
+    }
+
+    fn optimal_value(&self) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+/// Rastrigin function on `[-5.12, 5.12]^dim`.
+#[derive(Debug, Clone)]
+pub struct Rastrigin {
+    dim: usize,
+}
+
+impl Rastrigin {
+    /// Makes a new [`Rastrigin`] instance.
+    pub fn new(dim: usize) -> Self {
+        Self { dim }
+    }
+}
+
+impl Objective for Rastrigin {
+    fn name(&self) -> String {
+        format!("rastrigin-{}d", self.dim)
+    }
+
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(-5.12, 5.12); self.dim]
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        10.0 * x.len() as f64
+            + x.iter()
+                .map(|x| x * x - 10.0 * (2.0 * PI * x).cos())
+                .sum::<f64>()
+    }
+
+    fn optimal_value(&self) -> Option<f64> {
+        Some(0.0)
+    }
+}
+
+/// Branin function on `[-5, 10] x [0, 15]`.
+#[derive(Debug, Clone, Default)]
+pub struct Branin;
+
+impl Objective for Branin {
+    fn name(&self) -> String {
+        "branin".to_owned()
+    }
+
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(-5.0, 10.0), (0.0, 15.0)]
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        let b = 5.1 / (4.0 * PI * PI);
+        let c = 5.0 / PI;
+        let t = 1.0 / (8.0 * PI);
+        (x[1] - b * x[0] * x[0] + c * x[0] - 6.0).powi(2) + 10.0 * (1.0 - t) * x[0].cos() + 10.0
+    }
+
+    fn optimal_value(&self) -> Option<f64> {
+        Some(0.397887)
+    }
+}
+
+/// Six-dimensional Hartmann function on `[0, 1]^6`.
+#[derive(Debug, Clone, Default)]
+pub struct Hartmann6;
+
+impl Objective for Hartmann6 {
+    fn name(&self) -> String {
+        "hartmann6".to_owned()
+    }
+
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        vec![(0.0, 1.0); 6]
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        const ALPHA: [f64; 4] = [1.0, 1.2, 3.0, 3.2];
+        const A: [[f64; 6]; 4] = [
+            [10.0, 3.0, 17.0, 3.5, 1.7, 8.0],
+            [0.05, 10.0, 17.0, 0.1, 8.0, 14.0],
+            [3.0, 3.5, 1.7, 10.0, 17.0, 8.0],
+            [17.0, 8.0, 0.05, 10.0, 0.1, 14.0],
+        ];
+        const P: [[f64; 6]; 4] = [
+            [0.1312, 0.1696, 0.5569, 0.0124, 0.8283, 0.5886],
+            [0.2329, 0.4135, 0.8307, 0.3736, 0.1004, 0.9991],
+            [0.2348, 0.1451, 0.3522, 0.2883, 0.3047, 0.6650],
+            [0.4047, 0.8828, 0.8732, 0.5743, 0.1091, 0.0381],
+        ];
+        -(0..4)
+            .map(|i| {
+                let inner = (0..6)
+                    .map(|j| A[i][j] * (x[j] - P[i][j]).powi(2))
+                    .sum::<f64>();
+                ALPHA[i] * (-inner).exp()
+            })
+            .sum::<f64>()
+    }
+
+    fn optimal_value(&self) -> Option<f64> {
+        Some(-3.32237)
+    }
+}
+
+/// Wrapper that adds Gaussian noise to the values of an objective.
+#[derive(Debug, Clone)]
+pub struct Noisy<O> {
+    objective: O,
+    noise: Normal<f64>,
+    rng: StdRng,
+}
+
+impl<O: Objective> Noisy<O> {
+    /// Makes a new [`Noisy`] instance.
+    ///
+    /// `stddev` is the standard deviation of the noise (negative values are regarded as zero).
+    pub fn new(objective: O, stddev: f64, seed: u64) -> Self {
+        Self {
+            objective,
+            noise: Normal::new(0.0, stddev.max(0.0)).expect("unreachable"),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<O: Objective> Objective for Noisy<O> {
+    fn name(&self) -> String {
+        format!("noisy-{}", self.objective.name())
+    }
+
+    fn bounds(&self) -> Vec<(f64, f64)> {
+        self.objective.bounds()
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        self.objective.evaluate(x) + self.noise.sample(&mut self.rng)
+    }
+
+    fn optimal_value(&self) -> Option<f64> {
+        self.objective.optimal_value()
+    }
+}
+
+/// Result of [`run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    /// Name of the objective.
+    pub name: String,
+
+    /// Best value found so far at each trial.
+    pub best_values: Vec<f64>,
+
+    /// Global minimum value of the objective (if known).
+    pub optimal_value: Option<f64>,
+}
+
+impl BenchmarkResult {
+    /// Returns the best value found during the run.
+    pub fn best_value(&self) -> f64 {
+        self.best_values.last().copied().unwrap_or(f64::INFINITY)
+    }
+
+    /// Returns the difference between the best value and the global minimum value.
+    pub fn regret(&self) -> Option<f64> {
+        self.optimal_value.map(|v| self.best_value() - v)
+    }
+}
+
+/// Makes a [`Study`] which has a [`parzen_estimator`] optimizer for each dimension of the objective.
+pub fn default_study<O: Objective + ?Sized>(objective: &O, seed: u64) -> Result<Study, RangeError> {
+    let optimizers = objective
+        .bounds()
+        .into_iter()
+        .map(|(low, high)| Ok(TpeOptimizer::new(parzen_estimator(), range(low, high)?)))
+        .collect::<Result<_, RangeError>>()?;
+    Ok(Study::with_seed(optimizers, seed))
+}
+
+/// Evaluates the objective at the parameters asked by the study `trials` times.
+///
+/// NaN values are regarded as failed evaluations and not told to the study.
+pub fn run<O, T>(
+    objective: &mut O,
+    study: &mut Study<T>,
+    trials: usize,
+) -> Result<BenchmarkResult, T::Error>
+where
+    O: Objective + ?Sized,
+    T: BuildDensityEstimator,
+{
+    let mut best_values = Vec::with_capacity(trials);
+    let mut best = f64::INFINITY;
+    for _ in 0..trials {
+        let params = study.ask()?;
+        let value = objective.evaluate(&params);
+        if !value.is_nan() {
+            study.tell(params, value).expect("unreachable");
+            best = best.min(value);
+        }
+        best_values.push(best);
+    }
+    Ok(BenchmarkResult {
+        name: objective.name(),
+        best_values,
+        optimal_value: objective.optimal_value(),
+    })
+}
+
+/// Evaluates the objective at the parameters asked by independent samplers (one for each dimension) `trials` times.
+///
+/// This is useful to compare strategies other than [`Study`] (e.g., [`RandomSampler`](crate::sampler::RandomSampler))
+/// on the same objectives.
+/// NaN values are regarded as failed evaluations and not told to the samplers.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::benchmarks::{self, Objective, Sphere};
+/// use tpe::sampler::RandomSampler;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut objective = Sphere::new(2);
+/// let mut samplers = objective
+///     .bounds()
+///     .into_iter()
+///     .map(|(low, high)| Ok(RandomSampler::new(tpe::range(low, high)?)))
+///     .collect::<anyhow::Result<Vec<_>>>()?;
+/// let mut rng = rand::thread_rng();
+/// let result = benchmarks::run_samplers(&mut objective, &mut samplers, &mut rng, 100)?;
+/// assert_eq!(result.best_values.len(), 100);
+/// # Ok(())
+/// # }
+/// ```
+pub fn run_samplers<O, S>(
+    objective: &mut O,
+    samplers: &mut [S],
+    rng: &mut dyn RngCore,
+    trials: usize,
+) -> Result<BenchmarkResult, S::Error>
+where
+    O: Objective + ?Sized,
+    S: Sampler,
+{
+    let mut best_values = Vec::with_capacity(trials);
+    let mut best = f64::INFINITY;
+    for _ in 0..trials {
+        let params = samplers
+            .iter_mut()
+            .map(|s| s.ask(rng))
+            .collect::<Result<Vec<_>, _>>()?;
+        let value = objective.evaluate(&params);
+        if !value.is_nan() {
+            for (s, &p) in samplers.iter_mut().zip(&params) {
+                s.tell(p, value).expect("unreachable");
+            }
+            best = best.min(value);
+        }
+        best_values.push(best);
+    }
+    Ok(BenchmarkResult {
+        name: objective.name(),
+        best_values,
+        optimal_value: objective.optimal_value(),
+    })
+}
+
+/// Evaluates the objective at the points of a grid `trials` times (a baseline for [`run`]).
+///
+/// Each dimension of the grid has `points_per_dim` evenly spaced points (see [`GridSampler::evenly_spaced`]).
+/// If `trials` exceeds the number of the grid points, the walk starts over.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::benchmarks::{self, Sphere};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let result = benchmarks::run_grid(&mut Sphere::new(2), 5, 25)?;
+/// assert_eq!(result.best_value(), 0.0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn run_grid<O>(
+    objective: &mut O,
+    points_per_dim: usize,
+    trials: usize,
+) -> Result<BenchmarkResult, GridError>
+where
+    O: Objective + ?Sized,
+{
+    let axes = objective
+        .bounds()
+        .into_iter()
+        .map(|(low, high)| GridSampler::evenly_spaced(Range::new(low, high)?, points_per_dim))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut axes = GridSampler::product(axes);
+    let mut rng = StdRng::seed_from_u64(0); // unused by the grid samplers
+    run_samplers(objective, &mut axes, &mut rng, trials).map_err(|e| match e {})
+}
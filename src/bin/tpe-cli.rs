@@ -0,0 +1,176 @@
+//! Optimizes the parameters of an external command.
+//!
+//! ```console
+//! $ tpe-cli space.toml --trials 50 --output results.csv -- ./bench --lr {lr} --act {act}
+//! ```
+//!
+//! The search space file lists the parameters in order:
+//!
+//! ```toml
+//! [[param]]
+//! name = "lr"
+//! type = "log_float"
+//! low = 1e-5
+//! high = 1e-1
+//!
+//! [[param]]
+//! name = "act"
+//! type = "categorical"
+//! choices = ["relu", "gelu"]
+//! ```
+//!
+//! Each `{name}` in the command is replaced with the value of the parameter,
+//! and the last non-empty line of the command's stdout is parsed as the objective value (to be minimized).
+use std::error::Error;
+use std::process::Command;
+use tpe::search_space::{ParamSpec, ParamValue, SearchSpaceBuilder};
+
+const USAGE: &str = "Usage: tpe-cli SPACE_FILE [--trials N] [--output CSV_FILE] [--seed N] [--maximize] -- COMMAND [ARGS...]";
+
+#[derive(Debug, serde::Deserialize)]
+struct SpaceFile {
+    param: Vec<Param>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Param {
+    name: String,
+    #[serde(flatten)]
+    spec: ParamSpec,
+}
+
+#[derive(Debug)]
+struct Options {
+    space_file: String,
+    trials: usize,
+    output: String,
+    seed: Option<u64>,
+    maximize: bool,
+    command: Vec<String>,
+}
+
+impl Options {
+    fn parse() -> Result<Self, Box<dyn Error>> {
+        let mut args = std::env::args().skip(1);
+        let mut options = Options {
+            space_file: String::new(),
+            trials: 100,
+            output: "results.csv".to_owned(),
+            seed: None,
+            maximize: false,
+            command: Vec::new(),
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(USAGE);
+            match arg.as_str() {
+                "--trials" => options.trials = value()?.parse()?,
+                "--output" => options.output = value()?,
+                "--seed" => options.seed = Some(value()?.parse()?),
+                "--maximize" => options.maximize = true,
+                "--" => {
+                    options.command = args.collect();
+                    break;
+                }
+                _ if options.space_file.is_empty() && !arg.starts_with("--") => {
+                    options.space_file = arg
+                }
+                _ => return Err(USAGE.into()),
+            }
+        }
+        if options.space_file.is_empty() || options.command.is_empty() {
+            return Err(USAGE.into());
+        }
+        Ok(options)
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = Options::parse()?;
+    let space: SpaceFile = toml::from_str(&std::fs::read_to_string(&options.space_file)?)?;
+
+    let mut builder = SearchSpaceBuilder::new();
+    for p in &space.param {
+        builder.add_param(&p.name, &p.spec)?;
+    }
+    if let Some(seed) = options.seed {
+        builder.seed(seed);
+    }
+    let mut search_space = builder.build();
+
+    let mut output = csv::Writer::from_path(&options.output)?;
+    let mut header = vec!["trial".to_owned()];
+    header.extend(space.param.iter().map(|p| p.name.clone()));
+    header.push("value".to_owned());
+    output.write_record(&header)?;
+    output.flush()?;
+
+    for trial in 0..options.trials {
+        let params = search_space.ask()?;
+        let values = params
+            .iter()
+            .map(|(name, v)| {
+                let v = match v {
+                    ParamValue::Float(v) => v.to_string(),
+                    ParamValue::Int(v) => v.to_string(),
+                    ParamValue::Categorical(v) => v.clone(),
+                };
+                (name.to_owned(), v)
+            })
+            .collect::<Vec<_>>();
+        let args = options
+            .command
+            .iter()
+            .map(|arg| {
+                values.iter().fold(arg.clone(), |arg, (name, v)| {
+                    arg.replace(&format!("{{{}}}", name), v)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let value = match run(&args) {
+            Ok(value) => value,
+            Err(e) => {
+                eprintln!("[tpe-cli] trial {} failed: {}", trial, e);
+                continue;
+            }
+        };
+        let objective = if options.maximize { -value } else { value };
+        search_space.tell(params, objective)?;
+
+        let mut record = vec![trial.to_string()];
+        record.extend(values.into_iter().map(|(_, v)| v));
+        record.push(value.to_string());
+        output.write_record(&record)?;
+        output.flush()?;
+        eprintln!("[tpe-cli] trial {}: {}", trial, value);
+    }
+
+    if let Some(best) = search_space.study().best_trial() {
+        let best = if options.maximize {
+            -best.value
+        } else {
+            best.value
+        };
+        eprintln!("[tpe-cli] best value: {}", best);
+    }
+    Ok(())
+}
+
+// Runs the command and returns the objective value printed at the last line of its stdout.
+fn run(args: &[String]) -> Result<f64, Box<dyn Error>> {
+    let output = Command::new(&args[0]).args(&args[1..]).output()?;
+    if !output.status.success() {
+        return Err(format!("the command exited with {}", output.status).into());
+    }
+    let stdout = String::from_utf8(output.stdout)?;
+    let line = stdout
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .ok_or("the command printed nothing")?;
+    let value = line.trim().parse::<f64>()?;
+    if value.is_nan() {
+        return Err("the objective value is NaN".into());
+    }
+    Ok(value)
+}
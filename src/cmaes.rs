@@ -0,0 +1,367 @@
+//! Hybrid sampler that delegates continuous parameters to CMA-ES.
+//!
+//! [`HybridSampler`] samples all parameters by the TPE optimizers of a [`Study`] during the startup trials.
+//! After that, the parameters designated as continuous are jointly sampled by
+//! CMA-ES (Covariance Matrix Adaptation Evolution Strategy) started from the best trial,
+//! which often converges faster than TPE on smooth continuous subspaces.
+//! The other (e.g., categorical or conditional) parameters are still sampled by TPE.
+//!
+//! CMA-ES works in the unit hypercube obtained by normalizing the warped ranges (see [`Range::warped`](crate::range::Range::warped)).
+//!
+//! CMA-ES is implemented in this module rather than delegated to the [`cmaes`](https://docs.rs/cmaes) crate,
+//! because that crate evaluates a given objective function by itself, while [`HybridSampler`] has to follow
+//! the ask-and-tell protocol of [`Study`], in which the continuous parameters are evaluated by the caller
+//! together with the ones sampled by TPE.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::cmaes::HybridSampler;
+//! use tpe::study::Study;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let study = Study::with_seed(
+//!     vec![
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!         tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(3)?),
+//!     ],
+//!     0,
+//! );
+//! let mut sampler = HybridSampler::with_seed(study, vec![0, 1], 0)?;
+//! for _ in 0..200 {
+//!     let params = sampler.ask()?;
+//!     let value = (params[0] - 1.0).powi(2) + (params[1] + 2.0).powi(2) + params[2];
+//!     sampler.tell(params, value)?;
+//! }
+//! assert!(sampler.study().best_trial().map_or(false, |t| t.value < 0.1));
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::study::Study;
+use crate::TellError;
+use nalgebra::{DMatrix, DVector, SymmetricEigen};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::StandardNormal;
+
+const INITIAL_SIGMA: f64 = 0.2;
+const MAX_RESAMPLINGS: usize = 100;
+
+/// Sampler that uses CMA-ES for continuous parameters and TPE for the others.
+#[derive(Debug)]
+pub struct HybridSampler<T = DefaultEstimatorBuilder> {
+    study: Study<T>,
+    continuous: Vec<usize>,
+    rng: StdRng,
+    startup_trials: usize,
+    cma: Option<CmaEs>,
+}
+
+impl<T: BuildDensityEstimator> HybridSampler<T> {
+    /// Makes a new [`HybridSampler`] instance seeded from the system entropy.
+    ///
+    /// `continuous` are the indices of the parameters of `study` to be sampled by CMA-ES.
+    /// They should not be conditional (see [`Study::set_condition`]).
+    pub fn new(study: Study<T>, continuous: Vec<usize>) -> Result<Self, HybridError> {
+        Self::with_rng(study, continuous, StdRng::from_entropy())
+    }
+
+    /// Makes a new [`HybridSampler`] instance with a deterministic seed.
+    pub fn with_seed(
+        study: Study<T>,
+        continuous: Vec<usize>,
+        seed: u64,
+    ) -> Result<Self, HybridError> {
+        Self::with_rng(study, continuous, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(study: Study<T>, continuous: Vec<usize>, rng: StdRng) -> Result<Self, HybridError> {
+        if let Some(&index) = continuous.iter().find(|&&i| i >= study.optimizers().len()) {
+            return Err(HybridError::UnknownParam { index });
+        }
+        Ok(Self {
+            startup_trials: (2 * continuous.len()).max(10),
+            study,
+            continuous,
+            rng,
+            cma: None,
+        })
+    }
+
+    /// Sets the number of the trials sampled only by TPE before CMA-ES starts.
+    ///
+    /// The default value is `max(10, 2 * continuous.len())`.
+    pub fn set_startup_trials(&mut self, n: usize) {
+        self.startup_trials = n;
+    }
+
+    /// Returns the next parameter values to be evaluated.
+    pub fn ask(&mut self) -> Result<Vec<f64>, T::Error> {
+        if self.continuous.is_empty() || self.study.trials().len() < self.startup_trials {
+            return self.study.ask();
+        }
+
+        if self.cma.is_none() {
+            let mean = match self.study.best_trial() {
+                Some(t) => self
+                    .normalize(&t.params)
+                    .map(|u| u.unwrap_or(0.5))
+                    .collect(),
+                None => vec![0.5; self.continuous.len()],
+            };
+            self.cma = Some(CmaEs::new(DVector::from_vec(mean)));
+        }
+        let cma = self.cma.as_ref().expect("unreachable");
+        let u = cma.sample(&mut self.rng);
+        let mut values = vec![None; self.study.optimizers().len()];
+        for (&i, &u) in self.continuous.iter().zip(u.iter()) {
+            let range = self.study.optimizers()[i].param_range();
+            let warped = range.warped();
+            values[i] = Some(range.unwarp(warped.start() + u * warped.width()));
+        }
+
+        // Only the other parameters are sampled by TPE.
+        self.study.ask_partial(|i| values[i], &[])
+    }
+
+    /// Tells the evaluation result of the given parameter values.
+    ///
+    /// The result is told to both the TPE optimizers and CMA-ES (if it has started).
+    pub fn tell(&mut self, params: Vec<f64>, value: f64) -> Result<(), TellError> {
+        let u = self.normalize(&params).collect::<Option<Vec<_>>>();
+        self.study.tell(params, value)?;
+        if let (Some(cma), Some(u)) = (&mut self.cma, u) {
+            cma.tell(DVector::from_vec(u), value);
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying [`Study`].
+    pub fn study(&self) -> &Study<T> {
+        &self.study
+    }
+
+    /// Returns the underlying [`Study`], consuming this sampler.
+    pub fn into_study(self) -> Study<T> {
+        self.study
+    }
+
+    // Maps the continuous parameters into the unit hypercube (`None` for NaN or missing ones).
+    fn normalize<'a>(&'a self, params: &'a [f64]) -> impl 'a + Iterator<Item = Option<f64>> {
+        self.continuous.iter().map(move |&i| {
+            let p = params.get(i).copied().filter(|p| !p.is_nan())?;
+            let range = self.study.optimizers()[i].param_range();
+            let warped = range.warped();
+            Some(((range.warp(p) - warped.start()) / warped.width()).clamp(0.0, 1.0))
+        })
+    }
+}
+
+/// Possible errors during building [`HybridSampler`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum HybridError {
+    #[error("unknown parameter {index}")]
+    /// Unknown parameter.
+    UnknownParam {
+        /// Parameter index.
+        index: usize,
+    },
+}
+
+// (mu/mu_w, lambda)-CMA-ES with the default parameters in Hansen's tutorial (arXiv:1604.00772).
+#[derive(Debug)]
+struct CmaEs {
+    mean: DVector<f64>,
+    sigma: f64,
+    cov: DMatrix<f64>,
+    b: DMatrix<f64>,
+    d: DVector<f64>,
+    pc: DVector<f64>,
+    ps: DVector<f64>,
+    lambda: usize,
+    weights: Vec<f64>,
+    mu_eff: f64,
+    cs: f64,
+    ds: f64,
+    cc: f64,
+    c1: f64,
+    cmu: f64,
+    chi_n: f64,
+    generation: usize,
+    solutions: Vec<(DVector<f64>, f64)>,
+}
+
+impl CmaEs {
+    fn new(mean: DVector<f64>) -> Self {
+        let n = mean.len() as f64;
+        let lambda = 4 + (3.0 * n.ln()).floor() as usize;
+        let mu = lambda / 2;
+        let weights = (0..mu)
+            .map(|i| (mu as f64 + 0.5).ln() - (i as f64 + 1.0).ln())
+            .collect::<Vec<_>>();
+        let total = weights.iter().sum::<f64>();
+        let weights = weights.into_iter().map(|w| w / total).collect::<Vec<_>>();
+        let mu_eff = 1.0 / weights.iter().map(|w| w * w).sum::<f64>();
+
+        let cs = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+        let ds = 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + cs;
+        let cc = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+        let c1 = 2.0 / ((n + 1.3).powi(2) + mu_eff);
+        let cmu =
+            (1.0 - c1).min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff));
+        let chi_n = n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n));
+
+        let dim = mean.len();
+        Self {
+            mean,
+            sigma: INITIAL_SIGMA,
+            cov: DMatrix::identity(dim, dim),
+            b: DMatrix::identity(dim, dim),
+            d: DVector::from_element(dim, 1.0),
+            pc: DVector::zeros(dim),
+            ps: DVector::zeros(dim),
+            lambda,
+            weights,
+            mu_eff,
+            cs,
+            ds,
+            cc,
+            c1,
+            cmu,
+            chi_n,
+            generation: 0,
+            solutions: Vec::with_capacity(lambda),
+        }
+    }
+
+    // Samples a point in the unit hypercube (points outside of it are resampled and finally clamped).
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        let mut x = self.mean.clone();
+        for _ in 0..MAX_RESAMPLINGS {
+            let z = DVector::from_fn(self.mean.len(), |_, _| rng.sample(StandardNormal));
+            x = &self.mean + &self.b * z.component_mul(&self.d) * self.sigma;
+            if x.iter().all(|&v| (0.0..=1.0).contains(&v)) {
+                break;
+            }
+        }
+        x.iter().map(|v| v.clamp(0.0, 1.0)).collect()
+    }
+
+    fn tell(&mut self, x: DVector<f64>, value: f64) {
+        self.solutions.push((x, value));
+        if self.solutions.len() >= self.lambda {
+            self.update();
+        }
+    }
+
+    fn update(&mut self) {
+        let mut solutions = std::mem::take(&mut self.solutions);
+        solutions.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let ys = solutions
+            .iter()
+            .take(self.weights.len())
+            .map(|(x, _)| (x - &self.mean) / self.sigma)
+            .collect::<Vec<_>>();
+        let yw = ys
+            .iter()
+            .zip(&self.weights)
+            .fold(DVector::zeros(self.mean.len()), |acc, (y, &w)| acc + y * w);
+        self.mean += &yw * self.sigma;
+
+        let n = self.mean.len() as f64;
+        let inv_sqrt_c =
+            &self.b * DMatrix::from_diagonal(&self.d.map(|d| 1.0 / d)) * self.b.transpose();
+        self.ps = &self.ps * (1.0 - self.cs)
+            + inv_sqrt_c * &yw * (self.cs * (2.0 - self.cs) * self.mu_eff).sqrt();
+        self.generation += 1;
+        let ps_norm = self.ps.norm();
+        let threshold = (1.4 + 2.0 / (n + 1.0)) * self.chi_n;
+        let hs =
+            ps_norm / (1.0 - (1.0 - self.cs).powi(2 * self.generation as i32)).sqrt() < threshold;
+        let hs = if hs { 1.0 } else { 0.0 };
+        self.pc = &self.pc * (1.0 - self.cc)
+            + &yw * (hs * (self.cc * (2.0 - self.cc) * self.mu_eff).sqrt());
+
+        let rank_mu = ys.iter().zip(&self.weights).fold(
+            DMatrix::zeros(self.d.len(), self.d.len()),
+            |acc, (y, &w)| acc + y * y.transpose() * w,
+        );
+        let rank_one = &self.pc * self.pc.transpose();
+        self.cov = &self.cov * (1.0 - self.c1 - self.cmu)
+            + (rank_one + &self.cov * ((1.0 - hs) * self.cc * (2.0 - self.cc))) * self.c1
+            + rank_mu * self.cmu;
+        self.cov = (&self.cov + self.cov.transpose()) * 0.5;
+        self.sigma *= ((self.cs / self.ds) * (ps_norm / self.chi_n - 1.0)).exp();
+
+        let eigen = SymmetricEigen::new(self.cov.clone());
+        self.d = eigen.eigenvalues.map(|e| e.max(1e-20).sqrt());
+        self.b = eigen.eigenvectors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs CMA-ES on `f` and returns the best value found.
+    fn minimize<F>(cma: &mut CmaEs, evaluations: usize, mut f: F) -> f64
+    where
+        F: FnMut(&[f64]) -> f64,
+    {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut best = f64::INFINITY;
+        for _ in 0..evaluations {
+            let x = cma.sample(&mut rng);
+            let value = f(&x);
+            best = best.min(value);
+            cma.tell(DVector::from_vec(x), value);
+        }
+        best
+    }
+
+    #[test]
+    fn converges_on_sphere() {
+        let mut cma = CmaEs::new(DVector::from_element(3, 0.8));
+        let best = minimize(&mut cma, 1000, |x| {
+            x.iter().map(|v| (v - 0.3).powi(2)).sum::<f64>()
+        });
+        assert!(best < 1e-10, "{best}");
+        assert!(cma.mean.iter().all(|m| (m - 0.3).abs() < 1e-4));
+    }
+
+    #[test]
+    fn converges_on_ellipsoid() {
+        let n = 4;
+        let mut cma = CmaEs::new(DVector::from_element(n, 0.5));
+        let best = minimize(&mut cma, 4000, |x| {
+            x.iter()
+                .enumerate()
+                .map(|(i, v)| 1e4f64.powf(i as f64 / (n - 1) as f64) * (v - 0.4).powi(2))
+                .sum::<f64>()
+        });
+        assert!(best < 1e-8, "{best}");
+
+        // The covariance matrix adapts to the ill-conditioning (the axis ratio is 100).
+        let ratio = cma.d.max() / cma.d.min();
+        assert!(ratio > 10.0, "{ratio}");
+    }
+
+    #[test]
+    fn covariance_stays_symmetric_positive_definite() {
+        let mut cma = CmaEs::new(DVector::from_element(5, 0.5));
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            for _ in 0..cma.lambda {
+                let x = cma.sample(&mut rng);
+                let value = x[0] * 10.0 + x.iter().map(|v| (v - 0.2).powi(2)).sum::<f64>();
+                cma.tell(DVector::from_vec(x), value);
+            }
+            assert_eq!(cma.cov, cma.cov.transpose());
+            assert!(cma.cov.clone().cholesky().is_some());
+            assert!(cma.d.iter().all(|&d| d > 0.0));
+            assert!(cma.sigma.is_finite() && cma.sigma > 0.0);
+        }
+    }
+}
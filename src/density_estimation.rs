@@ -3,16 +3,98 @@ use crate::Range;
 use rand::distributions::Distribution;
 use rand::Rng;
 
-pub use self::histogram::{HistogramEstimator, HistogramEstimatorBuilder};
-pub use self::parzen::{ParzenEstimator, ParzenEstimatorBuilder};
+pub use self::bandwidth::{
+    BandwidthClip, BandwidthStrategy, DefaultBandwidth, MagicClipBandwidth, ScottBandwidth,
+    SilvermanBandwidth,
+};
+pub use self::beta::{BetaKernelEstimator, BetaKernelEstimatorBuilder};
+pub use self::gmm::{GmmEstimator, GmmEstimatorBuilder};
+pub use self::histogram::{HistogramEstimator, HistogramEstimatorBuilder, OrdinalSmoothing};
+pub use self::kernel::{Kernel, ParzenPrior};
+pub use self::parzen::{
+    DiscreteParzenEstimator, DiscreteParzenEstimatorBuilder, ParzenComponent, ParzenEstimator,
+    ParzenEstimatorBuilder,
+};
+pub use self::product::{ProductEstimator, ProductEstimatorBuilder};
+pub use self::thompson::{ThompsonEstimator, ThompsonEstimatorBuilder};
+pub use self::uniform::{UniformEstimator, UniformEstimatorBuilder};
 
+mod bandwidth;
+mod beta;
+mod gmm;
 mod histogram;
+mod kernel;
 mod parzen;
+mod product;
+mod thompson;
+mod uniform;
 
 /// This trait allows estimating the probability density of a sample and sampling from the function.
 pub trait DensityEstimator: Distribution<f64> {
     /// Estimates the log probability density of a sample.
     fn log_pdf(&self, x: f64) -> f64;
+
+    /// Estimates the log probability densities of multiple samples and stores them in `out`.
+    ///
+    /// The default implementation calls [`DensityEstimator::log_pdf`] for each sample.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `xs` and `out` have different lengths.
+    fn log_pdf_batch(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len());
+        for (&x, o) in xs.iter().zip(out.iter_mut()) {
+            *o = self.log_pdf(x);
+        }
+    }
+
+    /// Draws `n` samples and appends them to `out`.
+    ///
+    /// The default implementation calls [`Distribution::sample`] `n` times.
+    fn sample_n<R: Rng + ?Sized>(&self, rng: &mut R, n: usize, out: &mut Vec<f64>) {
+        out.extend((0..n).map(|_| self.sample(rng)));
+    }
+
+    /// Returns the number of draws rejected so far while sampling (e.g., by rejection sampling).
+    ///
+    /// The default implementation returns `0`.
+    fn rejected_draws(&self) -> usize {
+        0
+    }
+}
+
+/// This trait allows estimating the joint probability density of a multi-dimensional sample
+/// and sampling from the function.
+pub trait DensityEstimatorNd: Distribution<Vec<f64>> {
+    /// Returns the number of the dimensions.
+    fn dim(&self) -> usize;
+
+    /// Estimates the log probability density of a sample.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the length of `x` differs from [`DensityEstimatorNd::dim`].
+    fn log_pdf(&self, x: &[f64]) -> f64;
+}
+
+/// This trait allows building multivariate probability density estimators.
+pub trait BuildDensityEstimatorNd {
+    /// Density estimator to be built.
+    type Estimator: DensityEstimatorNd;
+
+    /// Possible error during building.
+    type Error: std::error::Error;
+
+    /// Builds a probability density estimator from the given samples.
+    ///
+    /// Each sample must have the same length as `ranges`.
+    fn build_density_estimator_nd<'a, I>(
+        &self,
+        xs: I,
+        ranges: &[Range],
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = &'a [f64]> + Clone;
 }
 
 /// This trait allows building probability density estimators.
@@ -31,6 +113,78 @@ pub trait BuildDensityEstimator {
     ) -> Result<Self::Estimator, Self::Error>
     where
         I: Iterator<Item = f64> + Clone;
+
+    /// Builds a probability density estimator from the given samples and their (non-negative finite) weights.
+    ///
+    /// A sample with the weight `1.0` has the same influence as a sample given to
+    /// [`BuildDensityEstimator::build_density_estimator`].
+    /// The builders of this crate fail with [`EstimatorBuildError::InvalidWeight`] if a weight is invalid.
+    ///
+    /// The default implementation ignores the weights.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, DensityEstimator as _, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let samples = [(0.1, 10.0), (0.9, 0.1)];
+    /// let estimator = ParzenEstimatorBuilder::new()
+    ///     .build_density_estimator_weighted(samples.iter().copied(), tpe::range(0.0, 1.0)?)?;
+    /// assert!(estimator.log_pdf(0.1) > estimator.log_pdf(0.9));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.build_density_estimator(xs.map(|(x, _)| x), range)
+    }
+}
+
+/// This trait allows adding an observation to a built estimator without keeping the original observations.
+///
+/// The updated estimator is equivalent to the one built from all the observations (up to rounding errors).
+/// Whether this is cheaper than rebuilding depends on the implementation:
+/// [`HistogramEstimatorBuilder`] rescales the probabilities in place (`O(cardinality)`),
+/// while [`ParzenEstimatorBuilder`] inserts a kernel into the sorted kernels but recomputes the bandwidths
+/// and the weights of all the kernels (`O(n)`), because the bandwidths may depend on all the kernels
+/// (e.g., the lower bound of [`BandwidthClip`] depends on their number),
+/// so it only saves collecting and sorting the observations.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::density_estimation::{
+///     BuildDensityEstimator as _, DensityEstimator as _, ParzenEstimatorBuilder, UpdateDensityEstimator as _,
+/// };
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let range = tpe::range(0.0, 1.0)?;
+/// let builder = ParzenEstimatorBuilder::new();
+/// let mut estimator = builder.build_density_estimator([0.1, 0.2].iter().copied(), range)?;
+/// builder.update_density_estimator(&mut estimator, 0.3, 1.0)?;
+///
+/// let rebuilt = builder.build_density_estimator([0.1, 0.2, 0.3].iter().copied(), range)?;
+/// assert!((estimator.log_pdf(0.25) - rebuilt.log_pdf(0.25)).abs() < 1e-9);
+/// # Ok(())
+/// # }
+/// ```
+pub trait UpdateDensityEstimator: BuildDensityEstimator {
+    /// Adds the observation `x` with the (non-negative) `weight` to `estimator`.
+    ///
+    /// `estimator` must have been built by this builder.
+    fn update_density_estimator(
+        &self,
+        estimator: &mut Self::Estimator,
+        x: f64,
+        weight: f64,
+    ) -> Result<(), Self::Error>;
 }
 
 /// Default estimator.
@@ -39,6 +193,11 @@ pub trait BuildDensityEstimator {
 pub enum DefaultEstimator {
     Parzen(ParzenEstimator),
     Histogram(HistogramEstimator),
+    DiscreteParzen(DiscreteParzenEstimator),
+    Gmm(GmmEstimator),
+    Beta(BetaKernelEstimator),
+    Uniform(UniformEstimator),
+    Thompson(ThompsonEstimator),
 }
 
 impl DensityEstimator for DefaultEstimator {
@@ -46,6 +205,47 @@ impl DensityEstimator for DefaultEstimator {
         match self {
             Self::Parzen(t) => t.log_pdf(x),
             Self::Histogram(t) => t.log_pdf(x),
+            Self::DiscreteParzen(t) => t.log_pdf(x),
+            Self::Gmm(t) => t.log_pdf(x),
+            Self::Beta(t) => t.log_pdf(x),
+            Self::Uniform(t) => t.log_pdf(x),
+            Self::Thompson(t) => t.log_pdf(x),
+        }
+    }
+
+    fn log_pdf_batch(&self, xs: &[f64], out: &mut [f64]) {
+        match self {
+            Self::Parzen(t) => t.log_pdf_batch(xs, out),
+            Self::Histogram(t) => t.log_pdf_batch(xs, out),
+            Self::DiscreteParzen(t) => t.log_pdf_batch(xs, out),
+            Self::Gmm(t) => t.log_pdf_batch(xs, out),
+            Self::Beta(t) => t.log_pdf_batch(xs, out),
+            Self::Uniform(t) => t.log_pdf_batch(xs, out),
+            Self::Thompson(t) => t.log_pdf_batch(xs, out),
+        }
+    }
+
+    fn sample_n<R: Rng + ?Sized>(&self, rng: &mut R, n: usize, out: &mut Vec<f64>) {
+        match self {
+            Self::Parzen(t) => t.sample_n(rng, n, out),
+            Self::Histogram(t) => t.sample_n(rng, n, out),
+            Self::DiscreteParzen(t) => t.sample_n(rng, n, out),
+            Self::Gmm(t) => t.sample_n(rng, n, out),
+            Self::Beta(t) => t.sample_n(rng, n, out),
+            Self::Uniform(t) => t.sample_n(rng, n, out),
+            Self::Thompson(t) => t.sample_n(rng, n, out),
+        }
+    }
+
+    fn rejected_draws(&self) -> usize {
+        match self {
+            Self::Parzen(t) => t.rejected_draws(),
+            Self::Histogram(t) => t.rejected_draws(),
+            Self::DiscreteParzen(t) => t.rejected_draws(),
+            Self::Gmm(t) => t.rejected_draws(),
+            Self::Beta(t) => t.rejected_draws(),
+            Self::Uniform(t) => t.rejected_draws(),
+            Self::Thompson(t) => t.rejected_draws(),
         }
     }
 }
@@ -55,21 +255,62 @@ impl Distribution<f64> for DefaultEstimator {
         match self {
             Self::Parzen(t) => t.sample(rng),
             Self::Histogram(t) => t.sample(rng),
+            Self::DiscreteParzen(t) => t.sample(rng),
+            Self::Gmm(t) => t.sample(rng),
+            Self::Beta(t) => t.sample(rng),
+            Self::Uniform(t) => t.sample(rng),
+            Self::Thompson(t) => t.sample(rng),
         }
     }
 }
 
 /// Builder of [`DefaultEstimator`].
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(missing_docs)]
 pub enum DefaultEstimatorBuilder {
     Parzen(ParzenEstimatorBuilder),
     Histogram(HistogramEstimatorBuilder),
+    DiscreteParzen(DiscreteParzenEstimatorBuilder),
+    Gmm(GmmEstimatorBuilder),
+    Beta(BetaKernelEstimatorBuilder),
+    Uniform(UniformEstimatorBuilder),
+    Thompson(ThompsonEstimatorBuilder),
+
+    /// Uses the uniform distribution if the number of the samples is less than the threshold.
+    UniformFallback(Box<DefaultEstimatorBuilder>, usize),
+}
+
+impl DefaultEstimatorBuilder {
+    /// Makes the builder build [`UniformEstimator`] instead
+    /// if the number of the finite samples is less than `min_samples`.
+    ///
+    /// This avoids the bias toward the prior kernel (e.g., the midpoint of the range)
+    /// while there are only a few observations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, DefaultEstimator};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let builder = tpe::parzen_estimator().uniform_fallback(2);
+    /// let range = tpe::range(0.0, 1.0)?;
+    /// let estimator = builder.build_density_estimator([0.3].iter().copied(), range)?;
+    /// assert!(matches!(estimator, DefaultEstimator::Uniform(_)));
+    ///
+    /// let estimator = builder.build_density_estimator([0.3, 0.4].iter().copied(), range)?;
+    /// assert!(matches!(estimator, DefaultEstimator::Parzen(_)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn uniform_fallback(self, min_samples: usize) -> Self {
+        Self::UniformFallback(Box::new(self), min_samples)
+    }
 }
 
 impl BuildDensityEstimator for DefaultEstimatorBuilder {
     type Estimator = DefaultEstimator;
-    type Error = std::convert::Infallible;
+    type Error = EstimatorBuildError;
 
     fn build_density_estimator<I>(
         &self,
@@ -86,6 +327,120 @@ impl BuildDensityEstimator for DefaultEstimatorBuilder {
             Self::Histogram(t) => t
                 .build_density_estimator(params, range)
                 .map(DefaultEstimator::Histogram),
+            Self::DiscreteParzen(t) => t
+                .build_density_estimator(params, range)
+                .map(DefaultEstimator::DiscreteParzen),
+            Self::Gmm(t) => t
+                .build_density_estimator(params, range)
+                .map(DefaultEstimator::Gmm),
+            Self::Beta(t) => t
+                .build_density_estimator(params, range)
+                .map(DefaultEstimator::Beta),
+            Self::Uniform(t) => t
+                .build_density_estimator(params, range)
+                .map(DefaultEstimator::Uniform)
+                .map_err(Into::into),
+            Self::Thompson(t) => t
+                .build_density_estimator(params, range)
+                .map(DefaultEstimator::Thompson),
+            Self::UniformFallback(t, min_samples) => {
+                if params.clone().filter(|x| x.is_finite()).count() < *min_samples {
+                    Ok(DefaultEstimator::Uniform(UniformEstimator::new(range)))
+                } else {
+                    t.build_density_estimator(params, range)
+                }
+            }
+        }
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        params: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        match self {
+            Self::Parzen(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Parzen),
+            Self::Histogram(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Histogram),
+            Self::DiscreteParzen(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::DiscreteParzen),
+            Self::Gmm(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Gmm),
+            Self::Beta(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Beta),
+            Self::Uniform(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Uniform)
+                .map_err(Into::into),
+            Self::Thompson(t) => t
+                .build_density_estimator_weighted(params, range)
+                .map(DefaultEstimator::Thompson),
+            Self::UniformFallback(t, min_samples) => {
+                if params.clone().filter(|(x, _)| x.is_finite()).count() < *min_samples {
+                    Ok(DefaultEstimator::Uniform(UniformEstimator::new(range)))
+                } else {
+                    t.build_density_estimator_weighted(params, range)
+                }
+            }
         }
     }
 }
+
+/// Possible errors during building density estimators.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum EstimatorBuildError {
+    #[error("the prior weight must be a positive finite number")]
+    /// The prior weight must be a positive finite number.
+    InvalidPriorWeight,
+
+    #[error("the sample weights must be non-negative finite numbers and must not be all zero")]
+    /// The sample weights must be non-negative finite numbers and must not be all zero.
+    InvalidWeight,
+
+    #[error("the factors of the bandwidth clip must be positive finite numbers")]
+    /// The factors of [`BandwidthClip`] must be positive finite numbers.
+    InvalidBandwidthClip,
+
+    #[error("the degrees of freedom of the kernel must be a positive finite number")]
+    /// The degrees of freedom of [`Kernel::StudentT`] must be a positive finite number.
+    InvalidDegreesOfFreedom,
+
+    #[error("the standard deviation of the prior must be a positive finite number")]
+    /// The standard deviation of [`ParzenPrior::Normal`] must be a positive finite number.
+    InvalidPriorStddev,
+
+    #[error("the standard deviation of the ordinal smoothing must be a positive finite number")]
+    /// The standard deviation of [`OrdinalSmoothing::Gaussian`] must be a positive finite number.
+    InvalidSmoothingStddev,
+
+    #[error("the bandwidth must be a positive finite number")]
+    /// The bandwidth of [`BetaKernelEstimatorBuilder`] must be a positive finite number.
+    InvalidBandwidth,
+
+    #[error("the number of the mixture components must be positive")]
+    /// The number of the components of [`GmmEstimatorBuilder`] must be positive.
+    InvalidComponents,
+}
+
+impl From<std::convert::Infallible> for EstimatorBuildError {
+    fn from(e: std::convert::Infallible) -> Self {
+        match e {}
+    }
+}
+
+// Checks that a sample weight is a non-negative finite number.
+pub(crate) fn validate_weight(weight: f64) -> Result<(), EstimatorBuildError> {
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(EstimatorBuildError::InvalidWeight);
+    }
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use crate::Range;
+
+/// This trait allows deciding the standard deviations of the kernels of [`ParzenEstimator`](super::ParzenEstimator).
+pub trait BandwidthStrategy: std::fmt::Debug + Send + Sync {
+    /// Returns the standard deviation of each kernel.
+    ///
+    /// `means` are the (sorted) centers of the kernels.
+    fn bandwidths(&self, means: &[f64], range: Range) -> Vec<f64>;
+
+    /// Same as [`BandwidthStrategy::bandwidths`] except that the results are clipped by `clip`
+    /// (see [`ParzenEstimatorBuilder::bandwidth_clip`](super::ParzenEstimatorBuilder::bandwidth_clip)).
+    ///
+    /// The default implementation ignores `clip`.
+    fn clipped_bandwidths(&self, means: &[f64], range: Range, clip: BandwidthClip) -> Vec<f64> {
+        let _ = clip;
+        self.bandwidths(means, range)
+    }
+}
+
+/// Bounds of the standard deviations decided by the bundled [`BandwidthStrategy`] implementations.
+///
+/// The standard deviations are clipped to `[width / min(min_divisor, n + 1), width * max_ratio]`,
+/// where `width` is the width of the range and `n` is the number of the kernels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandwidthClip {
+    /// Ratio of the maximum standard deviation to the width of the range.
+    ///
+    /// The default value is `1.0`.
+    pub max_ratio: f64,
+
+    /// Upper limit of the divisor of the minimum standard deviation.
+    ///
+    /// Larger values let the bandwidths shrink further as the observations increase.
+    /// The default value is `100.0`.
+    pub min_divisor: f64,
+}
+
+impl Default for BandwidthClip {
+    fn default() -> Self {
+        Self {
+            max_ratio: 1.0,
+            min_divisor: 100.0,
+        }
+    }
+}
+
+/// The default strategy.
+///
+/// The standard deviation of a kernel is the larger distance to its neighbors
+/// (the kernels at both ends only consider their inner neighbors),
+/// clipped to `[width / min(100, n + 1), width]` (see [`BandwidthClip`]).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultBandwidth;
+
+impl BandwidthStrategy for DefaultBandwidth {
+    fn bandwidths(&self, means: &[f64], range: Range) -> Vec<f64> {
+        self.clipped_bandwidths(means, range, BandwidthClip::default())
+    }
+
+    fn clipped_bandwidths(&self, means: &[f64], range: Range, clip: BandwidthClip) -> Vec<f64> {
+        let n = means.len();
+        let mut stddevs = neighbor_distances(means, range);
+        if n >= 2 {
+            stddevs[0] = means[1] - means[0];
+            stddevs[n - 1] = means[n - 1] - means[n - 2];
+        }
+        clip_stddevs(stddevs, range, clip)
+    }
+}
+
+/// Optuna's strategy (a.k.a. magic clip).
+///
+/// Same as [`DefaultBandwidth`] except that the kernels at both ends also consider
+/// the distances to the boundaries of the range.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MagicClipBandwidth;
+
+impl BandwidthStrategy for MagicClipBandwidth {
+    fn bandwidths(&self, means: &[f64], range: Range) -> Vec<f64> {
+        self.clipped_bandwidths(means, range, BandwidthClip::default())
+    }
+
+    fn clipped_bandwidths(&self, means: &[f64], range: Range, clip: BandwidthClip) -> Vec<f64> {
+        clip_stddevs(neighbor_distances(means, range), range, clip)
+    }
+}
+
+/// Scott's rule of thumb (`1.06 * stddev * n^(-1/5)`) shared by all kernels.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScottBandwidth;
+
+impl BandwidthStrategy for ScottBandwidth {
+    fn bandwidths(&self, means: &[f64], range: Range) -> Vec<f64> {
+        self.clipped_bandwidths(means, range, BandwidthClip::default())
+    }
+
+    fn clipped_bandwidths(&self, means: &[f64], range: Range, clip: BandwidthClip) -> Vec<f64> {
+        let n = means.len() as f64;
+        let h = 1.06 * stddev(means) * n.powf(-0.2);
+        clip_stddevs(vec![h; means.len()], range, clip)
+    }
+}
+
+/// Silverman's rule of thumb (`0.9 * min(stddev, IQR / 1.34) * n^(-1/5)`) shared by all kernels.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SilvermanBandwidth;
+
+impl BandwidthStrategy for SilvermanBandwidth {
+    fn bandwidths(&self, means: &[f64], range: Range) -> Vec<f64> {
+        self.clipped_bandwidths(means, range, BandwidthClip::default())
+    }
+
+    fn clipped_bandwidths(&self, means: &[f64], range: Range, clip: BandwidthClip) -> Vec<f64> {
+        let n = means.len() as f64;
+        let iqr = quantile(means, 0.75) - quantile(means, 0.25);
+        let spread = if iqr > 0.0 {
+            stddev(means).min(iqr / 1.34)
+        } else {
+            stddev(means)
+        };
+        let h = 0.9 * spread * n.powf(-0.2);
+        clip_stddevs(vec![h; means.len()], range, clip)
+    }
+}
+
+fn neighbor_distances(means: &[f64], range: Range) -> Vec<f64> {
+    (0..means.len())
+        .map(|i| {
+            let prev = if i == 0 { range.start() } else { means[i - 1] };
+            let curr = means[i];
+            let succ = means.get(i + 1).copied().unwrap_or(range.end());
+            (curr - prev).max(succ - curr)
+        })
+        .collect()
+}
+
+fn clip_stddevs(mut stddevs: Vec<f64>, range: Range, clip: BandwidthClip) -> Vec<f64> {
+    let max_stddev = range.width() * clip.max_ratio;
+    let min_stddev = range.width() / clip.min_divisor.min(1.0 + stddevs.len() as f64);
+    for x in &mut stddevs {
+        *x = x.max(min_stddev).min(max_stddev);
+    }
+    stddevs
+}
+
+fn stddev(xs: &[f64]) -> f64 {
+    let n = xs.len() as f64;
+    let mean = xs.iter().sum::<f64>() / n;
+    (xs.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n).sqrt()
+}
+
+// `xs` must be sorted.
+fn quantile(xs: &[f64], q: f64) -> f64 {
+    let i = (xs.len() - 1) as f64 * q;
+    let lower = xs[i.floor() as usize];
+    let upper = xs[i.ceil() as usize];
+    lower + (upper - lower) * i.fract()
+}
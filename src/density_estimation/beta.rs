@@ -0,0 +1,173 @@
+use crate::density_estimation::parzen::logsumexp;
+use crate::density_estimation::{
+    validate_weight, BuildDensityEstimator, DensityEstimator, EstimatorBuildError,
+};
+use crate::Range;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use statrs::distribution::Continuous;
+
+/// Builder of [`BetaKernelEstimator`].
+#[derive(Debug, Default, Clone)]
+pub struct BetaKernelEstimatorBuilder {
+    bandwidth: Option<f64>,
+}
+
+impl BetaKernelEstimatorBuilder {
+    /// Makes a new [`BetaKernelEstimatorBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the bandwidth (in the range scaled to `[0, 1]`).
+    ///
+    /// By default, `0.5 * n^(-2/5)` is used where `n` is the number of the samples plus one (the prior).
+    ///
+    /// The value must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidBandwidth`].
+    pub fn bandwidth(&mut self, bandwidth: f64) -> &mut Self {
+        self.bandwidth = Some(bandwidth);
+        self
+    }
+
+    fn build<I>(&self, xs: I, range: Range) -> Result<BetaKernelEstimator, EstimatorBuildError>
+    where
+        I: Iterator<Item = (f64, f64)>,
+    {
+        if let Some(b) = self.bandwidth {
+            if !b.is_finite() || b <= 0.0 {
+                return Err(EstimatorBuildError::InvalidBandwidth);
+            }
+        }
+
+        // As with `ParzenEstimator`, the midpoint of the range works as a prior.
+        let xs = xs
+            .map(|(x, w)| ((x - range.start()) / range.width(), w))
+            .chain(std::iter::once((0.5, 1.0)))
+            .collect::<Vec<_>>();
+        xs.iter().try_for_each(|&(_, w)| validate_weight(w))?;
+        let b = self
+            .bandwidth
+            .unwrap_or_else(|| 0.5 * (xs.len() as f64).powf(-0.4));
+        let total_weight = xs.iter().map(|&(_, w)| w).sum::<f64>();
+        let kernels = xs
+            .iter()
+            .map(|&(u, w)| {
+                let alpha = u.clamp(0.0, 1.0) / b + 1.0;
+                let beta = (1.0 - u.clamp(0.0, 1.0)) / b + 1.0;
+                Kernel {
+                    weight: w / total_weight,
+                    density: statrs::distribution::Beta::new(alpha, beta).expect("unreachable"),
+                    distribution: rand_distr::Beta::new(alpha, beta).expect("unreachable"),
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok(BetaKernelEstimator {
+            distribution: WeightedIndex::new(kernels.iter().map(|k| k.weight))
+                .expect("unreachable"),
+            kernels,
+            range,
+        })
+    }
+}
+
+impl BuildDensityEstimator for BetaKernelEstimatorBuilder {
+    type Estimator = BetaKernelEstimator;
+    type Error = EstimatorBuildError;
+
+    fn build_density_estimator<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = f64> + Clone,
+    {
+        self.build(xs.map(|x| (x, 1.0)), range)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.build(xs, range)
+    }
+}
+
+#[derive(Debug)]
+struct Kernel {
+    weight: f64,
+    density: statrs::distribution::Beta,
+    distribution: rand_distr::Beta<f64>,
+}
+
+/// Beta kernel density estimator.
+///
+/// The range is scaled to `[0, 1]` and each sample is represented by a Beta distribution,
+/// so no probability mass leaks outside of the range.
+/// This is suitable for bounded parameters such as dropout rates.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::TpeOptimizer::new(tpe::beta_estimator(), tpe::range(0.0, 1.0)?);
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..30 {
+///     let dropout = optim.ask(&mut rng)?;
+///     assert!((0.0..1.0).contains(&dropout));
+///     optim.tell(dropout, dropout)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BetaKernelEstimator {
+    kernels: Vec<Kernel>,
+    range: Range,
+    distribution: WeightedIndex<f64>,
+}
+
+impl DensityEstimator for BetaKernelEstimator {
+    fn log_pdf(&self, x: f64) -> f64 {
+        let u = (x - self.range.start()) / self.range.width();
+        if !(0.0..=1.0).contains(&u) {
+            return f64::NEG_INFINITY;
+        }
+        logsumexp(
+            self.kernels
+                .iter()
+                .map(|k| k.weight.ln() + k.density.ln_pdf(u)),
+        ) - self.range.width().ln()
+    }
+}
+
+impl Distribution<f64> for BetaKernelEstimator {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let k = &self.kernels[self.distribution.sample(rng)];
+        let u = k.distribution.sample(rng);
+        self.range
+            .clamp(self.range.start() + u * self.range.width())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_bandwidth_is_rejected() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        for b in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let builder = BetaKernelEstimatorBuilder::new().bandwidth(b).clone();
+            assert!(matches!(
+                builder.build_density_estimator(std::iter::empty(), range),
+                Err(EstimatorBuildError::InvalidBandwidth)
+            ));
+        }
+    }
+}
@@ -0,0 +1,316 @@
+use crate::density_estimation::parzen::logsumexp;
+use crate::density_estimation::{
+    validate_weight, BuildDensityEstimator, DensityEstimator, EstimatorBuildError, Kernel,
+};
+use crate::Range;
+use ordered_float::OrderedFloat;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+/// Builder of [`GmmEstimator`].
+#[derive(Debug, Clone)]
+pub struct GmmEstimatorBuilder {
+    components: usize,
+    iterations: usize,
+}
+
+impl GmmEstimatorBuilder {
+    /// Makes a new [`GmmEstimatorBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the (maximum) number of mixture components.
+    ///
+    /// The value must be positive,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidComponents`].
+    /// The default value is `4`.
+    pub fn components(&mut self, n: usize) -> &mut Self {
+        self.components = n;
+        self
+    }
+
+    /// Sets the number of EM iterations.
+    ///
+    /// The default value is `50`.
+    pub fn iterations(&mut self, n: usize) -> &mut Self {
+        self.iterations = n;
+        self
+    }
+
+    fn build(
+        &self,
+        mut xs: Vec<(f64, f64)>,
+        range: Range,
+    ) -> Result<GmmEstimator, EstimatorBuildError> {
+        if self.components == 0 {
+            return Err(EstimatorBuildError::InvalidComponents);
+        }
+        xs.iter().try_for_each(|&(_, w)| validate_weight(w))?;
+
+        // As with `ParzenEstimator`, the midpoint of the range works as a prior.
+        xs.push(((range.start() + range.end()) * 0.5, 1.0));
+        xs.sort_by_key(|&(x, _)| OrderedFloat(x));
+
+        let n = xs.len();
+        let k = self.components.min(n);
+        let total_weight = xs.iter().map(|&(_, w)| w).sum::<f64>();
+        let max_stddev = range.width();
+        let min_stddev = range.width() / 100f64.min(1.0 + n as f64);
+
+        let mut components = (0..k)
+            .map(|j| Component {
+                weight: 1.0 / k as f64,
+                mean: xs[((j as f64 + 0.5) / k as f64 * n as f64) as usize].0,
+                stddev: (range.width() / k as f64).clamp(min_stddev, max_stddev),
+                cdf_start: f64::NAN,
+                cdf_end: f64::NAN,
+            })
+            .collect::<Vec<_>>();
+
+        let mut responsibilities = vec![0.0; k];
+        let mut stats = vec![(0.0, 0.0, 0.0); k];
+        for _ in 0..self.iterations {
+            stats.iter_mut().for_each(|s| *s = (0.0, 0.0, 0.0));
+
+            // E-step (the second moments are accumulated around the current means).
+            for &(x, w) in &xs {
+                for (r, c) in responsibilities.iter_mut().zip(components.iter()) {
+                    *r = c.weight.ln() + c.log_pdf(x);
+                }
+                let z = logsumexp(responsibilities.iter().copied());
+                for ((r, s), c) in responsibilities
+                    .iter()
+                    .zip(stats.iter_mut())
+                    .zip(&components)
+                {
+                    let r = w * (r - z).exp();
+                    s.0 += r;
+                    s.1 += r * x;
+                    s.2 += r * (x - c.mean).powi(2);
+                }
+            }
+
+            // M-step.
+            for (c, &(nj, sum, sq)) in components.iter_mut().zip(stats.iter()) {
+                if nj <= f64::EPSILON {
+                    continue;
+                }
+                let mean = sum / nj;
+                let variance = sq / nj - (mean - c.mean).powi(2);
+                c.weight = nj / total_weight;
+                c.mean = mean;
+                c.stddev = variance.max(0.0).sqrt().clamp(min_stddev, max_stddev);
+            }
+        }
+
+        Ok(GmmEstimator::new(components, range))
+    }
+}
+
+impl Default for GmmEstimatorBuilder {
+    fn default() -> Self {
+        Self {
+            components: 4,
+            iterations: 50,
+        }
+    }
+}
+
+impl BuildDensityEstimator for GmmEstimatorBuilder {
+    type Estimator = GmmEstimator;
+    type Error = EstimatorBuildError;
+
+    fn build_density_estimator<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = f64> + Clone,
+    {
+        self.build(xs.map(|x| (x, 1.0)).collect(), range)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.build(xs.collect(), range)
+    }
+}
+
+#[derive(Debug)]
+struct Component {
+    weight: f64,
+    mean: f64,
+    stddev: f64,
+
+    // CDF values at the boundaries of the range.
+    cdf_start: f64,
+    cdf_end: f64,
+}
+
+impl Component {
+    fn log_pdf(&self, x: f64) -> f64 {
+        Kernel::Gaussian.ln_pdf(self.mean, self.stddev, x)
+    }
+}
+
+/// Gaussian mixture density estimator fitted by the EM algorithm.
+///
+/// Unlike [`ParzenEstimator`](super::ParzenEstimator) which places a kernel on each sample,
+/// this has a fixed number of components, so evaluating densities doesn't get slower as samples increase.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::TpeOptimizer::new(tpe::gmm_estimator(), tpe::range(-5.0, 5.0)?);
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..30 {
+///     let x = optim.ask(&mut rng)?;
+///     optim.tell(x, x.powi(2))?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct GmmEstimator {
+    components: Vec<Component>,
+    range: Range,
+    p_accept: f64,
+    distribution: WeightedIndex<f64>,
+}
+
+impl GmmEstimator {
+    fn new(mut components: Vec<Component>, range: Range) -> Self {
+        let total_weight = components.iter().map(|c| c.weight).sum::<f64>();
+        for c in &mut components {
+            c.weight /= total_weight;
+            c.cdf_start = Kernel::Gaussian.cdf(c.mean, c.stddev, range.start());
+            c.cdf_end = Kernel::Gaussian.cdf(c.mean, c.stddev, range.end());
+        }
+        let masses = components
+            .iter()
+            .map(|c| c.weight * (c.cdf_end - c.cdf_start))
+            .collect::<Vec<_>>();
+        let p_accept = masses.iter().sum::<f64>();
+
+        // A component is chosen in proportion to its mass within the range,
+        // which equals drawing from the whole mixture and rejecting the draws outside of the range.
+        let distribution = WeightedIndex::new(&masses)
+            .or_else(|_| WeightedIndex::new(components.iter().map(|c| c.weight)))
+            .expect("unreachable");
+        Self {
+            components,
+            range,
+            p_accept,
+            distribution,
+        }
+    }
+}
+
+impl DensityEstimator for GmmEstimator {
+    fn log_pdf(&self, x: f64) -> f64 {
+        logsumexp(self.components.iter().map(|c| c.weight.ln() + c.log_pdf(x))) - self.p_accept.ln()
+    }
+}
+
+impl Distribution<f64> for GmmEstimator {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        // Draws from the chosen component truncated to the range via its inverse CDF.
+        let c = &self.components[self.distribution.sample(rng)];
+        if c.cdf_start >= c.cdf_end {
+            return self.range.clamp(c.mean);
+        }
+        let p = rng.gen_range(c.cdf_start..c.cdf_end);
+        self.range
+            .clamp(Kernel::Gaussian.inverse_cdf(c.mean, c.stddev, p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng as _;
+
+    fn fit(builder: &GmmEstimatorBuilder, xs: &[f64], range: Range) -> GmmEstimator {
+        builder
+            .build_density_estimator(xs.iter().copied(), range)
+            .expect("unreachable")
+    }
+
+    #[test]
+    fn em_separates_clusters() {
+        let range = Range::new(-5.0, 5.0).expect("unreachable");
+        let xs = (0..100)
+            .map(|i| {
+                let center = if i % 2 == 0 { -3.0 } else { 3.0 };
+                center + 0.1 * ((i / 2) % 5) as f64 - 0.2
+            })
+            .collect::<Vec<_>>();
+        let estimator = fit(GmmEstimatorBuilder::new().components(2), &xs, range);
+
+        let mut means = estimator
+            .components
+            .iter()
+            .map(|c| c.mean)
+            .collect::<Vec<_>>();
+        means.sort_by(f64::total_cmp);
+        assert!((means[0] + 3.0).abs() < 0.1, "{means:?}");
+        assert!((means[1] - 3.0).abs() < 0.1, "{means:?}");
+        for c in &estimator.components {
+            assert!((c.weight - 0.5).abs() < 0.01);
+        }
+        assert!(estimator.log_pdf(-3.0) > estimator.log_pdf(0.0));
+        assert!(estimator.log_pdf(3.0) > estimator.log_pdf(0.0));
+    }
+
+    #[test]
+    fn em_keeps_parameters_valid() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        // Fewer samples than components and duplicated samples.
+        for xs in [&[][..], &[0.5][..], &[0.2, 0.2, 0.2, 0.2, 0.2, 0.2][..]] {
+            let estimator = fit(&GmmEstimatorBuilder::new(), xs, range);
+            assert!(estimator.components.len() <= xs.len() + 1);
+            let total_weight = estimator.components.iter().map(|c| c.weight).sum::<f64>();
+            assert!((total_weight - 1.0).abs() < 1e-12);
+            for c in &estimator.components {
+                assert!(c.stddev.is_finite() && c.stddev > 0.0 && c.stddev <= range.width());
+            }
+        }
+    }
+
+    #[test]
+    fn sample_terminates_when_mass_is_outside_of_range() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        let component = |mean| Component {
+            weight: 1.0,
+            mean,
+            stddev: 0.1,
+            cdf_start: f64::NAN,
+            cdf_end: f64::NAN,
+        };
+        let estimator = GmmEstimator::new(vec![component(1.3), component(100.0)], range);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..1000 {
+            assert!(range.contains(estimator.sample(&mut rng)));
+        }
+    }
+
+    #[test]
+    fn zero_components_are_rejected() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        assert!(matches!(
+            GmmEstimatorBuilder::new()
+                .components(0)
+                .build_density_estimator(std::iter::empty(), range),
+            Err(EstimatorBuildError::InvalidComponents)
+        ));
+    }
+}
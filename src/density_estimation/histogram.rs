@@ -1,22 +1,247 @@
-use crate::density_estimation::{BuildDensityEstimator, DensityEstimator};
+use crate::density_estimation::{
+    validate_weight, BuildDensityEstimator, DensityEstimator, EstimatorBuildError,
+    UpdateDensityEstimator,
+};
 use crate::Range;
 use rand::distributions::{Distribution, WeightedIndex};
 use rand::Rng;
 
 /// Builder of [`HistogramEstimator`].
-#[derive(Debug, Default)]
-pub struct HistogramEstimatorBuilder {}
+#[derive(Debug, Clone)]
+pub struct HistogramEstimatorBuilder {
+    prior_weight: f64,
+    legacy_log_pdf: bool,
+    smoothing: OrdinalSmoothing,
+    prior_free: bool,
+}
 
 impl HistogramEstimatorBuilder {
     /// Makes a new [`HistogramEstimatorBuilder`] instance.
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Sets the pseudo-count added to each category (i.e., the Dirichlet prior `alpha`).
+    ///
+    /// Larger values pull the estimated probabilities toward the uniform distribution,
+    /// and smaller values suppress rarely observed categories more aggressively.
+    ///
+    /// The value must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidPriorWeight`].
+    /// The default value is `1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, HistogramEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Histogram(HistogramEstimatorBuilder::new().prior_weight(0.1).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::categorical_range(3)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prior_weight(&mut self, prior_weight: f64) -> &mut Self {
+        self.prior_weight = prior_weight;
+        self
+    }
+
+    /// Drops the pseudo-counts (see [`HistogramEstimatorBuilder::prior_weight`]) when there are observations,
+    /// so the probabilities are estimated only from the observations.
+    ///
+    /// Note that categories which have never been observed are never sampled then,
+    /// and the pseudo-counts are still used if there are no observations.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, DensityEstimator as _, HistogramEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = HistogramEstimatorBuilder::new()
+    ///     .prior_free(true)
+    ///     .build_density_estimator([0.0, 0.0, 2.0].iter().copied(), tpe::categorical_range(3)?)?;
+    /// assert_eq!(estimator.log_pdf(1.0), f64::NEG_INFINITY);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prior_free(&mut self, enabled: bool) -> &mut Self {
+        self.prior_free = enabled;
+        self
+    }
+
+    /// Makes [`HistogramEstimator::log_pdf`] return the raw probability of a category
+    /// (the behavior of the older versions of this crate) instead of its logarithm.
+    ///
+    /// This is only useful to reproduce the results of previous studies.
+    ///
+    /// The default value is `false`.
+    pub fn legacy_log_pdf(&mut self, enabled: bool) -> &mut Self {
+        self.legacy_log_pdf = enabled;
+        self
+    }
+}
+
+impl HistogramEstimatorBuilder {
+    /// Sets how each observation shares its mass with the adjacent bins.
+    ///
+    /// This is useful for ordinal parameters (e.g., [`ordinal_range`](crate::ordinal_range))
+    /// where neighboring levels are expected to behave similarly.
+    ///
+    /// The default value is [`OrdinalSmoothing::None`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{
+    ///     BuildDensityEstimator as _, DensityEstimator as _, HistogramEstimatorBuilder, OrdinalSmoothing,
+    /// };
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = HistogramEstimatorBuilder::new()
+    ///     .ordinal_smoothing(OrdinalSmoothing::Triangular { radius: 1 })
+    ///     .build_density_estimator([5.0, 5.0].iter().copied(), tpe::ordinal_range(10)?)?;
+    /// assert!(estimator.log_pdf(5.0) > estimator.log_pdf(4.0));
+    /// assert!(estimator.log_pdf(4.0) > estimator.log_pdf(3.0));
+    /// assert_eq!(estimator.log_pdf(4.0), estimator.log_pdf(6.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The standard deviation of [`OrdinalSmoothing::Gaussian`] must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidSmoothingStddev`].
+    pub fn ordinal_smoothing(&mut self, smoothing: OrdinalSmoothing) -> &mut Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Builds a [`HistogramEstimator`] from the number of the observations of each category.
+    ///
+    /// The result is the same as the estimator built from the corresponding observations
+    /// on the range `0..counts.len()` (up to rounding errors).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DensityEstimator as _, HistogramEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // e.g., the result of `SELECT COUNT(*) FROM trials GROUP BY category`.
+    /// let estimator = HistogramEstimatorBuilder::new().from_counts(&[10, 0, 3])?;
+    /// assert!(estimator.log_pdf(0.0) > estimator.log_pdf(2.0));
+    /// assert!(estimator.log_pdf(2.0) > estimator.log_pdf(1.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `counts` is empty.
+    pub fn from_counts(&self, counts: &[u64]) -> Result<HistogramEstimator, EstimatorBuildError> {
+        assert!(!counts.is_empty(), "there must be at least one category");
+        self.validate()?;
+        let observations = counts.iter().map(|&c| c as f64).sum::<f64>();
+        let prior_weight = self.effective_prior_weight(observations);
+        let n = observations + counts.len() as f64 * prior_weight;
+
+        let weight = 1.0 / n;
+        if self.smoothing != OrdinalSmoothing::None {
+            let mut probabilities = vec![prior_weight * weight; counts.len()];
+            for (i, &c) in counts.iter().enumerate() {
+                self.add(&mut probabilities, i, c as f64 * weight);
+            }
+            return Ok(self.finish(probabilities, n, prior_weight > 0.0));
+        }
+        let probabilities = counts
+            .iter()
+            .map(|&c| prior_weight * weight + c as f64 * weight)
+            .collect();
+        Ok(self.finish(probabilities, n, prior_weight > 0.0))
+    }
+
+    fn validate(&self) -> Result<(), EstimatorBuildError> {
+        if !self.prior_weight.is_finite() || self.prior_weight <= 0.0 {
+            return Err(EstimatorBuildError::InvalidPriorWeight);
+        }
+        if let OrdinalSmoothing::Gaussian { stddev } = self.smoothing {
+            if !stddev.is_finite() || stddev <= 0.0 {
+                return Err(EstimatorBuildError::InvalidSmoothingStddev);
+            }
+        }
+        Ok(())
+    }
+
+    fn effective_prior_weight(&self, observations: f64) -> f64 {
+        if self.prior_free && observations > 0.0 {
+            0.0
+        } else {
+            self.prior_weight
+        }
+    }
+
+    // Adds `mass` to the `i`-th bin (and its neighbors if smoothed).
+    fn add(&self, probabilities: &mut [f64], i: usize, mass: f64) {
+        if self.smoothing == OrdinalSmoothing::None {
+            probabilities[i] += mass;
+            return;
+        }
+        let support = match self.smoothing {
+            OrdinalSmoothing::Triangular { radius } => {
+                let end = i.saturating_add(radius).saturating_add(1);
+                i.saturating_sub(radius)..end.min(probabilities.len())
+            }
+            _ => 0..probabilities.len(),
+        };
+
+        // The shares are normalized within the range so that no mass is lost at the edges.
+        let shares = support
+            .clone()
+            .map(|j| {
+                self.smoothing
+                    .share(j as f64 - i as f64)
+                    .expect("unreachable")
+            })
+            .collect::<Vec<_>>();
+        let total = shares.iter().sum::<f64>();
+        for (p, share) in probabilities[support].iter_mut().zip(shares) {
+            *p += mass * share / total;
+        }
+    }
+
+    fn finish(
+        &self,
+        probabilities: Vec<f64>,
+        total_weight: f64,
+        prior: bool,
+    ) -> HistogramEstimator {
+        let distribution = Sampler::new(&probabilities);
+        HistogramEstimator {
+            legacy_log_pdf: self.legacy_log_pdf,
+            probabilities,
+            total_weight,
+            prior,
+            distribution,
+        }
+    }
+}
+
+impl Default for HistogramEstimatorBuilder {
+    fn default() -> Self {
+        Self {
+            prior_weight: 1.0,
+            legacy_log_pdf: false,
+            smoothing: OrdinalSmoothing::None,
+            prior_free: false,
+        }
+    }
 }
 
 impl BuildDensityEstimator for HistogramEstimatorBuilder {
     type Estimator = HistogramEstimator;
-    type Error = std::convert::Infallible;
+    type Error = EstimatorBuildError;
 
     fn build_density_estimator<I>(
         &self,
@@ -26,20 +251,92 @@ impl BuildDensityEstimator for HistogramEstimatorBuilder {
     where
         I: Iterator<Item = f64> + Clone,
     {
+        self.build_density_estimator_weighted(xs.map(|x| (x, 1.0)), range)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.validate()?;
+        xs.clone().try_for_each(|(_, w)| validate_weight(w))?;
         let cardinality = range.width().ceil() as usize;
-        let n = xs.clone().count() + cardinality;
+        let observations = xs.clone().map(|(_, w)| w).sum::<f64>();
+        let prior_weight = self.effective_prior_weight(observations);
+        let n = observations + cardinality as f64 * prior_weight;
 
-        let weight = 1.0 / n as f64;
-        let mut probabilities = vec![weight; cardinality];
-        for x in xs {
-            probabilities[x.floor() as usize] += weight;
+        let weight = 1.0 / n;
+        let mut probabilities = vec![prior_weight * weight; cardinality];
+        for (x, w) in xs {
+            self.add(&mut probabilities, x.floor() as usize, w * weight);
         }
 
-        let distribution = WeightedIndex::new(probabilities.iter()).expect("unreachable");
-        Ok(HistogramEstimator {
-            probabilities,
-            distribution,
-        })
+        Ok(self.finish(probabilities, n, prior_weight > 0.0))
+    }
+}
+
+impl UpdateDensityEstimator for HistogramEstimatorBuilder {
+    fn update_density_estimator(
+        &self,
+        estimator: &mut Self::Estimator,
+        x: f64,
+        weight: f64,
+    ) -> Result<(), Self::Error> {
+        self.validate()?;
+        validate_weight(weight)?;
+        if self.prior_free && estimator.prior {
+            if weight == 0.0 {
+                // Nothing would remain after removing the pseudo-counts.
+                return Err(EstimatorBuildError::InvalidWeight);
+            }
+            // Only the pseudo-counts were there.
+            estimator.probabilities.iter_mut().for_each(|p| *p = 0.0);
+            estimator.total_weight = 0.0;
+            estimator.prior = false;
+        }
+        let n = estimator.total_weight + weight;
+        for p in &mut estimator.probabilities {
+            *p *= estimator.total_weight / n;
+        }
+        self.add(&mut estimator.probabilities, x.floor() as usize, weight / n);
+        estimator.total_weight = n;
+        estimator.distribution = Sampler::new(&estimator.probabilities);
+        Ok(())
+    }
+}
+
+/// Smoothing mode of [`HistogramEstimator`] for ordinal parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OrdinalSmoothing {
+    /// Each observation only raises the probability of its own bin.
+    #[default]
+    None,
+
+    /// The mass is shared with the bins within `radius` with linearly decreasing weights.
+    Triangular {
+        /// Number of the neighboring bins on each side.
+        radius: usize,
+    },
+
+    /// The mass is shared with the bins weighted by the discretized Gaussian kernel.
+    Gaussian {
+        /// Standard deviation in bins (must be positive).
+        stddev: f64,
+    },
+}
+
+impl OrdinalSmoothing {
+    // Unnormalized share of the bin at the distance `d` (`None` if not smoothed).
+    fn share(self, d: f64) -> Option<f64> {
+        match self {
+            Self::None => None,
+            Self::Triangular { radius } => Some((radius as f64 + 1.0 - d.abs()).max(0.0)),
+            Self::Gaussian { stddev } => Some((-0.5 * (d / stddev).powi(2)).exp()),
+        }
     }
 }
 
@@ -47,17 +344,31 @@ impl BuildDensityEstimator for HistogramEstimatorBuilder {
 ///
 /// This can be used for categorical parameters.
 ///
+/// Categories are drawn by binary search over the cumulative weights (`O(log n)` per draw),
+/// or by an alias table (`O(1)` per draw) if there are at least 1024 categories.
+/// The threshold is a heuristic rather than a benchmarked crossover point:
+/// the alias table is more expensive to build, which only pays off when `log n` gets large.
+///
 /// Note that this estimator assumes that each told value is the index, not the raw value,
 /// of a categorical parameter.
 #[derive(Debug)]
 pub struct HistogramEstimator {
+    legacy_log_pdf: bool,
     probabilities: Vec<f64>,
-    distribution: WeightedIndex<f64>,
+    total_weight: f64,
+    prior: bool,
+    distribution: Sampler,
 }
 
 impl DensityEstimator for HistogramEstimator {
     fn log_pdf(&self, x: f64) -> f64 {
-        self.probabilities[x.floor() as usize]
+        // Each bin has the unit width, so the density equals the probability.
+        let p = self.probabilities[x.floor() as usize];
+        if self.legacy_log_pdf {
+            p
+        } else {
+            p.ln()
+        }
     }
 }
 
@@ -66,3 +377,149 @@ impl Distribution<f64> for HistogramEstimator {
         self.distribution.sample(rng) as f64
     }
 }
+
+#[derive(Debug)]
+enum Sampler {
+    WeightedIndex(WeightedIndex<f64>),
+    Alias(AliasTable),
+}
+
+impl Sampler {
+    // Heuristic (not benchmarked) threshold above which the faster draws pay off the costlier construction.
+    const ALIAS_THRESHOLD: usize = 1024;
+
+    fn new(probabilities: &[f64]) -> Self {
+        if probabilities.len() < Self::ALIAS_THRESHOLD {
+            Self::WeightedIndex(WeightedIndex::new(probabilities).expect("unreachable"))
+        } else {
+            Self::Alias(AliasTable::new(probabilities))
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match self {
+            Self::WeightedIndex(d) => d.sample(rng),
+            Self::Alias(d) => d.sample(rng),
+        }
+    }
+}
+
+// Walker's alias table (built by Vose's method).
+#[derive(Debug)]
+struct AliasTable {
+    // Pairs of the threshold and the alias of each column.
+    columns: Vec<(f64, usize)>,
+}
+
+impl AliasTable {
+    fn new(probabilities: &[f64]) -> Self {
+        let n = probabilities.len();
+        let total = probabilities.iter().sum::<f64>();
+        let mut thresholds = probabilities
+            .iter()
+            .map(|p| p * n as f64 / total)
+            .collect::<Vec<_>>();
+        let mut aliases = (0..n).collect::<Vec<_>>();
+
+        let (mut small, mut large): (Vec<_>, Vec<_>) = (0..n).partition(|&i| thresholds[i] < 1.0);
+        while let (Some(&s), Some(&l)) = (small.last(), large.last()) {
+            small.pop();
+            aliases[s] = l;
+            thresholds[l] -= 1.0 - thresholds[s];
+            if thresholds[l] < 1.0 {
+                large.pop();
+                small.push(l);
+            }
+        }
+        // The remaining entries are (numerically) full.
+        for i in small.into_iter().chain(large) {
+            thresholds[i] = 1.0;
+        }
+        Self {
+            columns: thresholds.into_iter().zip(aliases).collect(),
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        // A single uniform draw decides both the column and the coin flip.
+        let u = rng.gen::<f64>() * self.columns.len() as f64;
+        let i = (u as usize).min(self.columns.len() - 1);
+        let (threshold, alias) = self.columns[i];
+        if u - (i as f64) < threshold {
+            i
+        } else {
+            alias
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng as _;
+
+    #[test]
+    fn alias_table_works() {
+        let probabilities = (1..=300).map(|i| i as f64).collect::<Vec<_>>();
+        let table = AliasTable::new(&probabilities);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        let mut counts = vec![0; probabilities.len()];
+        for _ in 0..300_000 {
+            counts[table.sample(&mut rng)] += 1;
+        }
+        let total = probabilities.iter().sum::<f64>();
+        for (c, p) in counts.iter().zip(&probabilities) {
+            let expected = 300_000.0 * p / total;
+            assert!((*c as f64 - expected).abs() < 5.0 * expected.sqrt() + 5.0);
+        }
+    }
+
+    #[test]
+    fn invalid_prior_weight_is_rejected() {
+        let range = Range::new(0.0, 3.0).expect("unreachable");
+        for w in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let builder = HistogramEstimatorBuilder::new().prior_weight(w).clone();
+            assert!(matches!(
+                builder.build_density_estimator(std::iter::empty(), range),
+                Err(EstimatorBuildError::InvalidPriorWeight)
+            ));
+            assert!(matches!(
+                builder.from_counts(&[1, 2, 3]),
+                Err(EstimatorBuildError::InvalidPriorWeight)
+            ));
+        }
+    }
+
+    #[test]
+    fn invalid_smoothing_is_rejected() {
+        let range = Range::new(0.0, 3.0).expect("unreachable");
+        for stddev in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let builder = HistogramEstimatorBuilder::new()
+                .ordinal_smoothing(OrdinalSmoothing::Gaussian { stddev })
+                .clone();
+            assert!(matches!(
+                builder.build_density_estimator(std::iter::empty(), range),
+                Err(EstimatorBuildError::InvalidSmoothingStddev)
+            ));
+        }
+    }
+
+    #[test]
+    fn triangular_smoothing_stays_within_radius() {
+        let range = Range::new(0.0, 10.0).expect("unreachable");
+        let estimator = HistogramEstimatorBuilder::new()
+            .prior_free(true)
+            .ordinal_smoothing(OrdinalSmoothing::Triangular { radius: 2 })
+            .build_density_estimator([1.0].iter().copied(), range)
+            .expect("unreachable");
+        let probabilities = (0..10)
+            .map(|i| estimator.log_pdf(i as f64).exp())
+            .collect::<Vec<_>>();
+        // The shares within the range are 2:3:2:1 (the bin `-1` is out of the range).
+        let expected = [2.0 / 8.0, 3.0 / 8.0, 2.0 / 8.0, 1.0 / 8.0];
+        for (p, e) in probabilities.iter().zip(expected) {
+            assert!((p - e).abs() < 1e-12);
+        }
+        assert!(probabilities[4..].iter().all(|&p| p == 0.0));
+    }
+}
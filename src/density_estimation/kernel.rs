@@ -0,0 +1,203 @@
+use rand::Rng;
+use statrs::distribution::{Continuous, ContinuousCDF};
+
+/// Kernel shape of [`ParzenEstimator`](super::ParzenEstimator).
+///
+/// The standard deviation of each kernel is decided by the [`BandwidthStrategy`](super::BandwidthStrategy)
+/// regardless of the shape (heavy-tailed kernels use it as their scale instead).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Kernel {
+    /// Normal distribution.
+    #[default]
+    Gaussian,
+
+    /// Epanechnikov (parabolic) kernel which has a compact support.
+    Epanechnikov,
+
+    /// Triangular kernel which has a compact support.
+    Triangular,
+
+    /// Cauchy distribution.
+    ///
+    /// Its heavy tails keep a baseline level of global exploration.
+    Cauchy,
+
+    /// Student's t-distribution with the given degrees of freedom.
+    ///
+    /// Its tails are heavier than the Gaussian's and lighter than the Cauchy's (which is the case of `dof = 1`).
+    StudentT {
+        /// Degrees of freedom (must be positive).
+        dof: f64,
+    },
+}
+
+impl Kernel {
+    // Ratio of the half-width of the support (or the scale) to the standard deviation.
+    fn scale(self) -> f64 {
+        match self {
+            Self::Gaussian | Self::Cauchy | Self::StudentT { .. } => 1.0,
+            Self::Epanechnikov => 5f64.sqrt(),
+            Self::Triangular => 6f64.sqrt(),
+        }
+    }
+
+    pub(crate) fn ln_pdf(self, mean: f64, stddev: f64, x: f64) -> f64 {
+        if self == Self::Gaussian {
+            return statrs::distribution::Normal::new(mean, stddev)
+                .expect("unreachable")
+                .ln_pdf(x);
+        }
+
+        let h = stddev * self.scale();
+        let z = (x - mean) / h;
+        match self {
+            Self::Cauchy => return -(std::f64::consts::PI * h * (1.0 + z * z)).ln(),
+            Self::StudentT { dof } => return students_t(mean, stddev, dof).ln_pdf(x),
+            _ => {}
+        }
+        if z.abs() >= 1.0 {
+            return f64::NEG_INFINITY;
+        }
+        let p = match self {
+            Self::Epanechnikov => 0.75 * (1.0 - z * z),
+            _ => 1.0 - z.abs(),
+        };
+        p.ln() - h.ln()
+    }
+
+    pub(crate) fn cdf(self, mean: f64, stddev: f64, x: f64) -> f64 {
+        if self == Self::Gaussian {
+            return statrs::distribution::Normal::new(mean, stddev)
+                .expect("unreachable")
+                .cdf(x);
+        }
+
+        match self {
+            Self::Cauchy => return 0.5 + ((x - mean) / stddev).atan() / std::f64::consts::PI,
+            Self::StudentT { dof } => return students_t(mean, stddev, dof).cdf(x),
+            _ => {}
+        }
+
+        let z = ((x - mean) / (stddev * self.scale())).clamp(-1.0, 1.0);
+        match self {
+            Self::Epanechnikov => 0.5 + 0.75 * (z - z.powi(3) / 3.0),
+            _ if z < 0.0 => (1.0 + z).powi(2) * 0.5,
+            _ => 1.0 - (1.0 - z).powi(2) * 0.5,
+        }
+    }
+
+    pub(crate) fn inverse_cdf(self, mean: f64, stddev: f64, p: f64) -> f64 {
+        if self == Self::Gaussian {
+            return statrs::distribution::Normal::new(mean, stddev)
+                .expect("unreachable")
+                .inverse_cdf(p);
+        }
+
+        let z = match self {
+            Self::Cauchy => (std::f64::consts::PI * (p - 0.5)).tan(),
+            Self::StudentT { .. } => {
+                // Bisection (the tails are wide enough to be bracketed by doubling).
+                let p = p.clamp(1e-12, 1.0 - 1e-12);
+                let (mut lower, mut upper) = (-1.0, 1.0);
+                while self.cdf(0.0, 1.0, lower) > p {
+                    lower *= 2.0;
+                }
+                while self.cdf(0.0, 1.0, upper) < p {
+                    upper *= 2.0;
+                }
+                for _ in 0..64 {
+                    let mid = (lower + upper) * 0.5;
+                    if self.cdf(0.0, 1.0, mid) < p {
+                        lower = mid;
+                    } else {
+                        upper = mid;
+                    }
+                }
+                (lower + upper) * 0.5
+            }
+            Self::Epanechnikov => 2.0 * ((2.0 * p - 1.0).asin() / 3.0).sin(),
+            _ if p < 0.5 => (2.0 * p).sqrt() - 1.0,
+            _ => 1.0 - (2.0 * (1.0 - p)).sqrt(),
+        };
+        mean + z * stddev * self.scale()
+    }
+}
+
+fn students_t(mean: f64, scale: f64, dof: f64) -> statrs::distribution::StudentsT {
+    statrs::distribution::StudentsT::new(mean, scale, dof).expect("unreachable")
+}
+
+/// Log density of the von Mises distribution on the angle `theta`
+/// (the normalizer uses the exponentially scaled Bessel function to avoid overflow).
+pub(crate) fn von_mises_ln_pdf(mean: f64, kappa: f64, theta: f64) -> f64 {
+    kappa * ((theta - mean).cos() - 1.0) - ln_bessel_i0e(kappa) - (2.0 * std::f64::consts::PI).ln()
+}
+
+/// Samples an angle from the von Mises distribution (Best and Fisher's algorithm).
+pub(crate) fn sample_von_mises<R: Rng + ?Sized>(rng: &mut R, mean: f64, kappa: f64) -> f64 {
+    if kappa < 1e-8 {
+        return mean + rng.gen_range(-std::f64::consts::PI..std::f64::consts::PI);
+    }
+    let tau = 1.0 + (1.0 + 4.0 * kappa * kappa).sqrt();
+    let rho = (tau - (2.0 * tau).sqrt()) / (2.0 * kappa);
+    let r = (1.0 + rho * rho) / (2.0 * rho);
+    loop {
+        let u1: f64 = rng.gen();
+        let u2: f64 = rng.gen();
+        let u3: f64 = rng.gen();
+        let z = (std::f64::consts::PI * u1).cos();
+        let f = (1.0 + r * z) / (r + z);
+        let c = kappa * (r - f);
+        if c * (2.0 - c) > u2 || (c / u2).ln() + 1.0 - c >= 0.0 {
+            let theta = f.clamp(-1.0, 1.0).acos();
+            return if u3 < 0.5 { mean - theta } else { mean + theta };
+        }
+    }
+}
+
+// `ln(I0(x) * exp(-x))` (polynomial approximations from Numerical Recipes).
+fn ln_bessel_i0e(x: f64) -> f64 {
+    let ax = x.abs();
+    if ax < 3.75 {
+        let y = (x / 3.75).powi(2);
+        let i0 = 1.0
+            + y * (3.5156229
+                + y * (3.0899424
+                    + y * (1.2067492 + y * (0.2659732 + y * (0.360768e-1 + y * 0.45813e-2)))));
+        i0.ln() - ax
+    } else {
+        let y = 3.75 / ax;
+        let p = 0.39894228
+            + y * (0.1328592e-1
+                + y * (0.225319e-2
+                    + y * (-0.157565e-2
+                        + y * (0.916281e-2
+                            + y * (-0.2057706e-1
+                                + y * (0.2635537e-1 + y * (-0.1647633e-1 + y * 0.392377e-2)))))));
+        p.ln() - 0.5 * ax.ln()
+    }
+}
+
+/// Prior component of [`ParzenEstimator`](super::ParzenEstimator).
+///
+/// The prior is mixed with the kernels of the observations as if it were an additional observation,
+/// which shapes the suggestions while there are few observations.
+/// Note that it is defined in the warped space of the parameter range (see [`Range::warp`](crate::range::Range::warp)).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParzenPrior {
+    /// A kernel at the midpoint of the range (its bandwidth is decided as the other kernels).
+    #[default]
+    Midpoint,
+
+    /// Normal distribution (e.g., centered at the current production value).
+    Normal {
+        /// Mean.
+        mean: f64,
+
+        /// Standard deviation (must be positive).
+        stddev: f64,
+    },
+
+    /// Uniform distribution over the range.
+    Uniform,
+}
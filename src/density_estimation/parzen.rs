@@ -1,14 +1,30 @@
-use crate::density_estimation::{BuildDensityEstimator, DensityEstimator};
+use crate::density_estimation::kernel::{sample_von_mises, von_mises_ln_pdf};
+use crate::density_estimation::validate_weight;
+use crate::density_estimation::{
+    BandwidthClip, BandwidthStrategy, BuildDensityEstimator, DefaultBandwidth, DensityEstimator,
+    EstimatorBuildError, Kernel, ParzenPrior, UpdateDensityEstimator,
+};
 use crate::Range;
 use ordered_float::OrderedFloat;
-use rand::distributions::Distribution;
-use rand::seq::SliceRandom;
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
 use rand::Rng;
-use statrs::distribution::{Continuous, ContinuousCDF};
+use statrs::function::erf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 /// Builder of [`ParzenEstimator`].
-#[derive(Debug, Default)]
-pub struct ParzenEstimatorBuilder {}
+#[derive(Debug, Clone)]
+pub struct ParzenEstimatorBuilder {
+    truncated: bool,
+    reflected: bool,
+    bandwidth: Arc<dyn BandwidthStrategy>,
+    consider_endpoints: bool,
+    bandwidth_clip: BandwidthClip,
+    kernel: Kernel,
+    prior: ParzenPrior,
+    prior_weight: f64,
+    prior_free: bool,
+}
 
 impl ParzenEstimatorBuilder {
     /// Makes a new [`ParzenEstimatorBuilder`] instance.
@@ -16,35 +32,312 @@ impl ParzenEstimatorBuilder {
         Self::default()
     }
 
-    fn setup_stddev(&self, xs: &mut [Normal], range: Range) {
-        let n = xs.len();
-        for i in 0..n {
-            let prev = if i == 0 {
-                range.start()
-            } else {
-                xs[i - 1].mean
-            };
-            let curr = xs[i].mean;
-            let succ = xs.get(i + 1).map_or(range.end(), |x| x.mean);
-            xs[i].stddev = (curr - prev).max(succ - curr);
+    /// Makes the kernels truncated normal distributions on the range.
+    ///
+    /// Then no probability mass leaks outside of the range near the boundaries.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().truncated(true).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn truncated(&mut self, truncated: bool) -> &mut Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Reflects the kernels at the boundaries of the range.
+    ///
+    /// The probability mass leaking outside of the range is folded back inside,
+    /// so the densities near the boundaries are not underestimated
+    /// (which helps to find optima lying on the boundaries).
+    /// If enabled, [`ParzenEstimatorBuilder::truncated`] is ignored.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().reflected(true).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 0.5)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..30 {
+    ///     let dropout = optim.ask(&mut rng)?;
+    ///     assert!((0.0..0.5).contains(&dropout));
+    ///     optim.tell(dropout, dropout)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reflected(&mut self, reflected: bool) -> &mut Self {
+        self.reflected = reflected;
+        self
+    }
+
+    /// Sets the strategy deciding the standard deviations of the kernels.
+    ///
+    /// The default value is [`DefaultBandwidth`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder, ScottBandwidth};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().bandwidth(ScottBandwidth).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bandwidth<B>(&mut self, strategy: B) -> &mut Self
+    where
+        B: 'static + BandwidthStrategy,
+    {
+        self.bandwidth = Arc::new(strategy);
+        self
+    }
+
+    /// Sets the bounds of the standard deviations decided by the bandwidth strategy.
+    ///
+    /// Custom strategies may ignore the bounds (see [`BandwidthStrategy::clipped_bandwidths`]).
+    ///
+    /// The default value is [`BandwidthClip::default`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BandwidthClip, DefaultEstimatorBuilder, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // Lets the bandwidths shrink down to 1/1000 of the range width.
+    /// let clip = BandwidthClip { min_divisor: 1000.0, ..Default::default() };
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().bandwidth_clip(clip).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Both factors must be positive finite numbers,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidBandwidthClip`].
+    pub fn bandwidth_clip(&mut self, clip: BandwidthClip) -> &mut Self {
+        self.bandwidth_clip = clip;
+        self
+    }
+
+    /// Makes the kernels at both ends also consider the distances to the boundaries of the range
+    /// when deciding their standard deviations (same as Optuna's `consider_endpoints`).
+    ///
+    /// The standard deviations of those kernels decided by the strategy (see [`ParzenEstimatorBuilder::bandwidth`])
+    /// are raised to the distances to the boundaries (up to the maximum of [`ParzenEstimatorBuilder::bandwidth_clip`]),
+    /// so combined with [`DefaultBandwidth`], this is equivalent to [`MagicClipBandwidth`](super::MagicClipBandwidth).
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().consider_endpoints(true).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn consider_endpoints(&mut self, enabled: bool) -> &mut Self {
+        self.consider_endpoints = enabled;
+        self
+    }
+
+    /// Sets the shape of the kernels.
+    ///
+    /// The default value is [`Kernel::Gaussian`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, Kernel, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut rng = rand::thread_rng();
+    /// for kernel in [Kernel::Epanechnikov, Kernel::Cauchy, Kernel::StudentT { dof: 3.0 }] {
+    ///     let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().kernel(kernel).clone());
+    ///     let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    ///     for _ in 0..10 {
+    ///         let x = optim.ask(&mut rng)?;
+    ///         optim.tell(x, x)?;
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The degrees of freedom of [`Kernel::StudentT`] must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidDegreesOfFreedom`].
+    pub fn kernel(&mut self, kernel: Kernel) -> &mut Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// Sets the prior component.
+    ///
+    /// The default value is [`ParzenPrior::Midpoint`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder, ParzenPrior};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // The current production value is `2.0`.
+    /// let prior = ParzenPrior::Normal { mean: 2.0, stddev: 0.5 };
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().prior(prior).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 10.0)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The standard deviation of [`ParzenPrior::Normal`] must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidPriorStddev`].
+    pub fn prior(&mut self, prior: ParzenPrior) -> &mut Self {
+        self.prior = prior;
+        self
+    }
+
+    /// Sets how many observations the prior component is worth.
+    ///
+    /// Larger values make the estimator more conservative while there are few observations,
+    /// and smaller values let it follow the observations sooner.
+    /// The value must be a positive finite number,
+    /// otherwise building an estimator fails with [`EstimatorBuildError::InvalidPriorWeight`].
+    ///
+    /// The default value is `1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{DefaultEstimatorBuilder, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = DefaultEstimatorBuilder::Parzen(ParzenEstimatorBuilder::new().prior_weight(5.0).clone());
+    /// let mut optim = tpe::TpeOptimizer::new(estimator, tpe::range(0.0, 1.0)?);
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prior_weight(&mut self, prior_weight: f64) -> &mut Self {
+        self.prior_weight = prior_weight;
+        self
+    }
+
+    /// Drops the prior component (see [`ParzenEstimatorBuilder::prior`]) when there are observations,
+    /// so the density is estimated only from the observations.
+    ///
+    /// This is useful for purely exploitative runs seeded with many trials.
+    /// Note that the prior is still used if there are no observations.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, DensityEstimator as _, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = ParzenEstimatorBuilder::new()
+    ///     .prior_free(true)
+    ///     .build_density_estimator([0.1, 0.2].iter().copied(), tpe::range(0.0, 1.0)?)?;
+    /// assert_eq!(estimator.components().len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prior_free(&mut self, enabled: bool) -> &mut Self {
+        self.prior_free = enabled;
+        self
+    }
+
+    fn validate(&self) -> Result<(), EstimatorBuildError> {
+        let positive = |x: f64| x.is_finite() && x > 0.0;
+        if !positive(self.bandwidth_clip.max_ratio) || !positive(self.bandwidth_clip.min_divisor) {
+            return Err(EstimatorBuildError::InvalidBandwidthClip);
+        }
+        if !positive(self.prior_weight) {
+            return Err(EstimatorBuildError::InvalidPriorWeight);
+        }
+        if let Kernel::StudentT { dof } = self.kernel {
+            if !positive(dof) {
+                return Err(EstimatorBuildError::InvalidDegreesOfFreedom);
+            }
+        }
+        if let ParzenPrior::Normal { stddev, .. } = self.prior {
+            if !positive(stddev) {
+                return Err(EstimatorBuildError::InvalidPriorStddev);
+            }
+        }
+        Ok(())
+    }
+
+    fn setup_stddev(&self, xs: &mut [ParzenComponent], range: Range) {
+        let means = xs.iter().map(|x| x.mean).collect::<Vec<_>>();
+        for (x, stddev) in xs.iter_mut().zip(self.bandwidth.clipped_bandwidths(
+            &means,
+            range,
+            self.bandwidth_clip,
+        )) {
+            x.stddev = stddev;
         }
 
-        if n >= 2 {
-            xs[0].stddev = xs[1].mean - xs[0].mean;
-            xs[n - 1].stddev = xs[n - 1].mean - xs[n - 2].mean;
+        if self.consider_endpoints {
+            let max_stddev = range.width() * self.bandwidth_clip.max_ratio;
+            if let Some(x) = xs.first_mut() {
+                x.stddev = x.stddev.max((x.mean - range.start()).min(max_stddev));
+            }
+            if let Some(x) = xs.last_mut() {
+                x.stddev = x.stddev.max((range.end() - x.mean).min(max_stddev));
+            }
         }
+    }
+}
 
-        let max_stddev = range.width();
-        let min_stddev = range.width() / 100f64.min(1.0 + n as f64);
-        for x in xs {
-            x.stddev = x.stddev.max(min_stddev).min(max_stddev);
+impl Default for ParzenEstimatorBuilder {
+    fn default() -> Self {
+        Self {
+            truncated: false,
+            reflected: false,
+            bandwidth: Arc::new(DefaultBandwidth),
+            consider_endpoints: false,
+            bandwidth_clip: BandwidthClip::default(),
+            kernel: Kernel::Gaussian,
+            prior: ParzenPrior::Midpoint,
+            prior_weight: 1.0,
+            prior_free: false,
         }
     }
 }
 
 impl BuildDensityEstimator for ParzenEstimatorBuilder {
     type Estimator = ParzenEstimator;
-    type Error = std::convert::Infallible;
+    type Error = EstimatorBuildError;
 
     fn build_density_estimator<I>(
         &self,
@@ -54,50 +347,323 @@ impl BuildDensityEstimator for ParzenEstimatorBuilder {
     where
         I: Iterator<Item = f64> + Clone,
     {
-        let prior = (range.start() + range.end()) * 0.5;
-        let mut xs = xs
-            .chain(std::iter::once(prior))
-            .map(|x| Normal {
+        self.build(xs.map(|x| (x, 1.0)), range, false)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.build(xs, range, true)
+    }
+}
+
+impl UpdateDensityEstimator for ParzenEstimatorBuilder {
+    fn update_density_estimator(
+        &self,
+        estimator: &mut Self::Estimator,
+        x: f64,
+        weight: f64,
+    ) -> Result<(), Self::Error> {
+        self.validate()?;
+        validate_weight(weight)?;
+        let mut xs = std::mem::take(&mut estimator.samples);
+        if estimator.prior && self.prior_free {
+            // Only the prior was there.
+            xs.clear();
+        } else if estimator.prior && self.prior != ParzenPrior::Midpoint {
+            xs.pop();
+        }
+        for c in &mut xs {
+            c.weight *= estimator.total_weight;
+        }
+        let i = xs.partition_point(|c| c.mean <= x);
+        xs.insert(
+            i,
+            ParzenComponent {
+                kernel: Some(self.kernel),
                 mean: x,
-                stddev: std::f64::NAN,
-            })
-            .collect::<Vec<_>>();
+                stddev: f64::NAN,
+                ln_stddev: f64::NAN,
+                scaled_stddev: f64::NAN,
+                cdf_start: f64::NAN,
+                cdf_end: f64::NAN,
+                ln_mass: 0.0,
+                weight,
+            },
+        );
+        *estimator = self.finish(xs, estimator.range, true, !self.prior_free)?;
+        Ok(())
+    }
+}
+
+impl ParzenEstimatorBuilder {
+    fn build<I>(
+        &self,
+        xs: I,
+        range: Range,
+        weighted: bool,
+    ) -> Result<ParzenEstimator, EstimatorBuildError>
+    where
+        I: Iterator<Item = (f64, f64)>,
+    {
+        self.validate()?;
+        let component = |(x, w)| ParzenComponent {
+            kernel: Some(self.kernel),
+            mean: x,
+            stddev: f64::NAN,
+            ln_stddev: f64::NAN,
+            scaled_stddev: f64::NAN,
+            cdf_start: f64::NAN,
+            cdf_end: f64::NAN,
+            ln_mass: 0.0,
+            weight: w,
+        };
+        let mut xs = xs.map(component).collect::<Vec<_>>();
+        xs.iter().try_for_each(|x| validate_weight(x.weight))?;
+        let prior = !self.prior_free || xs.is_empty();
+        if prior && self.prior == ParzenPrior::Midpoint {
+            let midpoint = (range.start() + range.end()) * 0.5;
+            xs.push(component((midpoint, self.prior_weight)));
+        }
         xs.sort_by_key(|x| OrderedFloat(x.mean));
+        // The components are no longer equally weighted if the prior is not worth one observation.
+        self.finish(xs, range, weighted || self.prior_weight != 1.0, prior)
+    }
 
+    // `xs` must be sorted by their means and not contain the fixed prior component.
+    // If `prior` is `false`, `xs` must not contain the midpoint component either.
+    fn finish(
+        &self,
+        mut xs: Vec<ParzenComponent>,
+        range: Range,
+        weighted: bool,
+        prior: bool,
+    ) -> Result<ParzenEstimator, EstimatorBuildError> {
+        let midpoint = (range.start() + range.end()) * 0.5;
         self.setup_stddev(&mut xs, range);
+        let fixed_prior = match self.prior {
+            _ if !prior => None,
+            ParzenPrior::Midpoint => None,
+            ParzenPrior::Normal { mean, stddev } => Some((Some(Kernel::Gaussian), mean, stddev)),
+            ParzenPrior::Uniform => Some((None, midpoint, range.width() / 12f64.sqrt())),
+        };
+        if let Some((kernel, mean, stddev)) = fixed_prior {
+            xs.push(ParzenComponent {
+                kernel,
+                mean,
+                stddev,
+                ln_stddev: f64::NAN,
+                scaled_stddev: f64::NAN,
+                cdf_start: f64::NAN,
+                cdf_end: f64::NAN,
+                ln_mass: 0.0,
+                weight: self.prior_weight,
+            });
+        }
+        for x in &mut xs {
+            x.ln_stddev = x.stddev.ln();
+            x.scaled_stddev = x.stddev * std::f64::consts::SQRT_2;
+            x.cdf_start = x.cdf(range.start());
+            x.cdf_end = x.cdf(range.end());
+        }
+        if self.reflected {
+            // The mass within the range after folding the outside mass at both boundaries once.
+            let lower = 2.0 * range.start() - range.end();
+            let upper = 2.0 * range.end() - range.start();
+            for x in &mut xs {
+                x.ln_mass = (x.cdf(upper) - x.cdf(lower)).ln();
+            }
+        } else if self.truncated {
+            for x in &mut xs {
+                x.ln_mass = (x.cdf_end - x.cdf_start).ln();
+            }
+        }
 
-        let p_accept = xs
-            .iter()
-            .map(|x| x.cdf(range.end()) - x.cdf(range.start()))
-            .sum::<f64>()
-            / xs.len() as f64;
+        let total_weight = xs.iter().map(|x| x.weight).sum::<f64>();
+        if total_weight <= 0.0 {
+            return Err(EstimatorBuildError::InvalidWeight);
+        }
+        for x in &mut xs {
+            x.weight /= total_weight;
+        }
+
+        let p_accept = if weighted {
+            xs.iter()
+                .map(|x| x.weight * (x.cdf_end - x.cdf_start))
+                .sum::<f64>()
+        } else {
+            xs.iter().map(|x| x.cdf_end - x.cdf_start).sum::<f64>() / xs.len() as f64
+        };
 
+        let weights = || WeightedIndex::new(xs.iter().map(|x| x.weight)).expect("unreachable");
+        let cyclic = range.is_cyclic();
+        let distribution = if cyclic {
+            Some(weights())
+        } else if !self.truncated && !self.reflected {
+            // A component is chosen in proportion to its mass within the range,
+            // which is equivalent to rejecting the draws outside of the range.
+            WeightedIndex::new(xs.iter().map(|x| x.weight * (x.cdf_end - x.cdf_start)))
+                .ok()
+                .or_else(|| Some(weights()))
+        } else if weighted {
+            Some(weights())
+        } else {
+            None
+        };
         Ok(ParzenEstimator {
+            distribution,
             samples: xs,
             range,
             p_accept,
+            total_weight,
+            truncated: self.truncated && !self.reflected && !cyclic,
+            reflected: self.reflected && !cyclic,
+            cyclic,
+            prior,
+            rejected_draws: AtomicUsize::new(0),
         })
     }
 }
 
-/// Normal distribution.
+/// Mixture component (kernel) of [`ParzenEstimator`].
 #[derive(Debug)]
-struct Normal {
+pub struct ParzenComponent {
+    // `None` is the box of the uniform prior.
+    kernel: Option<Kernel>,
     mean: f64,
     stddev: f64,
+
+    // Constants of the Gaussian kernel (`ln(stddev)` and `sqrt(2) * stddev`).
+    ln_stddev: f64,
+    scaled_stddev: f64,
+
+    // CDF values at the boundaries of the range.
+    cdf_start: f64,
+    cdf_end: f64,
+
+    // Log of the probability mass within the range (used only if truncated or reflected).
+    ln_mass: f64,
+
+    // Normalized mixture weight.
+    weight: f64,
 }
 
-impl Normal {
+impl ParzenComponent {
+    /// Returns the shape of the kernel (`None` for the component of [`ParzenPrior::Uniform`]).
+    pub fn kernel(&self) -> Option<Kernel> {
+        self.kernel
+    }
+
+    /// Returns the center of the kernel.
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    /// Returns the standard deviation (bandwidth) of the kernel.
+    pub fn stddev(&self) -> f64 {
+        self.stddev
+    }
+
+    /// Returns the normalized mixture weight of the kernel.
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
     fn log_pdf(&self, x: f64) -> f64 {
-        statrs::distribution::Normal::new(self.mean, self.stddev)
-            .expect("unreachable")
-            .ln_pdf(x)
+        match self.kernel {
+            Some(Kernel::Gaussian) => {
+                let d = (x - self.mean) / self.stddev;
+                (-0.5 * d * d) - statrs::consts::LN_SQRT_2PI - self.ln_stddev
+            }
+            Some(kernel) => kernel.ln_pdf(self.mean, self.stddev, x),
+            None if (x - self.mean).abs() < self.box_half_width() => {
+                -(2.0 * self.box_half_width()).ln()
+            }
+            None => f64::NEG_INFINITY,
+        }
+    }
+
+    fn box_half_width(&self) -> f64 {
+        self.stddev * 3f64.sqrt()
+    }
+
+    fn reflected_log_pdf(&self, x: f64, range: Range) -> f64 {
+        logsumexp([
+            self.log_pdf(x),
+            self.log_pdf(2.0 * range.start() - x),
+            self.log_pdf(2.0 * range.end() - x),
+        ])
+    }
+
+    // Von Mises kernel of which concentration corresponds to the standard deviation.
+    fn cyclic_log_pdf(&self, x: f64, range: Range) -> f64 {
+        let scale = 2.0 * std::f64::consts::PI / range.width();
+        let kappa = (self.stddev * scale).powi(-2);
+        let theta = (x - range.start()) * scale;
+        let mean = (self.mean - range.start()) * scale;
+        von_mises_ln_pdf(mean, kappa, theta) + scale.ln()
+    }
+
+    fn sample_cyclic<R: Rng + ?Sized>(&self, rng: &mut R, range: Range) -> f64 {
+        let scale = 2.0 * std::f64::consts::PI / range.width();
+        let kappa = (self.stddev * scale).powi(-2);
+        let mean = (self.mean - range.start()) * scale;
+        let theta = sample_von_mises(rng, mean, kappa).rem_euclid(2.0 * std::f64::consts::PI);
+        range.clamp(range.start() + theta / scale)
     }
 
     fn cdf(&self, x: f64) -> f64 {
-        statrs::distribution::Normal::new(self.mean, self.stddev)
-            .expect("unreachable")
-            .cdf(x)
+        match self.kernel {
+            Some(Kernel::Gaussian) => 0.5 * erf::erfc((self.mean - x) / self.scaled_stddev),
+            Some(kernel) => kernel.cdf(self.mean, self.stddev, x),
+            None => (((x - self.mean) / self.box_half_width()).clamp(-1.0, 1.0) + 1.0) * 0.5,
+        }
+    }
+
+    fn inverse_cdf(&self, p: f64) -> f64 {
+        match self.kernel {
+            Some(Kernel::Gaussian) => self.mean - self.scaled_stddev * erf::erfc_inv(2.0 * p),
+            Some(kernel) => kernel.inverse_cdf(self.mean, self.stddev, p),
+            None => self.mean + (2.0 * p - 1.0) * self.box_half_width(),
+        }
+    }
+
+    // Uniform distribution over the CDF values within the range (`None` if the mass within the range vanishes).
+    fn cdf_bounds(&self) -> Option<Uniform<f64>> {
+        (self.cdf_start < self.cdf_end).then(|| Uniform::new(self.cdf_start, self.cdf_end))
+    }
+
+    // Draws from the kernel truncated to the range via its inverse CDF.
+    fn sample_within<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        bounds: Option<&Uniform<f64>>,
+        range: Range,
+    ) -> f64 {
+        match bounds {
+            Some(bounds) => range.clamp(self.inverse_cdf(bounds.sample(rng))),
+            None => range.clamp(self.mean),
+        }
+    }
+
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match self.kernel {
+            Some(Kernel::Gaussian) => rand_distr::Normal::new(self.mean, self.stddev)
+                .expect("unreachable")
+                .sample(rng),
+            Some(Kernel::StudentT { dof }) => {
+                let z: f64 = rand_distr::StudentT::new(dof)
+                    .expect("unreachable")
+                    .sample(rng);
+                self.mean + self.stddev * z
+            }
+            _ => self.inverse_cdf(rng.gen()),
+        }
     }
 }
 
@@ -106,41 +672,710 @@ impl Normal {
 /// This can be used for numerical parameters.
 #[derive(Debug)]
 pub struct ParzenEstimator {
-    samples: Vec<Normal>,
+    samples: Vec<ParzenComponent>,
     range: Range,
     p_accept: f64,
+    total_weight: f64,
+    truncated: bool,
+    reflected: bool,
+    cyclic: bool,
+
+    // Whether the prior component is included.
+    prior: bool,
+
+    // `None` if the components are chosen uniformly.
+    distribution: Option<WeightedIndex<f64>>,
+
+    // Number of the reflected draws which were still out of the range.
+    rejected_draws: AtomicUsize,
 }
 
 impl DensityEstimator for ParzenEstimator {
     fn log_pdf(&self, x: f64) -> f64 {
-        let weight = 1.0 / self.samples.len() as f64;
-        let xs = self
+        if self.cyclic {
+            if !self.range.contains(x) {
+                return f64::NEG_INFINITY;
+            }
+            return logsumexp(
+                self.samples
+                    .iter()
+                    .map(|sample| sample.cyclic_log_pdf(x, self.range) + sample.weight.ln()),
+            );
+        }
+
+        if self.reflected {
+            if !self.range.contains(x) {
+                return f64::NEG_INFINITY;
+            }
+            return logsumexp(self.samples.iter().map(|sample| {
+                sample.reflected_log_pdf(x, self.range) - sample.ln_mass + sample.weight.ln()
+            }));
+        }
+
+        if self.truncated {
+            if !self.range.contains(x) {
+                return f64::NEG_INFINITY;
+            }
+            return logsumexp(
+                self.samples
+                    .iter()
+                    .map(|sample| sample.log_pdf(x) - sample.ln_mass + sample.weight.ln()),
+            );
+        }
+
+        logsumexp(
+            self.samples
+                .iter()
+                .map(|sample| sample.log_pdf(x) + (sample.weight / self.p_accept).ln()),
+        )
+    }
+
+    fn sample_n<R: Rng + ?Sized>(&self, rng: &mut R, n: usize, out: &mut Vec<f64>) {
+        out.reserve(n);
+        if self.reflected || self.cyclic {
+            out.extend((0..n).map(|_| self.sample(rng)));
+            return;
+        }
+
+        // The CDF bounds of the kernels are shared by all the draws in the batch.
+        let bounds = self
+            .samples
+            .iter()
+            .map(|x| x.cdf_bounds())
+            .collect::<Vec<_>>();
+        for _ in 0..n {
+            let i = self.choose_index(rng);
+            let x = &self.samples[i];
+            out.push(x.sample_within(rng, bounds[i].as_ref(), self.range));
+        }
+    }
+
+    fn rejected_draws(&self) -> usize {
+        self.rejected_draws.load(Ordering::Relaxed)
+    }
+
+    fn log_pdf_batch(&self, xs: &[f64], out: &mut [f64]) {
+        assert_eq!(xs.len(), out.len());
+        if self.reflected || self.cyclic {
+            for (&x, o) in xs.iter().zip(out.iter_mut()) {
+                *o = self.log_pdf(x);
+            }
+            return;
+        }
+
+        // The log weights are shared by all the samples.
+        let ln_weights = self
             .samples
             .iter()
-            .map(|sample| sample.log_pdf(x) + (weight / self.p_accept).ln())
+            .map(|sample| {
+                if self.truncated {
+                    sample.weight.ln()
+                } else {
+                    (sample.weight / self.p_accept).ln()
+                }
+            })
             .collect::<Vec<_>>();
-        logsumexp(&xs)
+
+        for (&x, o) in xs.iter().zip(out.iter_mut()) {
+            if self.truncated && !self.range.contains(x) {
+                *o = f64::NEG_INFINITY;
+                continue;
+            }
+            *o = logsumexp(self.samples.iter().zip(ln_weights.iter()).map(
+                |(sample, &ln_weight)| {
+                    let log_pdf = sample.log_pdf(x);
+                    if self.truncated {
+                        log_pdf - sample.ln_mass + ln_weight
+                    } else {
+                        log_pdf + ln_weight
+                    }
+                },
+            ));
+        }
     }
 }
 
-fn logsumexp(xs: &[f64]) -> f64 {
-    let max_x = xs
-        .iter()
-        .max_by_key(|&&x| OrderedFloat(x))
-        .expect("unreachable");
-    xs.iter().map(|&x| (x - max_x).exp()).sum::<f64>().ln() + max_x
+// Computed in a single pass (the accumulator is rescaled whenever the maximum is updated)
+// so that no buffer is needed.
+// Any NaN yields NaN and, once the maximum becomes infinite, the remaining terms can't change the result.
+pub(super) fn logsumexp<I>(xs: I) -> f64
+where
+    I: IntoIterator<Item = f64>,
+{
+    let mut max_x = f64::NEG_INFINITY;
+    let mut sum = 0.0;
+    for x in xs {
+        if x.is_nan() {
+            return f64::NAN;
+        }
+        if x == f64::NEG_INFINITY || max_x == f64::INFINITY {
+            continue;
+        }
+        if x > max_x {
+            sum = sum * (max_x - x).exp() + 1.0;
+            max_x = x;
+        } else {
+            sum += (x - max_x).exp();
+        }
+    }
+    if max_x == f64::NEG_INFINITY {
+        return f64::NEG_INFINITY;
+    }
+    sum.ln() + max_x
 }
 
 impl Distribution<f64> for ParzenEstimator {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
-        while let Some(x) = self.samples.choose(rng) {
-            let draw = rand_distr::Normal::new(x.mean, x.stddev)
-                .expect("unreachable")
-                .sample(rng);
+        if self.cyclic {
+            return self.choose(rng).sample_cyclic(rng, self.range);
+        }
+        if !self.reflected {
+            return self.sample_truncated(rng);
+        }
+
+        loop {
+            let x = self.choose(rng);
+            let mut draw = x.sample(rng);
+            if draw < self.range.start() {
+                draw = 2.0 * self.range.start() - draw;
+            } else if draw >= self.range.end() {
+                draw = 2.0 * self.range.end() - draw;
+            }
             if self.range.contains(draw) {
                 return draw;
             }
+            self.rejected_draws.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl ParzenEstimator {
+    /// Returns the kernels of the mixture (including the prior component).
+    ///
+    /// The kernels are defined in the space in which densities are estimated (see [`Range::warp`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let estimator = ParzenEstimatorBuilder::new()
+    ///     .build_density_estimator([0.1, 0.2].iter().copied(), tpe::range(0.0, 1.0)?)?;
+    /// for c in estimator.components() {
+    ///     println!("mean={}, stddev={}, weight={}", c.mean(), c.stddev(), c.weight());
+    /// }
+    /// assert_eq!(estimator.components().len(), 3); // Two samples and the midpoint prior.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn components(&self) -> &[ParzenComponent] {
+        &self.samples
+    }
+
+    /// Returns the range on which the density is estimated.
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    fn sample_truncated<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let x = self.choose(rng);
+        x.sample_within(rng, x.cdf_bounds().as_ref(), self.range)
+    }
+
+    fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> &ParzenComponent {
+        &self.samples[self.choose_index(rng)]
+    }
+
+    fn choose_index<R: Rng + ?Sized>(&self, rng: &mut R) -> usize {
+        match &self.distribution {
+            Some(d) => d.sample(rng),
+            None => rng.gen_range(0..self.samples.len()),
+        }
+    }
+}
+
+/// Builder of [`DiscreteParzenEstimator`].
+#[derive(Debug, Clone)]
+pub struct DiscreteParzenEstimatorBuilder {
+    parzen: ParzenEstimatorBuilder,
+    step: f64,
+}
+
+impl DiscreteParzenEstimatorBuilder {
+    /// Makes a new [`DiscreteParzenEstimatorBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the builder of the underlying continuous estimator (e.g., to change the kernels).
+    pub fn parzen(&mut self, parzen: ParzenEstimatorBuilder) -> &mut Self {
+        self.parzen = parzen;
+        self
+    }
+
+    /// Sets the width of each bin.
+    ///
+    /// This should be the same as the step of the discrete range (see [`Range::discrete`]).
+    ///
+    /// The default value is `1.0`.
+    pub fn step(&mut self, step: f64) -> &mut Self {
+        self.step = step;
+        self
+    }
+
+    fn build<I>(
+        &self,
+        xs: I,
+        range: Range,
+        weighted: bool,
+    ) -> Result<DiscreteParzenEstimator, EstimatorBuildError>
+    where
+        I: Iterator<Item = (f64, f64)>,
+    {
+        let parzen = self.parzen.build(xs, range, weighted)?;
+        let bins = ((range.width() / self.step).round() as usize).max(1);
+        let masses = (0..bins)
+            .map(|i| {
+                let lower = range.start() + i as f64 * self.step;
+                let upper = lower + self.step;
+                parzen
+                    .samples
+                    .iter()
+                    .map(|x| x.weight * (x.cdf(upper) - x.cdf(lower)))
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+        let total = masses.iter().sum::<f64>();
+        Ok(DiscreteParzenEstimator {
+            start: range.start(),
+            step: self.step,
+            log_probabilities: masses.iter().map(|m| (m / total).ln()).collect(),
+            distribution: WeightedIndex::new(&masses).expect("unreachable"),
+        })
+    }
+}
+
+impl Default for DiscreteParzenEstimatorBuilder {
+    fn default() -> Self {
+        Self {
+            parzen: ParzenEstimatorBuilder::default(),
+            step: 1.0,
+        }
+    }
+}
+
+impl BuildDensityEstimator for DiscreteParzenEstimatorBuilder {
+    type Estimator = DiscreteParzenEstimator;
+    type Error = EstimatorBuildError;
+
+    fn build_density_estimator<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = f64> + Clone,
+    {
+        self.build(xs.map(|x| (x, 1.0)), range, false)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        self.build(xs, range, true)
+    }
+}
+
+/// Parzen window based density estimator for discrete (e.g., integer) parameters.
+///
+/// The probability of each bin is the kernel mass within the bin,
+/// and only the centers of the bins are sampled.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::range::Range;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::TpeOptimizer::new(tpe::discrete_parzen_estimator(), Range::discrete(0.0, 10.0, 1.0)?);
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let x = optim.ask(&mut rng)?;
+///     assert_eq!(x.fract(), 0.0);
+///     optim.tell(x, (x - 3.0).abs())?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct DiscreteParzenEstimator {
+    start: f64,
+    step: f64,
+    log_probabilities: Vec<f64>,
+    distribution: WeightedIndex<f64>,
+}
+
+impl DensityEstimator for DiscreteParzenEstimator {
+    fn log_pdf(&self, x: f64) -> f64 {
+        let i = ((x - self.start) / self.step).floor().max(0.0) as usize;
+        let i = i.min(self.log_probabilities.len() - 1);
+        self.log_probabilities[i] - self.step.ln()
+    }
+}
+
+impl Distribution<f64> for DiscreteParzenEstimator {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let i = self.distribution.sample(rng);
+        self.start + (i as f64 + 0.5) * self.step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::density_estimation::{
+        BetaKernelEstimatorBuilder, GmmEstimatorBuilder, HistogramEstimatorBuilder,
+        MagicClipBandwidth, ScottBandwidth, ThompsonEstimatorBuilder,
+    };
+
+    #[test]
+    fn invalid_weights_are_rejected() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        let builder = ParzenEstimatorBuilder::new();
+        for w in [f64::NAN, -1.0, f64::INFINITY] {
+            let xs = [(0.2, 1.0), (0.5, w)];
+            assert!(matches!(
+                builder.build_density_estimator_weighted(xs.iter().copied(), range),
+                Err(EstimatorBuildError::InvalidWeight)
+            ));
+
+            let mut estimator = builder
+                .build_density_estimator([0.2].iter().copied(), range)
+                .expect("unreachable");
+            assert!(matches!(
+                builder.update_density_estimator(&mut estimator, 0.5, w),
+                Err(EstimatorBuildError::InvalidWeight)
+            ));
+        }
+
+        let xs = [(0.2, 0.0), (0.5, 0.0)];
+        assert!(matches!(
+            builder
+                .clone()
+                .prior_free(true)
+                .build_density_estimator_weighted(xs.iter().copied(), range),
+            Err(EstimatorBuildError::InvalidWeight)
+        ));
+        assert!(builder
+            .build_density_estimator_weighted(xs.iter().copied(), range)
+            .is_ok());
+
+        // The other builders follow the same contract.
+        let categorical = crate::categorical_range(3).expect("unreachable");
+        for w in [f64::NAN, -1.0, f64::INFINITY] {
+            let xs = [(0.0, 1.0), (1.0, w)];
+            let results = [
+                HistogramEstimatorBuilder::new()
+                    .build_density_estimator_weighted(xs.iter().copied(), categorical)
+                    .err(),
+                ThompsonEstimatorBuilder::new()
+                    .build_density_estimator_weighted(xs.iter().copied(), categorical)
+                    .err(),
+                BetaKernelEstimatorBuilder::new()
+                    .build_density_estimator_weighted(xs.iter().copied(), range)
+                    .err(),
+                GmmEstimatorBuilder::new()
+                    .build_density_estimator_weighted(xs.iter().copied(), range)
+                    .err(),
+            ];
+            for result in results {
+                assert!(matches!(result, Some(EstimatorBuildError::InvalidWeight)));
+            }
+
+            let builder = HistogramEstimatorBuilder::new();
+            let mut estimator = builder
+                .build_density_estimator([0.0].iter().copied(), categorical)
+                .expect("unreachable");
+            assert!(matches!(
+                builder.update_density_estimator(&mut estimator, 1.0, w),
+                Err(EstimatorBuildError::InvalidWeight)
+            ));
+        }
+
+        let xs = [(0.0, 0.0), (1.0, 0.0)];
+        assert!(HistogramEstimatorBuilder::new()
+            .build_density_estimator_weighted(xs.iter().copied(), categorical)
+            .is_ok());
+        let mut builder = HistogramEstimatorBuilder::new();
+        builder.prior_free(true);
+        let mut estimator = builder
+            .build_density_estimator(std::iter::empty(), categorical)
+            .expect("unreachable");
+        assert!(matches!(
+            builder.update_density_estimator(&mut estimator, 1.0, 0.0),
+            Err(EstimatorBuildError::InvalidWeight)
+        ));
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let range = Range::new(0.0, 1.0).expect("unreachable");
+        let build = |builder: &ParzenEstimatorBuilder| {
+            builder
+                .build_density_estimator([0.2].iter().copied(), range)
+                .err()
+        };
+
+        let clip = BandwidthClip {
+            max_ratio: 0.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            build(ParzenEstimatorBuilder::new().bandwidth_clip(clip)),
+            Some(EstimatorBuildError::InvalidBandwidthClip)
+        ));
+
+        for w in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                build(ParzenEstimatorBuilder::new().prior_weight(w)),
+                Some(EstimatorBuildError::InvalidPriorWeight)
+            ));
+        }
+
+        assert!(matches!(
+            build(ParzenEstimatorBuilder::new().kernel(Kernel::StudentT { dof: 0.0 })),
+            Some(EstimatorBuildError::InvalidDegreesOfFreedom)
+        ));
+
+        for stddev in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let prior = ParzenPrior::Normal { mean: 0.5, stddev };
+            assert!(matches!(
+                build(ParzenEstimatorBuilder::new().prior(prior)),
+                Some(EstimatorBuildError::InvalidPriorStddev)
+            ));
+        }
+    }
+
+    #[test]
+    fn consider_endpoints_is_combined_with_bandwidth() {
+        let range = Range::new(0.0, 10.0).expect("unreachable");
+        let xs = [3.0, 3.5, 4.0, 4.5];
+        let stddevs = |builder: &ParzenEstimatorBuilder| {
+            builder
+                .build_density_estimator(xs.iter().copied(), range)
+                .expect("unreachable")
+                .components()
+                .iter()
+                .map(|c| c.stddev())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            stddevs(ParzenEstimatorBuilder::new().consider_endpoints(true)),
+            stddevs(ParzenEstimatorBuilder::new().bandwidth(MagicClipBandwidth))
+        );
+
+        let scott = stddevs(ParzenEstimatorBuilder::new().bandwidth(ScottBandwidth));
+        let scott_endpoints = stddevs(
+            ParzenEstimatorBuilder::new()
+                .consider_endpoints(true)
+                .bandwidth(ScottBandwidth),
+        );
+        assert_eq!(scott_endpoints[0], 3.0);
+        assert_eq!(scott_endpoints[1..4], scott[1..4]);
+        assert_eq!(scott_endpoints[4], 5.0); // The midpoint prior.
+    }
+
+    // Ranges of ordinary, extremely narrow and extremely wide widths.
+    fn edge_ranges() -> Vec<Range> {
+        vec![
+            Range::new(0.0, 1.0).expect("unreachable"),
+            Range::new(1.0, 1.0 + 1e-9).expect("unreachable"),
+            Range::new(-1e12, 1e12).expect("unreachable"),
+        ]
+    }
+
+    fn edge_builders() -> Vec<ParzenEstimatorBuilder> {
+        vec![
+            ParzenEstimatorBuilder::new(),
+            ParzenEstimatorBuilder::new().truncated(true).clone(),
+            ParzenEstimatorBuilder::new().reflected(true).clone(),
+            ParzenEstimatorBuilder::new().kernel(Kernel::Cauchy).clone(),
+            ParzenEstimatorBuilder::new()
+                .prior(ParzenPrior::Uniform)
+                .truncated(true)
+                .clone(),
+        ]
+    }
+
+    // Observations at both ends, next to the end and in the middle of the range.
+    fn edge_observations(range: Range) -> Vec<f64> {
+        let last = f64::from_bits(range.end().to_bits() - 1);
+        vec![
+            range.start(),
+            range.start(),
+            range.start() + range.width() / 2.0,
+            last,
+        ]
+    }
+
+    // Points to be evaluated, including the ones outside of the range.
+    fn edge_points(range: Range) -> Vec<f64> {
+        let mut points = edge_observations(range);
+        points.extend([
+            range.end(),
+            range.start() - range.width(),
+            range.end() + range.width(),
+            f64::NAN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ]);
+        points
+    }
+
+    fn assert_same(actual: f64, expected: f64) {
+        assert!(
+            actual == expected || (actual.is_nan() && expected.is_nan()),
+            "{actual} != {expected}"
+        );
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        if expected.is_finite() {
+            let tolerance = 1e-9 * expected.abs().max(1.0);
+            assert!(
+                (actual - expected).abs() <= tolerance,
+                "{actual} != {expected}"
+            );
+        } else {
+            assert_same(actual, expected);
+        }
+    }
+
+    // The straightforward two-pass implementation.
+    fn two_pass_logsumexp(xs: &[f64]) -> f64 {
+        if xs.iter().any(|x| x.is_nan()) {
+            return f64::NAN;
+        }
+        let max_x = xs.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if max_x.is_infinite() {
+            return max_x;
+        }
+        xs.iter().map(|&x| (x - max_x).exp()).sum::<f64>().ln() + max_x
+    }
+
+    #[test]
+    fn logsumexp_matches_two_pass_reference() {
+        let inf = f64::INFINITY;
+        let cases: &[&[f64]] = &[
+            &[],
+            &[0.0],
+            &[-inf],
+            &[-inf, -inf],
+            &[-inf, 0.5],
+            &[0.5, -inf, 1.5],
+            &[inf],
+            &[1.0, inf],
+            &[inf, inf],
+            &[inf, -inf],
+            &[f64::NAN],
+            &[1.0, f64::NAN],
+            &[f64::NAN, inf],
+            &[-inf, f64::NAN],
+            &[f64::MAX, f64::MAX],
+            &[-f64::MAX, 0.0],
+            &[0.0, -745.0, -1000.0],
+            &[-1000.0, -1000.0, -999.0],
+            &[3.0, 1.0, 2.0, 5.0, 4.0],
+            &[700.0, 710.0, 705.0],
+        ];
+        for xs in cases {
+            assert_close(logsumexp(xs.iter().copied()), two_pass_logsumexp(xs));
+        }
+    }
+
+    #[test]
+    fn gaussian_kernel_matches_statrs() {
+        use statrs::distribution::{Continuous as _, ContinuousCDF as _};
+
+        for range in edge_ranges() {
+            let estimator = ParzenEstimatorBuilder::new()
+                .build_density_estimator(edge_observations(range).into_iter(), range)
+                .expect("unreachable");
+            for c in estimator.components() {
+                let normal =
+                    statrs::distribution::Normal::new(c.mean(), c.stddev()).expect("unreachable");
+                for x in edge_points(range) {
+                    assert_close(c.log_pdf(x), normal.ln_pdf(x));
+                    if !x.is_nan() {
+                        assert_close(c.cdf(x), normal.cdf(x));
+                    }
+                }
+            }
+
+            // The mixture of the untruncated kernels normalized by the accepted mass.
+            for x in edge_points(range) {
+                let expected = two_pass_logsumexp(
+                    &estimator
+                        .components()
+                        .iter()
+                        .map(|c| {
+                            let normal = statrs::distribution::Normal::new(c.mean(), c.stddev())
+                                .expect("unreachable");
+                            normal.ln_pdf(x) + (c.weight() / estimator.p_accept).ln()
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                assert_close(estimator.log_pdf(x), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn log_pdf_batch_matches_log_pdf() {
+        let mut ranges = edge_ranges();
+        ranges.push(Range::cyclic(0.0, 10.0).expect("unreachable"));
+        for range in ranges {
+            for builder in edge_builders() {
+                let estimator = builder
+                    .build_density_estimator(edge_observations(range).into_iter(), range)
+                    .expect("unreachable");
+                let xs = edge_points(range);
+                let mut batch = vec![0.0; xs.len()];
+                estimator.log_pdf_batch(&xs, &mut batch);
+                for (&x, &actual) in xs.iter().zip(batch.iter()) {
+                    assert_same(actual, estimator.log_pdf(x));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sample_n_matches_sample() {
+        use rand::SeedableRng as _;
+
+        for range in edge_ranges() {
+            for builder in edge_builders() {
+                let estimator = builder
+                    .build_density_estimator(edge_observations(range).into_iter(), range)
+                    .expect("unreachable");
+                let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+                let mut batch = Vec::new();
+                estimator.sample_n(&mut rng, 100, &mut batch);
+
+                let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+                let scalar = (0..100)
+                    .map(|_| estimator.sample(&mut rng))
+                    .collect::<Vec<_>>();
+                assert_eq!(batch, scalar);
+                assert!(batch.iter().all(|&x| range.contains(x)));
+            }
         }
-        unreachable!();
     }
 }
@@ -0,0 +1,101 @@
+use crate::density_estimation::{
+    BuildDensityEstimator, BuildDensityEstimatorNd, DensityEstimator, DensityEstimatorNd,
+};
+use crate::Range;
+use rand::distributions::Distribution;
+use rand::Rng;
+
+/// Builder of [`ProductEstimator`].
+#[derive(Debug, Clone, Default)]
+pub struct ProductEstimatorBuilder<B> {
+    builder: B,
+}
+
+impl<B: BuildDensityEstimator> ProductEstimatorBuilder<B> {
+    /// Makes a new [`ProductEstimatorBuilder`] instance which builds the estimator of each dimension with `builder`.
+    pub fn new(builder: B) -> Self {
+        Self { builder }
+    }
+}
+
+impl<B: BuildDensityEstimator> BuildDensityEstimatorNd for ProductEstimatorBuilder<B> {
+    type Estimator = ProductEstimator<B::Estimator>;
+    type Error = B::Error;
+
+    fn build_density_estimator_nd<'a, I>(
+        &self,
+        xs: I,
+        ranges: &[Range],
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = &'a [f64]> + Clone,
+    {
+        let estimators = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &range)| {
+                self.builder
+                    .build_density_estimator(xs.clone().map(move |x| x[i]), range)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ProductEstimator { estimators })
+    }
+}
+
+/// Multivariate density estimator which treats each dimension independently.
+///
+/// The density is the product of the univariate densities of the dimensions.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::density_estimation::{
+///     BuildDensityEstimatorNd as _, DensityEstimatorNd as _, ParzenEstimatorBuilder, ProductEstimatorBuilder,
+/// };
+/// use rand::distributions::Distribution as _;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let xs = [vec![0.1, 2.0], vec![0.2, 3.0]];
+/// let ranges = [tpe::range(0.0, 1.0)?, tpe::range(0.0, 10.0)?];
+/// let estimator = ProductEstimatorBuilder::new(ParzenEstimatorBuilder::new())
+///     .build_density_estimator_nd(xs.iter().map(|x| x.as_slice()), &ranges)?;
+/// assert_eq!(estimator.dim(), 2);
+/// assert!(estimator.log_pdf(&[0.15, 2.5]) > estimator.log_pdf(&[0.9, 9.0]));
+///
+/// let x = estimator.sample(&mut rand::thread_rng());
+/// assert!(ranges[0].contains(x[0]) && ranges[1].contains(x[1]));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ProductEstimator<E> {
+    estimators: Vec<E>,
+}
+
+impl<E> ProductEstimator<E> {
+    /// Returns the univariate estimators of the dimensions.
+    pub fn estimators(&self) -> &[E] {
+        &self.estimators
+    }
+}
+
+impl<E: DensityEstimator> DensityEstimatorNd for ProductEstimator<E> {
+    fn dim(&self) -> usize {
+        self.estimators.len()
+    }
+
+    fn log_pdf(&self, x: &[f64]) -> f64 {
+        assert_eq!(x.len(), self.dim());
+        self.estimators
+            .iter()
+            .zip(x.iter())
+            .map(|(e, &x)| e.log_pdf(x))
+            .sum()
+    }
+}
+
+impl<E: DensityEstimator> Distribution<Vec<f64>> for ProductEstimator<E> {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        self.estimators.iter().map(|e| e.sample(rng)).collect()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::density_estimation::{
+    validate_weight, BuildDensityEstimator, DensityEstimator, EstimatorBuildError,
+};
+use crate::Range;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use rand_distr::Gamma;
+use std::sync::OnceLock;
+
+/// Builder of [`ThompsonEstimator`].
+///
+/// The built estimator keeps the Dirichlet posterior of the observations (with the prior `alpha = prior_weight`).
+/// When it is sampled for the first time, the probabilities of the categories are drawn from the posterior
+/// using the given random number generator, instead of being set to the posterior mean
+/// as [`HistogramEstimator`](super::HistogramEstimator) does.
+/// Until then (e.g., the inferior estimator of [`TpeOptimizer`](crate::TpeOptimizer), which is never sampled),
+/// the posterior mean is used.
+///
+/// So the density ratio of a category is a posterior sample of its probability in the superior group
+/// divided by its expected probability in the inferior group,
+/// and choosing the category with the largest ratio is a form of Thompson sampling.
+/// This is suitable for purely categorical parameters with noisy rewards.
+///
+/// # Examples
+///
+/// ```
+/// use rand::SeedableRng as _;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut run = |seed| -> anyhow::Result<Vec<f64>> {
+///     let mut optim = tpe::TpeOptimizer::new(tpe::thompson_estimator(), tpe::categorical_range(3)?);
+///     let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+///     let mut arms = Vec::new();
+///     for _ in 0..30 {
+///         let arm = optim.ask(&mut rng)?;
+///         let reward = if arm == 1.0 { 1.0 } else { 0.0 };
+///         optim.tell(arm, -reward)?;
+///         arms.push(arm);
+///     }
+///     Ok(arms)
+/// };
+///
+/// // The posterior samples are drawn from the generator passed to `ask`.
+/// assert_eq!(run(0)?, run(0)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ThompsonEstimatorBuilder {
+    prior_weight: f64,
+}
+
+impl ThompsonEstimatorBuilder {
+    /// Makes a new [`ThompsonEstimatorBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the pseudo-count added to each category (i.e., the Dirichlet prior `alpha`).
+    ///
+    /// The value must be a positive finite number. The default value is `1.0`.
+    pub fn prior_weight(&mut self, prior_weight: f64) -> &mut Self {
+        self.prior_weight = prior_weight;
+        self
+    }
+}
+
+impl Default for ThompsonEstimatorBuilder {
+    fn default() -> Self {
+        Self { prior_weight: 1.0 }
+    }
+}
+
+impl BuildDensityEstimator for ThompsonEstimatorBuilder {
+    type Estimator = ThompsonEstimator;
+    type Error = EstimatorBuildError;
+
+    fn build_density_estimator<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = f64> + Clone,
+    {
+        self.build_density_estimator_weighted(xs.map(|x| (x, 1.0)), range)
+    }
+
+    fn build_density_estimator_weighted<I>(
+        &self,
+        xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = (f64, f64)> + Clone,
+    {
+        if !self.prior_weight.is_finite() || self.prior_weight <= 0.0 {
+            return Err(EstimatorBuildError::InvalidPriorWeight);
+        }
+        xs.clone().try_for_each(|(_, w)| validate_weight(w))?;
+
+        let cardinality = range.width().ceil() as usize;
+        let mut counts = vec![self.prior_weight; cardinality];
+        for (x, w) in xs {
+            counts[x.floor() as usize] += w;
+        }
+        let total = counts.iter().sum::<f64>();
+        Ok(ThompsonEstimator {
+            counts,
+            total,
+            posterior_sample: OnceLock::new(),
+        })
+    }
+}
+
+/// Categorical distribution whose probabilities are drawn from a Dirichlet posterior.
+///
+/// See [`ThompsonEstimatorBuilder`] for details.
+#[derive(Debug)]
+pub struct ThompsonEstimator {
+    counts: Vec<f64>,
+    total: f64,
+    posterior_sample: OnceLock<(Vec<f64>, WeightedIndex<f64>)>,
+}
+
+impl ThompsonEstimator {
+    fn posterior_sample<R: Rng + ?Sized>(&self, rng: &mut R) -> &(Vec<f64>, WeightedIndex<f64>) {
+        self.posterior_sample.get_or_init(|| {
+            let mut probabilities = self
+                .counts
+                .iter()
+                .map(|&c| {
+                    let gamma = Gamma::new(c, 1.0).expect("unreachable");
+                    gamma.sample(rng).max(f64::MIN_POSITIVE)
+                })
+                .collect::<Vec<_>>();
+            let total = probabilities.iter().sum::<f64>();
+            for p in &mut probabilities {
+                *p /= total;
+            }
+            let distribution = WeightedIndex::new(&probabilities).expect("unreachable");
+            (probabilities, distribution)
+        })
+    }
+}
+
+impl DensityEstimator for ThompsonEstimator {
+    fn log_pdf(&self, x: f64) -> f64 {
+        let i = x.floor() as usize;
+        match self.posterior_sample.get() {
+            Some((probabilities, _)) => probabilities[i].ln(),
+            None => (self.counts[i] / self.total).ln(),
+        }
+    }
+}
+
+impl Distribution<f64> for ThompsonEstimator {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        let (_, distribution) = self.posterior_sample(rng);
+        distribution.sample(rng) as f64
+    }
+}
@@ -0,0 +1,65 @@
+use crate::density_estimation::{BuildDensityEstimator, DensityEstimator};
+use crate::Range;
+use rand::distributions::Distribution;
+use rand::Rng;
+
+/// Builder of [`UniformEstimator`].
+///
+/// The given samples are ignored.
+#[derive(Debug, Default, Clone)]
+pub struct UniformEstimatorBuilder;
+
+impl UniformEstimatorBuilder {
+    /// Makes a new [`UniformEstimatorBuilder`] instance.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl BuildDensityEstimator for UniformEstimatorBuilder {
+    type Estimator = UniformEstimator;
+    type Error = std::convert::Infallible;
+
+    fn build_density_estimator<I>(
+        &self,
+        _xs: I,
+        range: Range,
+    ) -> Result<Self::Estimator, Self::Error>
+    where
+        I: Iterator<Item = f64> + Clone,
+    {
+        Ok(UniformEstimator::new(range))
+    }
+}
+
+/// Uniform distribution over the range.
+///
+/// This is used as a fallback of [`DefaultEstimatorBuilder`](super::DefaultEstimatorBuilder)
+/// when there are too few samples (see [`DefaultEstimatorBuilder::uniform_fallback`](super::DefaultEstimatorBuilder::uniform_fallback)).
+#[derive(Debug)]
+pub struct UniformEstimator {
+    range: Range,
+}
+
+impl UniformEstimator {
+    pub(crate) fn new(range: Range) -> Self {
+        Self { range }
+    }
+}
+
+impl DensityEstimator for UniformEstimator {
+    fn log_pdf(&self, x: f64) -> f64 {
+        if self.range.contains(x) {
+            -self.range.width().ln()
+        } else {
+            f64::NEG_INFINITY
+        }
+    }
+}
+
+impl Distribution<f64> for UniformEstimator {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.range
+            .clamp(rng.gen_range(self.range.start()..self.range.end()))
+    }
+}
@@ -0,0 +1,294 @@
+//! Gaussian-process based sampler which maximizes the expected improvement.
+//!
+//! [`GpSampler`] implements [`Sampler`], so it can be used in place of [`TpeOptimizer`](crate::TpeOptimizer)
+//! (e.g., to compare TPE with Bayesian optimization on the same problem).
+//!
+//! # Examples
+//!
+//! ```
+//! use rand::SeedableRng as _;
+//! use tpe::gp::GpSampler;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+//! let mut sampler = GpSampler::new(tpe::range(-5.0, 5.0)?);
+//! for _ in 0..30 {
+//!     let x = sampler.ask(&mut rng)?;
+//!     sampler.tell(x, (x - 1.0).powi(2))?;
+//! }
+//! let best = sampler.trials().map(|t| t.1).fold(f64::INFINITY, f64::min);
+//! assert!(best < 0.1);
+//! # Ok(())
+//! # }
+//! ```
+use crate::range::Range;
+use crate::sampler::Sampler;
+use crate::TellError;
+use nalgebra::{Cholesky, DMatrix, DVector};
+use rand::{Rng, RngCore};
+use statrs::function::erf;
+use std::convert::Infallible;
+
+const LENGTH_SCALES: [f64; 6] = [0.01, 0.03, 0.1, 0.3, 1.0, 3.0];
+const MAX_FIT_RETRIES: usize = 10;
+
+/// Sampler that fits a Gaussian process to the trials and maximizes the expected improvement.
+///
+/// The process has a squared exponential kernel over the normalized warped range (see [`Range::warped`]),
+/// of which length scale is selected by maximizing the marginal likelihood.
+#[derive(Debug, Clone)]
+pub struct GpSampler {
+    range: Range,
+    trials: Vec<(f64, f64)>,
+    startup_trials: usize,
+    candidates: usize,
+    noise: f64,
+}
+
+impl GpSampler {
+    /// Makes a new [`GpSampler`] instance.
+    pub fn new(param_range: Range) -> Self {
+        Self {
+            range: param_range,
+            trials: Vec::new(),
+            startup_trials: 5,
+            candidates: 256,
+            noise: 1e-6,
+        }
+    }
+
+    /// Sets the number of the initial trials sampled uniformly from the range.
+    ///
+    /// The default value is `5`.
+    pub fn set_startup_trials(&mut self, n: usize) {
+        self.startup_trials = n;
+    }
+
+    /// Sets the number of the random candidates of which expected improvements are evaluated in [`GpSampler::ask`].
+    ///
+    /// The default value is `256`.
+    pub fn set_candidates(&mut self, n: usize) {
+        self.candidates = n.max(1);
+    }
+
+    /// Sets the variance of the observation noise (relative to the variance of the standardized values).
+    ///
+    /// The default value is `1e-6`.
+    pub fn set_noise(&mut self, noise: f64) {
+        self.noise = noise.max(1e-12);
+    }
+
+    /// Returns the next parameter value to be evaluated.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<f64, Infallible> {
+        let observations = self
+            .trials
+            .iter()
+            .filter(|t| !t.0.is_nan())
+            .map(|&(x, y)| (self.normalize(x), y))
+            .collect::<Vec<_>>();
+        if observations.len() < self.startup_trials.max(1) {
+            return Ok(self.range.sample(rng));
+        }
+
+        let Some(gp) = GaussianProcess::fit(&observations, self.noise) else {
+            return Ok(self.range.sample(rng));
+        };
+        let best = (0..self.candidates)
+            .map(|_| self.range.sample(rng))
+            .map(|x| (x, gp.expected_improvement(self.normalize(x))))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .expect("unreachable");
+        Ok(best.0)
+    }
+
+    /// Tells the evaluation result of a parameter value to the sampler.
+    pub fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::NanValue);
+        }
+        if !param.is_nan() && !self.range.contains(param) {
+            return Err(TellError::ParamOutOfRange {
+                param,
+                range: self.range,
+            });
+        }
+        self.trials.push((param, value));
+        Ok(())
+    }
+
+    /// Returns the range of the parameter.
+    pub fn param_range(&self) -> Range {
+        self.range
+    }
+
+    /// Returns an iterator over the told trials.
+    pub fn trials(&self) -> impl '_ + Iterator<Item = (f64, f64)> {
+        self.trials.iter().copied()
+    }
+
+    fn normalize(&self, x: f64) -> f64 {
+        let warped = self.range.warped();
+        (self.range.warp(x) - warped.start()) / warped.width()
+    }
+}
+
+impl Sampler for GpSampler {
+    type Error = Infallible;
+
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error> {
+        GpSampler::ask(self, rng)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        GpSampler::tell(self, param, value)
+    }
+
+    fn param_range(&self) -> Range {
+        GpSampler::param_range(self)
+    }
+}
+
+#[derive(Debug)]
+struct GaussianProcess {
+    xs: Vec<f64>,
+    length_scale: f64,
+    cholesky: Cholesky<f64, nalgebra::Dyn>,
+    alpha: DVector<f64>,
+    best: f64,
+}
+
+impl GaussianProcess {
+    // If the kernel matrix is not positive definite (e.g., due to duplicate points),
+    // the noise is increased tenfold (at least to `f64::EPSILON`) up to `MAX_FIT_RETRIES` times.
+    fn fit(observations: &[(f64, f64)], mut noise: f64) -> Option<Self> {
+        let n = observations.len();
+        let mean = observations.iter().map(|o| o.1).sum::<f64>() / n as f64;
+        let stddev = (observations
+            .iter()
+            .map(|o| (o.1 - mean).powi(2))
+            .sum::<f64>()
+            / n as f64)
+            .sqrt()
+            .max(f64::EPSILON);
+        let xs = observations.iter().map(|o| o.0).collect::<Vec<_>>();
+        let ys = DVector::from_iterator(n, observations.iter().map(|o| (o.1 - mean) / stddev));
+
+        for _ in 0..=MAX_FIT_RETRIES {
+            let gp = LENGTH_SCALES
+                .iter()
+                .filter_map(|&length_scale| {
+                    let k = DMatrix::from_fn(n, n, |i, j| {
+                        kernel(xs[i], xs[j], length_scale) + if i == j { noise } else { 0.0 }
+                    });
+                    let cholesky = Cholesky::new(k)?;
+                    let alpha = cholesky.solve(&ys);
+                    // Log marginal likelihood (without the constant term).
+                    let log_likelihood = -0.5 * ys.dot(&alpha)
+                        - cholesky
+                            .l_dirty()
+                            .diagonal()
+                            .iter()
+                            .map(|d| d.ln())
+                            .sum::<f64>();
+                    Some((log_likelihood, length_scale, cholesky, alpha))
+                })
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, length_scale, cholesky, alpha)| Self {
+                    xs: xs.clone(),
+                    length_scale,
+                    cholesky,
+                    alpha,
+                    best: ys.min(),
+                });
+            if gp.is_some() {
+                return gp;
+            }
+            noise = (noise * 10.0).max(f64::EPSILON);
+        }
+        None
+    }
+
+    // Returns the posterior mean and standard deviation (of the standardized value) at `x`.
+    fn predict(&self, x: f64) -> (f64, f64) {
+        let k = DVector::from_iterator(
+            self.xs.len(),
+            self.xs.iter().map(|&xi| kernel(x, xi, self.length_scale)),
+        );
+        let mean = k.dot(&self.alpha);
+        let variance = (1.0 - k.dot(&self.cholesky.solve(&k))).max(0.0);
+        (mean, variance.sqrt())
+    }
+
+    fn expected_improvement(&self, x: f64) -> f64 {
+        let (mean, stddev) = self.predict(x);
+        expected_improvement(self.best, mean, stddev)
+    }
+}
+
+// Expected improvement over `best` (for minimization) of a normal distribution.
+fn expected_improvement(best: f64, mean: f64, stddev: f64) -> f64 {
+    if stddev <= f64::EPSILON {
+        return (best - mean).max(0.0);
+    }
+    let z = (best - mean) / stddev;
+    let cdf = 0.5 * erf::erfc(-z / std::f64::consts::SQRT_2);
+    let pdf = (-0.5 * z * z).exp() / (2.0 * std::f64::consts::PI).sqrt();
+    (best - mean) * cdf + stddev * pdf
+}
+
+fn kernel(a: f64, b: f64, length_scale: f64) -> f64 {
+    (-0.5 * ((a - b) / length_scale).powi(2)).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_interpolates_observations() {
+        let observations = [(0.1, 3.0), (0.4, 1.0), (0.7, 2.0), (0.9, 5.0)];
+        let gp = GaussianProcess::fit(&observations, 1e-6).expect("unreachable");
+
+        let mean = 11.0 / 4.0;
+        let stddev = (observations
+            .iter()
+            .map(|o| (o.1 - mean).powi(2))
+            .sum::<f64>()
+            / 4.0)
+            .sqrt();
+        for &(x, y) in &observations {
+            let (m, s) = gp.predict(x);
+            assert!((m - (y - mean) / stddev).abs() < 1e-3, "{m}");
+            assert!(s < 1e-2, "{s}");
+        }
+
+        // Far from the observations, the posterior falls back to the prior.
+        let (m, s) = gp.predict(100.0);
+        assert!(m.abs() < 1e-9, "{m}");
+        assert!((s - 1.0).abs() < 1e-9, "{s}");
+    }
+
+    #[test]
+    fn fit_retries_with_larger_noise() {
+        // The kernel matrix of the duplicate points is singular without noise.
+        let observations = [(0.5, 1.0), (0.5, 3.0), (0.5, 1.0), (0.5, 3.0)];
+        let gp = GaussianProcess::fit(&observations, 0.0).expect("unreachable");
+        let (m, _) = gp.predict(0.5);
+        assert!(m.abs() < 1e-3, "{m}");
+    }
+
+    #[test]
+    fn expected_improvement_works() {
+        // Reference values: `stddev * phi(z) + (best - mean) * Phi(z)`.
+        assert!((expected_improvement(0.0, 0.0, 1.0) - 0.398_942_280_401_432_7).abs() < 1e-9);
+        assert!((expected_improvement(1.0, 0.0, 1.0) - 1.083_315_470_587_686_4).abs() < 1e-9);
+        assert!((expected_improvement(0.0, 1.0, 2.0) - 0.395_593_114_802_612_17).abs() < 1e-9);
+        assert_eq!(expected_improvement(1.0, 0.0, 0.0), 1.0);
+        assert_eq!(expected_improvement(0.0, 1.0, 0.0), 0.0);
+
+        let observations = [(0.1, 3.0), (0.4, 1.0), (0.7, 2.0), (0.9, 5.0)];
+        let gp = GaussianProcess::fit(&observations, 1e-6).expect("unreachable");
+        assert!(gp.expected_improvement(0.4) < 1e-3);
+        assert!(gp.expected_improvement(0.45) > gp.expected_improvement(0.9));
+    }
+}
@@ -0,0 +1,307 @@
+//! Multi-fidelity scheduling of trials (Hyperband with TPE proposals, a.k.a. BOHB).
+use self::successive_halving::{max_promotions, Bracket};
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::TpeOptimizer;
+use rand::Rng;
+use std::collections::HashMap;
+
+pub use self::asha::{AshaScheduler, AshaSchedulerBuilder};
+pub use self::successive_halving::{SuccessiveHalving, SuccessiveHalvingBuilder};
+
+mod asha;
+mod successive_halving;
+
+/// Trial to be evaluated with a limited budget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetedTrial {
+    /// Identifier of the trial which should be passed to `tell` with the evaluation result.
+    pub id: u64,
+
+    /// Parameter values (one for each optimizer).
+    pub params: Vec<f64>,
+
+    /// Budget (e.g., the number of epochs) used to evaluate the trial.
+    pub budget: f64,
+}
+
+/// Builder of [`Hyperband`].
+#[derive(Debug)]
+pub struct HyperbandBuilder {
+    eta: usize,
+    min_model_trials: Option<usize>,
+}
+
+impl HyperbandBuilder {
+    /// Makes a new [`HyperbandBuilder`] instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reduction factor of successive halving.
+    ///
+    /// In each round, only the top `1/eta` trials are promoted to the next budget (which is `eta` times larger).
+    ///
+    /// The default value is `3`.
+    pub fn eta(&mut self, eta: usize) -> &mut Self {
+        self.eta = eta;
+        self
+    }
+
+    /// Sets the number of observations required at a budget before its model is used to propose configurations.
+    ///
+    /// The default value is the number of optimizers plus one.
+    pub fn min_model_trials(&mut self, n: usize) -> &mut Self {
+        self.min_model_trials = Some(n);
+        self
+    }
+
+    /// Builds a [`Hyperband`] with the given settings.
+    ///
+    /// `optimizers` are used as the templates of the per-budget models.
+    /// Configurations are proposed by them (as is) until any of the models has enough observations.
+    pub fn build<T>(
+        &self,
+        optimizers: Vec<TpeOptimizer<T>>,
+        min_budget: f64,
+        max_budget: f64,
+    ) -> Result<Hyperband<T>, BuildError>
+    where
+        T: BuildDensityEstimator + Clone,
+    {
+        let s_max = max_promotions(self.eta, min_budget, max_budget)?;
+
+        Ok(Hyperband {
+            eta: self.eta,
+            max_budget,
+            s_max,
+            min_model_trials: self.min_model_trials.unwrap_or(optimizers.len() + 1),
+            models: vec![optimizers.clone(); s_max + 1],
+            template: optimizers,
+            next_s: s_max,
+            bracket: None,
+            pendings: HashMap::new(),
+            next_trial_id: 0,
+        })
+    }
+}
+
+impl Default for HyperbandBuilder {
+    fn default() -> Self {
+        Self {
+            eta: 3,
+            min_model_trials: None,
+        }
+    }
+}
+
+/// Hyperband scheduler which uses TPE to propose configurations (BOHB).
+///
+/// Brackets of successive halving are executed one after another (and repeated forever).
+/// A model is kept for each budget, and new configurations are proposed by
+/// the model of the largest budget which has enough observations.
+///
+/// # Examples
+///
+/// ```
+/// use rand::SeedableRng as _;
+/// use tpe::hyperband::HyperbandBuilder;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let optimizers = vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)];
+/// let mut hyperband = HyperbandBuilder::new().build(optimizers, 1.0, 27.0)?;
+///
+/// let mut rng = rand::rngs::StdRng::from_seed(Default::default());
+/// let mut best_value = f64::INFINITY;
+/// for _ in 0..200 {
+///     let trial = hyperband.ask(&mut rng)?.expect("no pending trials");
+///     let value = trial.params[0].powi(2) + 1.0 / trial.budget;
+///     hyperband.tell(trial.id, value)?;
+///     best_value = best_value.min(value);
+/// }
+/// assert!(best_value < 1.0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Hyperband<T = DefaultEstimatorBuilder> {
+    eta: usize,
+    max_budget: f64,
+    s_max: usize,
+    min_model_trials: usize,
+    template: Vec<TpeOptimizer<T>>,
+    models: Vec<Vec<TpeOptimizer<T>>>,
+    next_s: usize,
+    bracket: Option<Bracket>,
+    pendings: HashMap<u64, usize>,
+    next_trial_id: u64,
+}
+
+impl<T: BuildDensityEstimator + Clone> Hyperband<T> {
+    /// Returns the next trial to be evaluated.
+    ///
+    /// `None` is returned if the current round of successive halving cannot proceed until
+    /// the results of the pending trials are told.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<Option<BudgetedTrial>, T::Error> {
+        loop {
+            if self.bracket.is_none() {
+                let s = self.next_s;
+                self.next_s = if s == 0 { self.s_max } else { s - 1 };
+                let n = ((self.s_max + 1) as f64 / (s + 1) as f64
+                    * (self.eta as f64).powi(s as i32))
+                .ceil() as usize;
+                self.bracket = Some(Bracket::new(n, s));
+            }
+            let bracket = self.bracket.as_mut().expect("unreachable");
+
+            if let Some(slot) = bracket.queue.pop_front() {
+                let config = match slot {
+                    Some(config) => config,
+                    None => {
+                        let params = Self::propose(
+                            &mut self.models,
+                            &mut self.template,
+                            self.min_model_trials,
+                            rng,
+                        )?;
+                        bracket.configs.push(params);
+                        bracket.configs.len() - 1
+                    }
+                };
+                let id = self.next_trial_id;
+                self.next_trial_id += 1;
+                self.pendings.insert(id, config);
+                let budget_index = bracket.max_promotions - bracket.rung;
+                return Ok(Some(BudgetedTrial {
+                    id,
+                    params: bracket.configs[config].clone(),
+                    budget: self.max_budget / (self.eta as f64).powi(budget_index as i32),
+                }));
+            }
+
+            if !self.pendings.is_empty() {
+                return Ok(None);
+            }
+
+            if !bracket.promote(self.eta) {
+                self.bracket = None;
+            }
+        }
+    }
+
+    /// Tells the evaluation result of a trial returned by [`Hyperband::ask`].
+    pub fn tell(&mut self, trial_id: u64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::Optimizer(crate::TellError::NanValue));
+        }
+        let config = self
+            .pendings
+            .remove(&trial_id)
+            .ok_or(TellError::UnknownTrial { id: trial_id })?;
+        let bracket = self.bracket.as_mut().expect("unreachable");
+        bracket.results.push((config, value));
+
+        let model = &mut self.models[bracket.max_promotions - bracket.rung];
+        for (o, &p) in model.iter_mut().zip(bracket.configs[config].iter()) {
+            o.tell(p, value)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of trials which have been asked but not told yet.
+    pub fn pending_trials(&self) -> usize {
+        self.pendings.len()
+    }
+
+    fn propose<R: Rng + ?Sized>(
+        models: &mut [Vec<TpeOptimizer<T>>],
+        template: &mut [TpeOptimizer<T>],
+        min_model_trials: usize,
+        rng: &mut R,
+    ) -> Result<Vec<f64>, T::Error> {
+        let optimizers = models
+            .iter_mut()
+            .find(|m| m.first().map_or(0, |o| o.trials().count()) >= min_model_trials)
+            .map_or(template, |m| m.as_mut_slice());
+        optimizers.iter_mut().map(|o| o.ask(rng)).collect()
+    }
+}
+
+/// Possible errors during building schedulers.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BuildError {
+    #[error("the value of `eta` must be greater than or equal to 2")]
+    /// The value of `eta` must be greater than or equal to `2`.
+    EtaTooSmall,
+
+    #[error("budgets must satisfy `0 < min_budget <= max_budget < inf`")]
+    /// Budgets must satisfy `0 < min_budget <= max_budget < inf`.
+    InvalidBudget,
+
+    #[error("the number of configurations must be a positive integer")]
+    /// The number of configurations must be a positive integer.
+    ZeroConfigs,
+}
+
+/// Possible errors during telling results to schedulers.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TellError {
+    #[error("unknown trial {id}")]
+    /// The trial is not pending.
+    UnknownTrial {
+        /// Trial identifier.
+        id: u64,
+    },
+
+    #[error(transparent)]
+    /// Error from the underlying optimizer.
+    Optimizer(#[from] crate::TellError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parzen_estimator, range};
+    use rand::SeedableRng;
+
+    #[test]
+    fn hyperband_brackets_work() -> anyhow::Result<()> {
+        let optimizers = vec![TpeOptimizer::new(parzen_estimator(), range(0.0, 1.0)?)];
+        let mut hyperband = HyperbandBuilder::new().build(optimizers, 1.0, 9.0)?;
+        let mut rng = rand::rngs::StdRng::from_seed(Default::default());
+
+        let mut budgets = Vec::new();
+        for _ in 0..(9 + 3 + 1) {
+            let trial = hyperband.ask(&mut rng)?.expect("unreachable");
+            hyperband.tell(trial.id, trial.params[0])?;
+            budgets.push(trial.budget);
+        }
+        assert_eq!(budgets[..9], [1.0; 9]);
+        assert_eq!(budgets[9..12], [3.0; 3]);
+        assert_eq!(budgets[12..], [9.0]);
+
+        // The second bracket starts from the budget `3.0`.
+        let trial = hyperband.ask(&mut rng)?.expect("unreachable");
+        assert_eq!(trial.budget, 3.0);
+        assert_eq!(hyperband.ask(&mut rng)?.map(|t| t.budget), Some(3.0));
+        Ok(())
+    }
+
+    #[test]
+    fn hyperband_waits_pending_trials() -> anyhow::Result<()> {
+        let optimizers = vec![TpeOptimizer::new(parzen_estimator(), range(0.0, 1.0)?)];
+        let mut hyperband = HyperbandBuilder::new().build(optimizers, 3.0, 9.0)?;
+        let mut rng = rand::rngs::StdRng::from_seed(Default::default());
+
+        let trials = (0..3)
+            .map(|_| hyperband.ask(&mut rng).map(|t| t.expect("unreachable")))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(hyperband.ask(&mut rng)?, None);
+
+        for t in &trials {
+            hyperband.tell(t.id, t.params[0])?;
+        }
+        assert!(hyperband.tell(trials[0].id, 0.0).is_err());
+        assert_eq!(hyperband.ask(&mut rng)?.map(|t| t.budget), Some(9.0));
+        Ok(())
+    }
+}
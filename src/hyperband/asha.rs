@@ -0,0 +1,197 @@
+use super::successive_halving::max_promotions;
+use super::{BudgetedTrial, BuildError, TellError};
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::TpeOptimizer;
+use ordered_float::OrderedFloat;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+/// Builder of [`AshaScheduler`].
+#[derive(Debug)]
+pub struct AshaSchedulerBuilder {
+    eta: usize,
+}
+
+impl AshaSchedulerBuilder {
+    /// Makes a new [`AshaSchedulerBuilder`] instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reduction factor.
+    ///
+    /// A configuration is promoted to the next rung (whose budget is `eta` times larger)
+    /// if it is in the top `1/eta` of the results of its rung.
+    ///
+    /// The default value is `3`.
+    pub fn eta(&mut self, eta: usize) -> &mut Self {
+        self.eta = eta;
+        self
+    }
+
+    /// Builds an [`AshaScheduler`] with the given settings.
+    pub fn build<T>(
+        &self,
+        optimizers: Vec<TpeOptimizer<T>>,
+        min_budget: f64,
+        max_budget: f64,
+    ) -> Result<AshaScheduler<T>, BuildError>
+    where
+        T: BuildDensityEstimator,
+    {
+        let max_promotions = max_promotions(self.eta, min_budget, max_budget)?;
+        Ok(AshaScheduler {
+            eta: self.eta,
+            min_budget,
+            optimizers,
+            configs: Vec::new(),
+            rungs: (0..=max_promotions).map(|_| Rung::default()).collect(),
+            pendings: HashMap::new(),
+            next_trial_id: 0,
+        })
+    }
+}
+
+impl Default for AshaSchedulerBuilder {
+    fn default() -> Self {
+        Self { eta: 3 }
+    }
+}
+
+/// Asynchronous successive halving scheduler (ASHA).
+///
+/// Unlike [`SuccessiveHalving`](super::SuccessiveHalving), this never waits for pending trials.
+/// Each time a trial is asked, a configuration which is in the top `1/eta` of the (finished) results of a rung
+/// is promoted to the next rung if exists, otherwise a new configuration is proposed by the [`TpeOptimizer`]s
+/// (which learn from the results of the first rung).
+///
+/// # Examples
+///
+/// ```
+/// use rand::SeedableRng as _;
+/// use tpe::hyperband::AshaSchedulerBuilder;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let optimizers = vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)];
+/// let mut asha = AshaSchedulerBuilder::new().build(optimizers, 1.0, 27.0)?;
+///
+/// let mut rng = rand::rngs::StdRng::from_seed(Default::default());
+///
+/// // Four workers evaluate trials concurrently.
+/// let mut running = (0..4).map(|_| asha.ask(&mut rng)).collect::<Result<Vec<_>, _>>()?;
+/// for _ in 0..100 {
+///     let trial = running.remove(0);
+///     let value = trial.params[0].powi(2) + 1.0 / trial.budget;
+///     asha.tell(trial.id, value)?;
+///     running.push(asha.ask(&mut rng)?);
+/// }
+/// assert!(asha.best_trial(3).is_some());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AshaScheduler<T = DefaultEstimatorBuilder> {
+    eta: usize,
+    min_budget: f64,
+    optimizers: Vec<TpeOptimizer<T>>,
+    configs: Vec<Vec<f64>>,
+    rungs: Vec<Rung>,
+    pendings: HashMap<u64, (usize, usize)>,
+    next_trial_id: u64,
+}
+
+impl<T: BuildDensityEstimator> AshaScheduler<T> {
+    /// Returns the next trial to be evaluated.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<BudgetedTrial, T::Error> {
+        let (config, rung) = match self.find_promotable() {
+            Some((config, rung)) => {
+                self.rungs[rung].promoted.insert(config);
+                (config, rung + 1)
+            }
+            None => {
+                let params = self
+                    .optimizers
+                    .iter_mut()
+                    .map(|o| o.ask(rng))
+                    .collect::<Result<_, _>>()?;
+                self.configs.push(params);
+                (self.configs.len() - 1, 0)
+            }
+        };
+
+        let id = self.next_trial_id;
+        self.next_trial_id += 1;
+        self.pendings.insert(id, (config, rung));
+        Ok(BudgetedTrial {
+            id,
+            params: self.configs[config].clone(),
+            budget: self.budget(rung),
+        })
+    }
+
+    /// Tells the evaluation result of a trial returned by [`AshaScheduler::ask`].
+    pub fn tell(&mut self, trial_id: u64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::Optimizer(crate::TellError::NanValue));
+        }
+        let (config, rung) = self
+            .pendings
+            .remove(&trial_id)
+            .ok_or(TellError::UnknownTrial { id: trial_id })?;
+        self.rungs[rung].results.push((config, value));
+
+        if rung == 0 {
+            for (o, &p) in self.optimizers.iter_mut().zip(self.configs[config].iter()) {
+                o.tell(p, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the budget of the given rung.
+    pub fn budget(&self, rung: usize) -> f64 {
+        self.min_budget * (self.eta as f64).powi(rung as i32)
+    }
+
+    /// Returns the number of rungs.
+    pub fn rungs(&self) -> usize {
+        self.rungs.len()
+    }
+
+    /// Returns the best configuration and its value among the finished trials of the given rung.
+    pub fn best_trial(&self, rung: usize) -> Option<(&[f64], f64)> {
+        self.rungs
+            .get(rung)?
+            .results
+            .iter()
+            .min_by_key(|&&(_, v)| OrderedFloat(v))
+            .map(|&(c, v)| (self.configs[c].as_slice(), v))
+    }
+
+    /// Returns the number of trials which have been asked but not told yet.
+    pub fn pending_trials(&self) -> usize {
+        self.pendings.len()
+    }
+
+    fn find_promotable(&self) -> Option<(usize, usize)> {
+        for (i, rung) in self.rungs.iter().enumerate().rev().skip(1) {
+            let k = rung.results.len() / self.eta;
+            let mut results = rung.results.clone();
+            results.sort_by_key(|&(_, v)| OrderedFloat(v));
+            if let Some(&(config, _)) = results
+                .iter()
+                .take(k)
+                .find(|(c, _)| !rung.promoted.contains(c))
+            {
+                return Some((config, i));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct Rung {
+    results: Vec<(usize, f64)>,
+    promoted: HashSet<usize>,
+}
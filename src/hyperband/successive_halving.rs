@@ -0,0 +1,251 @@
+use super::{BudgetedTrial, BuildError, TellError};
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::TpeOptimizer;
+use ordered_float::OrderedFloat;
+use rand::Rng;
+use std::collections::{HashMap, VecDeque};
+
+/// Builder of [`SuccessiveHalving`].
+#[derive(Debug)]
+pub struct SuccessiveHalvingBuilder {
+    eta: usize,
+}
+
+impl SuccessiveHalvingBuilder {
+    /// Makes a new [`SuccessiveHalvingBuilder`] instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the reduction factor.
+    ///
+    /// In each rung, only the top `1/eta` trials are promoted to the next rung (whose budget is `eta` times larger).
+    ///
+    /// The default value is `3`.
+    pub fn eta(&mut self, eta: usize) -> &mut Self {
+        self.eta = eta;
+        self
+    }
+
+    /// Builds a [`SuccessiveHalving`] with the given settings.
+    ///
+    /// `configs` configurations are proposed by `optimizers` and evaluated with `min_budget` at the first rung.
+    /// The number of rungs is decided so that the budget of the last rung doesn't exceed `max_budget`.
+    pub fn build<T>(
+        &self,
+        optimizers: Vec<TpeOptimizer<T>>,
+        configs: usize,
+        min_budget: f64,
+        max_budget: f64,
+    ) -> Result<SuccessiveHalving<T>, BuildError>
+    where
+        T: BuildDensityEstimator,
+    {
+        if configs == 0 {
+            return Err(BuildError::ZeroConfigs);
+        }
+        let max_promotions = max_promotions(self.eta, min_budget, max_budget)?;
+        Ok(SuccessiveHalving {
+            eta: self.eta,
+            min_budget,
+            optimizers,
+            bracket: Bracket::new(configs, max_promotions),
+            pendings: HashMap::new(),
+            next_trial_id: 0,
+            finished: false,
+        })
+    }
+}
+
+impl Default for SuccessiveHalvingBuilder {
+    fn default() -> Self {
+        Self { eta: 3 }
+    }
+}
+
+/// Successive halving scheduler.
+///
+/// Configurations of the first rung are proposed by [`TpeOptimizer`]s which learn from the results of that rung.
+/// Once all the trials of a rung are finished, the top `1/eta` configurations are promoted to the next rung.
+///
+/// # Examples
+///
+/// ```
+/// use rand::SeedableRng as _;
+/// use tpe::hyperband::SuccessiveHalvingBuilder;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let optimizers = vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)];
+/// let mut sh = SuccessiveHalvingBuilder::new().build(optimizers, 27, 1.0, 27.0)?;
+///
+/// let mut rng = rand::rngs::StdRng::from_seed(Default::default());
+/// while let Some(trial) = sh.ask(&mut rng)? {
+///     let value = trial.params[0].powi(2) + 1.0 / trial.budget;
+///     sh.tell(trial.id, value)?;
+/// }
+/// assert!(sh.is_finished());
+/// assert_eq!(sh.rung(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SuccessiveHalving<T = DefaultEstimatorBuilder> {
+    eta: usize,
+    min_budget: f64,
+    optimizers: Vec<TpeOptimizer<T>>,
+    bracket: Bracket,
+    pendings: HashMap<u64, usize>,
+    next_trial_id: u64,
+    finished: bool,
+}
+
+impl<T: BuildDensityEstimator> SuccessiveHalving<T> {
+    /// Returns the next trial to be evaluated.
+    ///
+    /// `None` is returned if the current rung cannot proceed until the results of the pending trials are told,
+    /// or if all the rungs have been finished.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<Option<BudgetedTrial>, T::Error> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+
+            if let Some(slot) = self.bracket.queue.pop_front() {
+                let config = match slot {
+                    Some(config) => config,
+                    None => {
+                        let params = self
+                            .optimizers
+                            .iter_mut()
+                            .map(|o| o.ask(rng))
+                            .collect::<Result<_, _>>()?;
+                        self.bracket.configs.push(params);
+                        self.bracket.configs.len() - 1
+                    }
+                };
+                let id = self.next_trial_id;
+                self.next_trial_id += 1;
+                self.pendings.insert(id, config);
+                return Ok(Some(BudgetedTrial {
+                    id,
+                    params: self.bracket.configs[config].clone(),
+                    budget: self.budget(),
+                }));
+            }
+
+            if !self.pendings.is_empty() {
+                return Ok(None);
+            }
+
+            if !self.bracket.promote(self.eta) {
+                self.finished = true;
+            }
+        }
+    }
+
+    /// Tells the evaluation result of a trial returned by [`SuccessiveHalving::ask`].
+    pub fn tell(&mut self, trial_id: u64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::Optimizer(crate::TellError::NanValue));
+        }
+        let config = self
+            .pendings
+            .remove(&trial_id)
+            .ok_or(TellError::UnknownTrial { id: trial_id })?;
+        self.bracket.results.push((config, value));
+
+        if self.bracket.rung == 0 {
+            let params = &self.bracket.configs[config];
+            for (o, &p) in self.optimizers.iter_mut().zip(params.iter()) {
+                o.tell(p, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the index of the current rung.
+    pub fn rung(&self) -> usize {
+        self.bracket.rung
+    }
+
+    /// Returns the budget of the current rung.
+    pub fn budget(&self) -> f64 {
+        self.min_budget * (self.eta as f64).powi(self.bracket.rung as i32)
+    }
+
+    /// Returns the results (configuration and value pairs) of the current rung told so far.
+    pub fn rung_results(&self) -> impl '_ + Iterator<Item = (&[f64], f64)> {
+        self.bracket
+            .results
+            .iter()
+            .map(move |&(c, v)| (self.bracket.configs[c].as_slice(), v))
+    }
+
+    /// Returns `true` if all the rungs have been finished.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Returns the number of trials which have been asked but not told yet.
+    pub fn pending_trials(&self) -> usize {
+        self.pendings.len()
+    }
+}
+
+/// A bracket of successive halving.
+#[derive(Debug)]
+pub(super) struct Bracket {
+    pub(super) max_promotions: usize,
+    pub(super) rung: usize,
+    pub(super) configs: Vec<Vec<f64>>,
+
+    // `None` means a new configuration to be proposed.
+    pub(super) queue: VecDeque<Option<usize>>,
+    pub(super) results: Vec<(usize, f64)>,
+}
+
+impl Bracket {
+    pub(super) fn new(configs: usize, max_promotions: usize) -> Self {
+        Self {
+            max_promotions,
+            rung: 0,
+            configs: Vec::new(),
+            queue: std::iter::repeat_n(None, configs).collect(),
+            results: Vec::new(),
+        }
+    }
+
+    // Returns `false` if the bracket has been finished.
+    pub(super) fn promote(&mut self, eta: usize) -> bool {
+        if self.rung == self.max_promotions {
+            return false;
+        }
+
+        let mut results = std::mem::take(&mut self.results);
+        results.sort_by_key(|&(_, v)| OrderedFloat(v));
+        let k = (results.len() / eta).max(1);
+        self.queue = results.into_iter().take(k).map(|(c, _)| Some(c)).collect();
+        self.rung += 1;
+        true
+    }
+}
+
+/// Returns the number of times that the budget can be multiplied by `eta` within `max_budget`.
+pub(super) fn max_promotions(
+    eta: usize,
+    min_budget: f64,
+    max_budget: f64,
+) -> Result<usize, BuildError> {
+    if eta < 2 {
+        return Err(BuildError::EtaTooSmall);
+    }
+    if !(0.0 < min_budget && min_budget <= max_budget && max_budget.is_finite()) {
+        return Err(BuildError::InvalidBudget);
+    }
+
+    let mut n = 0;
+    while min_budget * (eta as f64).powi(n as i32 + 1) <= max_budget {
+        n += 1;
+    }
+    Ok(n)
+}
@@ -0,0 +1,309 @@
+//! Append-only JSON Lines journal of trials.
+use crate::study::{self, StudyStorage};
+#[cfg(doc)]
+use crate::TpeOptimizer;
+use crate::{TellError, Trial};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Journal that appends each asked parameter and told trial to a file as a JSON line.
+///
+/// Each line is written with a single write call when it is recorded, so the journal can be
+/// followed by log shippers and a [`TpeOptimizer`] can be resumed from it by [`TpeOptimizer::resume_from_journal`].
+///
+/// The lines look like the following:
+///
+/// ```text
+/// {"op":"ask","param":0.5}
+/// {"op":"tell","param":0.5,"value":1.2}
+/// ```
+///
+/// It also implements [`StudyStorage`], which appends the trials of a [`Study`](crate::study::Study)
+/// as `{"op":"trial","params":[0.5,null],"value":1.2}` (`null` is an inactive parameter)
+/// and the best ones as `{"op":"best",...}`.
+///
+/// As JSON has no representation of non-finite numbers, they are written as the strings
+/// `"NaN"`, `"inf"` and `"-inf"` (e.g., `{"op":"tell","param":"NaN","value":"inf"}`).
+///
+/// # Examples
+///
+/// ```
+/// use tpe::journal::Journal;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("journal.jsonl");
+///
+/// let mut rng = rand::thread_rng();
+/// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+/// let mut journal = Journal::open(&path)?;
+/// for _ in 0..10 {
+///     let x = optim.ask(&mut rng)?;
+///     journal.record_ask(x)?;
+///     optim.tell(x, x.powi(2))?;
+///     journal.record_tell(x, x.powi(2))?;
+/// }
+///
+/// let resumed =
+///     tpe::TpeOptimizer::resume_from_journal(&path, tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+/// assert_eq!(resumed.export_trials(), optim.export_trials());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```
+/// use tpe::journal::Journal;
+/// use tpe::study::Study;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("study.jsonl");
+/// let new_study = || -> anyhow::Result<Study> {
+///     Ok(Study::with_seed(
+///         vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)],
+///         0,
+///     ))
+/// };
+///
+/// let mut journal = Journal::open(&path)?;
+/// let mut study = new_study()?;
+/// for _ in 0..10 {
+///     let params = study.ask()?;
+///     let value = params[0].powi(2);
+///     study.tell_and_store(&mut journal, params, value)?;
+/// }
+///
+/// let mut resumed = new_study()?;
+/// resumed.restore(&Journal::open(&path)?)?;
+/// assert_eq!(resumed.trials(), study.trials());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    /// Opens (or creates) the journal file at the given path in append mode.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, JournalError> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self { path, file })
+    }
+
+    /// Appends the parameter returned by [`TpeOptimizer::ask`].
+    ///
+    /// Asked parameters are informational and ignored when resuming.
+    pub fn record_ask(&mut self, param: f64) -> Result<(), JournalError> {
+        self.append(&Entry::Ask { param })
+    }
+
+    /// Appends the trial told by [`TpeOptimizer::tell`].
+    pub fn record_tell(&mut self, param: f64, value: f64) -> Result<(), JournalError> {
+        self.record_trial(Trial::new(param, value))
+    }
+
+    /// Appends the given trial (including its metadata).
+    pub fn record_trial(&mut self, trial: Trial) -> Result<(), JournalError> {
+        if trial.value.is_nan() {
+            return Err(TellError::NanValue.into());
+        }
+        self.append(&Entry::Tell {
+            param: trial.param,
+            value: trial.value,
+            metadata: trial.metadata,
+        })
+    }
+
+    fn append(&mut self, entry: &Entry) -> Result<(), JournalError> {
+        let mut line = serde_json::to_vec(entry).expect("unreachable");
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        Ok(())
+    }
+
+    fn append_study_trial(&mut self, trial: &study::Trial, best: bool) -> Result<(), JournalError> {
+        let params = trial
+            .params
+            .iter()
+            .map(|p| Some(*p).filter(|p| !p.is_nan()))
+            .collect();
+        let value = trial.value;
+        if best {
+            self.append(&Entry::Best { params, value })
+        } else {
+            self.append(&Entry::Trial { params, value })
+        }
+    }
+
+    // Loads the study trials (or the best ones) in the order they were appended.
+    fn load_study_trials(&self, best: bool) -> Result<Vec<study::Trial>, JournalError> {
+        let content = std::fs::read_to_string(&self.path)?;
+        let mut trials = Vec::new();
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (params, value) = match serde_json::from_str(line) {
+                Ok(Entry::Trial { params, value }) if !best => (params, value),
+                Ok(Entry::Best { params, value }) if best => (params, value),
+                Ok(_) => continue,
+                // A truncated last line (e.g., caused by a crash during writing) is ignored.
+                Err(_) if !line.ends_with('\n') => break,
+                Err(e) => {
+                    return Err(JournalError::Json {
+                        line: i + 1,
+                        source: e,
+                    })
+                }
+            };
+            trials.push(study::Trial {
+                params: params.into_iter().map(|p| p.unwrap_or(f64::NAN)).collect(),
+                value,
+            });
+        }
+        Ok(trials)
+    }
+}
+
+impl StudyStorage for Journal {
+    type Error = JournalError;
+
+    fn append_trial(&mut self, trial: &study::Trial) -> Result<(), Self::Error> {
+        self.append_study_trial(trial, false)
+    }
+
+    fn load_trials(&self) -> Result<Vec<study::Trial>, Self::Error> {
+        self.load_study_trials(false)
+    }
+
+    fn record_best(&mut self, trial: &study::Trial) -> Result<(), Self::Error> {
+        self.append_study_trial(trial, true)
+    }
+
+    fn load_best(&self) -> Result<Option<study::Trial>, Self::Error> {
+        Ok(self.load_study_trials(true)?.pop())
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub(crate) enum Entry {
+    Ask {
+        param: f64,
+    },
+    Tell {
+        #[serde(with = "non_finite")]
+        param: f64,
+        #[serde(with = "non_finite")]
+        value: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metadata: Option<String>,
+    },
+    Trial {
+        params: Vec<Option<f64>>,
+        #[serde(with = "non_finite")]
+        value: f64,
+    },
+    Best {
+        params: Vec<Option<f64>>,
+        #[serde(with = "non_finite")]
+        value: f64,
+    },
+}
+
+// (De)serializes a number as is if it's finite, otherwise as a string such as `"NaN"` or `"inf"`.
+mod non_finite {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(x: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if x.is_finite() {
+            serializer.serialize_f64(*x)
+        } else {
+            serializer.serialize_str(&x.to_string())
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(f64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(x) => Ok(x),
+            Repr::Text(s) => match s.as_str() {
+                "NaN" | "inf" | "-inf" => Ok(s.parse().expect("unreachable")),
+                _ => Err(serde::de::Error::custom(format!("not a number: {s:?}"))),
+            },
+        }
+    }
+}
+
+/// Possible errors during [`Journal`] operations and [`TpeOptimizer::resume_from_journal`].
+#[derive(Debug, thiserror::Error)]
+pub enum JournalError {
+    #[error(transparent)]
+    /// I/O error.
+    Io(#[from] std::io::Error),
+
+    #[error("line {line} is not a valid journal entry")]
+    /// The line is not a valid journal entry.
+    Json {
+        /// One-based line number.
+        line: usize,
+        /// Underlying error.
+        source: serde_json::Error,
+    },
+
+    #[error(transparent)]
+    /// A trial was rejected by the optimizer.
+    Tell(#[from] TellError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_numbers_survive_round_trip() -> anyhow::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("journal.jsonl");
+        let range = crate::range(0.0, 1.0)?;
+
+        let mut journal = Journal::open(&path)?;
+        journal.record_tell(0.5, f64::INFINITY)?;
+        journal.record_tell(f64::NAN, 1.0)?;
+        journal.record_tell(0.2, f64::NEG_INFINITY)?;
+        assert!(
+            std::fs::read_to_string(&path)?.contains(r#"{"op":"tell","param":"NaN","value":1.0}"#)
+        );
+
+        let resumed =
+            crate::TpeOptimizer::resume_from_journal(&path, crate::parzen_estimator(), range)?;
+        let trials = resumed.export_trials();
+        assert_eq!(trials.len(), 3);
+        assert!(trials.iter().any(|t| t.param.is_nan() && t.value == 1.0));
+        assert!(trials.iter().any(|t| t.value == f64::INFINITY));
+        assert!(trials.iter().any(|t| t.value == f64::NEG_INFINITY));
+
+        let trial = study::Trial {
+            params: vec![0.5, f64::NAN],
+            value: f64::INFINITY,
+        };
+        journal.append_trial(&trial)?;
+        journal.record_best(&trial)?;
+        let loaded = journal.load_trials()?;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].params[0], 0.5);
+        assert!(loaded[0].params[1].is_nan());
+        assert_eq!(loaded[0].value, f64::INFINITY);
+        assert_eq!(journal.load_best()?.map(|t| t.value), Some(f64::INFINITY));
+        Ok(())
+    }
+}
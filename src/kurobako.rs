@@ -0,0 +1,118 @@
+//! A TPE based solver for the [`kurobako`] benchmark.
+//!
+//! [`kurobako`]: https://github.com/sile/kurobako
+use crate::{categorical_range, histogram_estimator, parzen_estimator, range, TpeOptimizer};
+use kurobako_core::domain::{self, Variable};
+use kurobako_core::problem::ProblemSpec;
+use kurobako_core::rng::ArcRng;
+use kurobako_core::solver::{Capability, Solver, SolverFactory, SolverSpec, SolverSpecBuilder};
+use kurobako_core::trial::{EvaluatedTrial, IdGen, NextTrial, Params, TrialId};
+use kurobako_core::{ErrorKind, Result};
+use std::collections::HashMap;
+use trackable::error::ErrorKindExt as _;
+
+/// Factory of [`TpeSolver`].
+#[derive(Debug, Default, Clone)]
+pub struct TpeSolverFactory;
+
+impl TpeSolverFactory {
+    /// Makes a new [`TpeSolverFactory`] instance.
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl SolverFactory for TpeSolverFactory {
+    type Solver = TpeSolver;
+
+    fn specification(&self) -> Result<SolverSpec> {
+        Ok(SolverSpecBuilder::new("TPE")
+            .capable(Capability::Categorical)
+            .capable(Capability::Concurrent)
+            .capable(Capability::LogUniformContinuous)
+            .capable(Capability::UniformContinuous)
+            .capable(Capability::UniformDiscrete)
+            .finish())
+    }
+
+    fn create_solver(&self, rng: ArcRng, problem: &ProblemSpec) -> Result<Self::Solver> {
+        TpeSolver::new(problem.clone(), rng)
+    }
+}
+
+/// Solver that optimizes each parameter of a problem by a [`TpeOptimizer`].
+#[derive(Debug)]
+pub struct TpeSolver {
+    problem: ProblemSpec,
+    optimizers: Vec<TpeOptimizer>,
+    evaluating: HashMap<TrialId, Vec<f64>>,
+    rng: ArcRng,
+}
+
+impl TpeSolver {
+    /// Makes a new [`TpeSolver`] instance for the given problem.
+    pub fn new(problem: ProblemSpec, rng: ArcRng) -> Result<Self> {
+        let optimizers = problem
+            .params_domain
+            .variables()
+            .iter()
+            .map(create_optimizer)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            problem,
+            optimizers,
+            evaluating: HashMap::new(),
+            rng,
+        })
+    }
+}
+
+impl Solver for TpeSolver {
+    fn ask(&mut self, idg: &mut IdGen) -> Result<NextTrial> {
+        let rng = &mut self.rng;
+        let params = self
+            .optimizers
+            .iter_mut()
+            .map(|o| o.ask(rng).map_err(|e| ErrorKind::Bug.cause(e).into()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let trial = NextTrial {
+            id: idg.generate(),
+            params: Params::new(params.clone()),
+            next_step: Some(self.problem.steps.last()),
+        };
+        self.evaluating.insert(trial.id, params);
+        Ok(trial)
+    }
+
+    fn tell(&mut self, trial: EvaluatedTrial) -> Result<()> {
+        let params = self.evaluating.remove(&trial.id).ok_or_else(|| {
+            ErrorKind::InvalidInput.cause(format!("unknown trial {:?}", trial.id))
+        })?;
+        for (o, p) in self.optimizers.iter_mut().zip(params.into_iter()) {
+            o.tell(p, trial.values[0])
+                .map_err(|e| ErrorKind::InvalidInput.cause(e))?;
+        }
+        Ok(())
+    }
+}
+
+fn create_optimizer(param: &Variable) -> Result<TpeOptimizer> {
+    let invalid = |e| ErrorKind::InvalidInput.cause(e);
+    let range = match param.range() {
+        domain::Range::Continuous { low, high } => match param.distribution() {
+            domain::Distribution::Uniform => range(*low, *high).map_err(invalid)?,
+            domain::Distribution::LogUniform => {
+                crate::range::Range::log_uniform(*low, *high).map_err(invalid)?
+            }
+        },
+        domain::Range::Discrete { low, high } => {
+            crate::range::Range::discrete(*low as f64, *high as f64, 1.0).map_err(invalid)?
+        }
+        domain::Range::Categorical { choices } => {
+            let range = categorical_range(choices.len()).map_err(invalid)?;
+            return Ok(TpeOptimizer::new(histogram_estimator(), range));
+        }
+    };
+    Ok(TpeOptimizer::new(parzen_estimator(), range))
+}
@@ -7,29 +7,27 @@
 //! use rand::SeedableRng as _;
 //!
 //! # fn main() -> anyhow::Result<()> {
-//! let choices = [1, 10, 100];
 //! let mut optim0 =
 //!     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
-//! let mut optim1 =
-//!     tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(choices.len())?);
+//! let mut optim1 = tpe::CategoricalTpeOptimizer::new(&[1, 10, 100])?;
 //!
 //! fn objective(x: f64, y: i32) -> f64 {
 //!     x.powi(2) + y as f64
 //! }
 //!
-//! let mut best_value = std::f64::INFINITY;
+//! let mut best_value = f64::INFINITY;
 //! let mut rng = rand::rngs::StdRng::from_seed(Default::default());
 //! for _ in 0..100 {
 //!    let x = optim0.ask(&mut rng)?;
-//!    let y = optim1.ask(&mut rng)?;
+//!    let y = *optim1.ask(&mut rng)?;
 //!
-//!    let v = objective(x, choices[y as usize]);
+//!    let v = objective(x, y);
 //!    optim0.tell(x, v)?;
-//!    optim1.tell(y, v)?;
+//!    optim1.tell(&y, v)?;
 //!    best_value = best_value.min(v);
 //! }
 //!
-//! assert_eq!(best_value, 1.000054276671888);
+//! assert_eq!(best_value, 1.0001809759527722);
 //! # Ok(())
 //! # }
 //! ```
@@ -41,17 +39,56 @@
 //! - [Algorithms for Hyper-Parameter Optimization](https://papers.nips.cc/paper/4443-algorithms-for-hyper-parameter-optimization.pdf)
 //! - [Making a Science of Model Search: Hyperparameter Optimization in Hundreds of Dimensions for Vision Architectures](http://proceedings.mlr.press/v28/bergstra13.pdf)
 #![warn(missing_docs)]
-use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder, DensityEstimator};
+use crate::acquisition::{AcquisitionContext, AcquisitionFunction, LogDensityRatio};
 #[cfg(doc)]
-use crate::density_estimation::{HistogramEstimator, ParzenEstimator};
-use crate::range::{Range, RangeError};
+use crate::density_estimation::{
+    BetaKernelEstimator, DiscreteParzenEstimator, GmmEstimator, HistogramEstimator,
+    ParzenEstimator, ThompsonEstimator,
+};
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder, DensityEstimator};
+use crate::range::{Categorical, Range, RangeError};
+use crate::transform::ParamTransform;
 use ordered_float::OrderedFloat;
 use rand::distributions::Distribution;
-use rand::Rng;
+use rand::{Rng, RngCore};
 use std::num::NonZeroUsize;
+use std::sync::Arc;
 
+pub mod acquisition;
+#[cfg(feature = "argmin")]
+pub mod argmin;
+#[cfg(feature = "arrow")]
+mod arrow;
+pub mod benchmarks;
+#[cfg(feature = "cmaes")]
+pub mod cmaes;
 pub mod density_estimation;
+#[cfg(feature = "gp")]
+pub mod gp;
+pub mod hyperband;
+#[cfg(feature = "journal")]
+pub mod journal;
+#[cfg(feature = "kurobako")]
+pub mod kurobako;
+pub mod multi_objective;
+#[cfg(feature = "optuna")]
+pub mod optuna;
+pub mod permutation;
+#[cfg(feature = "plot")]
+pub mod plot;
+pub mod pruning;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod range;
+pub mod sampler;
+pub mod search_space;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod study;
+pub mod subset;
+pub mod transform;
 
 /// Creates a [`Range`] instance.
 pub fn range(start: f64, end: f64) -> Result<Range, RangeError> {
@@ -65,21 +102,90 @@ pub fn categorical_range(cardinality: usize) -> Result<Range, RangeError> {
     Range::new(0.0, cardinality as f64)
 }
 
+/// Creates a [`Range`] for an ordinal parameter (e.g., "small", "medium" and "large").
+///
+/// Unlike [`categorical_range`], this should be used with [`parzen_estimator`]:
+/// densities are estimated over the index scale, so neighboring levels share probability mass.
+///
+/// This is equivalent to `Range::discrete(0.0, levels as f64, 1.0)`.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let levels = ["small", "medium", "large", "x-large"];
+/// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::ordinal_range(levels.len())?);
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let i = optim.ask(&mut rng)?;
+///     let level = levels[i as usize];
+///     optim.tell(i, if level == "large" { 0.0 } else { 1.0 })?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn ordinal_range(levels: usize) -> Result<Range, RangeError> {
+    Range::discrete(0.0, levels as f64, 1.0)
+}
+
 /// Creates a [`DefaultEstimatorBuilder`] to build [`ParzenEstimator`] (for categorical parameter).
+///
+/// The uniform distribution is used instead while there are less than two samples
+/// (see [`DefaultEstimatorBuilder::uniform_fallback`]).
 pub fn parzen_estimator() -> DefaultEstimatorBuilder {
-    DefaultEstimatorBuilder::Parzen(Default::default())
+    DefaultEstimatorBuilder::Parzen(Default::default()).uniform_fallback(DEFAULT_FALLBACK_SAMPLES)
+}
+
+/// Creates a [`DefaultEstimatorBuilder`] to build [`DiscreteParzenEstimator`] (for integer parameter).
+///
+/// The range of the parameter should be made by `Range::discrete(start, end, 1.0)`.
+pub fn discrete_parzen_estimator() -> DefaultEstimatorBuilder {
+    DefaultEstimatorBuilder::DiscreteParzen(Default::default())
+}
+
+/// Creates a [`DefaultEstimatorBuilder`] to build [`BetaKernelEstimator`] (for bounded numerical parameter).
+///
+/// The uniform distribution is used instead while there are less than two samples
+/// (see [`DefaultEstimatorBuilder::uniform_fallback`]).
+pub fn beta_estimator() -> DefaultEstimatorBuilder {
+    DefaultEstimatorBuilder::Beta(Default::default()).uniform_fallback(DEFAULT_FALLBACK_SAMPLES)
 }
 
+/// Creates a [`DefaultEstimatorBuilder`] to build [`GmmEstimator`] (for numerical parameter).
+///
+/// The uniform distribution is used instead while there are less than two samples
+/// (see [`DefaultEstimatorBuilder::uniform_fallback`]).
+pub fn gmm_estimator() -> DefaultEstimatorBuilder {
+    DefaultEstimatorBuilder::Gmm(Default::default()).uniform_fallback(DEFAULT_FALLBACK_SAMPLES)
+}
+
+// With fewer samples, the estimators collapse to their prior kernels at the midpoint of the range.
+const DEFAULT_FALLBACK_SAMPLES: usize = 2;
+
 /// Creates a [`DefaultEstimatorBuilder`] to build [`HistogramEstimator`] (for numerical parameter).
 pub fn histogram_estimator() -> DefaultEstimatorBuilder {
     DefaultEstimatorBuilder::Histogram(Default::default())
 }
 
+/// Creates a [`DefaultEstimatorBuilder`] to build [`ThompsonEstimator`] (for categorical parameter with noisy results).
+pub fn thompson_estimator() -> DefaultEstimatorBuilder {
+    DefaultEstimatorBuilder::Thompson(Default::default())
+}
+
 /// Builder of [`TpeOptimizer`].
 #[derive(Debug)]
 pub struct TpeOptimizerBuilder {
     gamma: f64,
     candidates: usize,
+    prior_density: Option<PriorDensity>,
+    transform: Option<Arc<dyn ParamTransform>>,
+    out_of_range_policy: OutOfRangePolicy,
+    rank_weights: bool,
+    forgetting_window: Option<usize>,
+    local_penalization: Option<f64>,
+    acquisition: Arc<dyn AcquisitionFunction>,
+    record_operations: bool,
 }
 
 impl TpeOptimizerBuilder {
@@ -104,6 +210,172 @@ impl TpeOptimizerBuilder {
         self
     }
 
+    /// Sets a density which has been fitted on a previous (related) study.
+    ///
+    /// The density of superior parameters becomes the mixture of the estimated density (`1 - weight`) and
+    /// the given one (`weight`), so the optimizer is biased toward the regions which were good previously.
+    /// Note that `estimator` is evaluated in the warped space of the parameter range (see [`Range::warp`] and [`TpeOptimizerBuilder::transform`]),
+    /// and samples drawn from it outside of the range are discarded.
+    ///
+    /// The value of `weight` must be in the range from `0.0` to `1.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::density_estimation::{BuildDensityEstimator as _, ParzenEstimatorBuilder};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // Good parameters found in a previous study.
+    /// let previous_bests = [0.9, 1.0, 1.2];
+    /// let prior = ParzenEstimatorBuilder::new()
+    ///     .build_density_estimator(previous_bests.iter().copied(), tpe::range(-5.0, 5.0)?)?;
+    ///
+    /// let mut optim = tpe::TpeOptimizerBuilder::new()
+    ///     .prior_density(prior, 0.5)
+    ///     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prior_density<D>(&mut self, estimator: D, weight: f64) -> &mut Self
+    where
+        D: 'static + DensityEstimator + std::fmt::Debug + Send + Sync,
+    {
+        self.prior_density = Some(PriorDensity {
+            estimator: Arc::new(estimator),
+            weight,
+        });
+        self
+    }
+
+    /// Sets a transform applied to parameter values before estimating densities.
+    ///
+    /// Values passed to and returned from the optimizer are not transformed
+    /// (see the [`transform`](crate::transform) module).
+    pub fn transform<X>(&mut self, transform: X) -> &mut Self
+    where
+        X: 'static + ParamTransform,
+    {
+        self.transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Sets how parameters outside of the range are handled by [`TpeOptimizer::tell`].
+    ///
+    /// The default value is [`OutOfRangePolicy::Reject`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::OutOfRangePolicy;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizerBuilder::new()
+    ///     .out_of_range_policy(OutOfRangePolicy::Tolerance(1e-9))
+    ///     .build(tpe::parzen_estimator(), tpe::range(0.0, 1.0)?)?;
+    /// optim.tell(-1e-12, 0.5)?;
+    /// assert_eq!(optim.trials().next(), Some((0.0, 0.5)));
+    /// assert!(optim.tell(-0.1, 0.5).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn out_of_range_policy(&mut self, policy: OutOfRangePolicy) -> &mut Self {
+        self.out_of_range_policy = policy;
+        self
+    }
+
+    /// Makes better trials in the superior group have larger kernel weights.
+    ///
+    /// The weight of the `i`-th best trial (`i = 0, 1, ..`) of the `n` superior trials is `(n - i) / n`.
+    /// Note that the weights are ignored by estimators which don't support them
+    /// (see [`BuildDensityEstimator::build_density_estimator_weighted`]).
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizerBuilder::new()
+    ///     .rank_weights(true)
+    ///     .linear_forgetting(Some(25))
+    ///     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..50 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x * x)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rank_weights(&mut self, enabled: bool) -> &mut Self {
+        self.rank_weights = enabled;
+        self
+    }
+
+    /// Makes older trials have smaller kernel weights (Hyperopt's linear forgetting).
+    ///
+    /// The latest `window` trials have the weight `1.0`,
+    /// and the weights of the older ones ramp down linearly to `1 / n` (`n` is the number of the trials).
+    ///
+    /// The default value is `None` (i.e., all the trials have the same weight).
+    pub fn linear_forgetting(&mut self, window: Option<usize>) -> &mut Self {
+        self.forgetting_window = window;
+        self
+    }
+
+    /// Penalizes candidates close to the already selected (or pending) parameters in [`TpeOptimizer::ask_batch`].
+    ///
+    /// The acquisition value of a candidate `x` is multiplied by `1 - exp(-d^2 / 2)` for each penalized point `p`,
+    /// where `d` is the distance between `x` and `p` in the warped space divided by `width` times the width of the range.
+    /// So batch members spread across modes instead of clustering on one peak.
+    ///
+    /// The value of `width` must be positive.
+    /// The default value is `None` (i.e., no penalization).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizerBuilder::new()
+    ///     .local_penalization(Some(0.05))
+    ///     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x * x)?;
+    /// }
+    ///
+    /// let batch = optim.ask_batch(&mut rng, 4, &[])?;
+    /// assert_eq!(batch.len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn local_penalization(&mut self, width: Option<f64>) -> &mut Self {
+        self.local_penalization = width;
+        self
+    }
+
+    /// Sets the acquisition function used to score candidates.
+    ///
+    /// The default value is [`LogDensityRatio`] (see the [`acquisition`](crate::acquisition) module).
+    pub fn acquisition<A>(&mut self, acquisition: A) -> &mut Self
+    where
+        A: 'static + AcquisitionFunction,
+    {
+        self.acquisition = Arc::new(acquisition);
+        self
+    }
+
+    /// Makes the optimizer record the operations applied to it in memory
+    /// (see [`TpeOptimizer::operations`] and [`TpeOptimizer::replay`]).
+    ///
+    /// The default value is `false`.
+    pub fn record_operations(&mut self, enabled: bool) -> &mut Self {
+        self.record_operations = enabled;
+        self
+    }
+
     /// Builds a [`TpeOptimizer`] with the given settings.
     pub fn build<T>(
         &self,
@@ -116,6 +388,21 @@ impl TpeOptimizerBuilder {
         if !(0.0 <= self.gamma && self.gamma <= 1.0) {
             return Err(BuildError::GammaOutOfRange);
         }
+        if let Some(prior) = &self.prior_density {
+            if !(0.0 <= prior.weight && prior.weight <= 1.0) {
+                return Err(BuildError::PriorWeightOutOfRange);
+            }
+        }
+        if let Some(transform) = &self.transform {
+            transformed_range(transform.as_ref(), param_range.warped())
+                .map_err(|_| BuildError::InvalidTransform)?;
+        }
+        if self
+            .local_penalization
+            .is_some_and(|w| w.is_nan() || w <= 0.0)
+        {
+            return Err(BuildError::NonPositivePenalizationWidth);
+        }
 
         Ok(TpeOptimizer {
             param_range,
@@ -124,6 +411,19 @@ impl TpeOptimizerBuilder {
             is_sorted: false,
             gamma: self.gamma,
             candidates: NonZeroUsize::new(self.candidates).ok_or(BuildError::ZeroCandidates)?,
+            prior_density: self.prior_density.clone(),
+            warm_start_trials: Vec::new(),
+            warm_start_weight: 0.0,
+            transform: self.transform.clone(),
+            out_of_range_policy: self.out_of_range_policy,
+            rank_weights: self.rank_weights,
+            forgetting_window: self.forgetting_window,
+            local_penalization: self.local_penalization,
+            acquisition: self.acquisition.clone(),
+            last_ask: None,
+            last_ask_stats: None,
+            initial_param_range: param_range,
+            operations: self.record_operations.then(Vec::new),
         })
     }
 }
@@ -133,10 +433,79 @@ impl Default for TpeOptimizerBuilder {
         Self {
             gamma: 0.1,
             candidates: 24,
+            prior_density: None,
+            transform: None,
+            out_of_range_policy: OutOfRangePolicy::Reject,
+            rank_weights: false,
+            forgetting_window: None,
+            local_penalization: None,
+            acquisition: Arc::new(LogDensityRatio),
+            record_operations: false,
         }
     }
 }
 
+/// How parameters outside of the range are handled by [`TpeOptimizer::tell`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutOfRangePolicy {
+    /// Rejects the parameter with [`TellError::ParamOutOfRange`].
+    Reject,
+
+    /// Replaces the parameter with the nearest value contained in the range.
+    Clamp,
+
+    /// Same as [`OutOfRangePolicy::Clamp`] if the distance to the nearest value is at most the given tolerance,
+    /// otherwise same as [`OutOfRangePolicy::Reject`].
+    Tolerance(f64),
+}
+
+/// How trials outside of a new range are handled by [`TpeOptimizer::set_param_range`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationPolicy {
+    /// Keeps the trials as they are.
+    ///
+    /// Their parameters are not used to estimate densities (same as NaN parameters),
+    /// though their values still affect which trials are regarded as superior.
+    Keep,
+
+    /// Replaces the parameters with the nearest values contained in the new range.
+    Clamp,
+
+    /// Removes the trials.
+    Drop,
+
+    /// Maps all parameters linearly from the old warped range to the new one.
+    ///
+    /// This is useful when the cardinality of a categorical or ordinal parameter changes
+    /// but the relative positions of its values should be preserved
+    /// (e.g., the category `i` of `categorical_range(n)` becomes `i * m / n` of `categorical_range(m)`).
+    Rescale,
+}
+
+/// Which trials are kept by [`TpeOptimizer::restart`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// Removes all trials.
+    KeepNone,
+
+    /// Keeps the given number of the best trials and removes the others.
+    KeepTopK(usize),
+
+    /// Keeps all trials, but moves them to the warm-start trials with the given similarity weight
+    /// (see [`TpeOptimizer::warm_start`]).
+    ///
+    /// The densities of the native trials are estimated from scratch again,
+    /// so their bandwidths, which shrink as the trials increase, become wide again.
+    KeepAll {
+        /// Weight of each moved trial relative to a native trial (from `0.0` to `1.0`).
+        similarity_weight: f64,
+    },
+}
+
+const MAX_FILTERED_SAMPLINGS: usize = 100;
+const STAGNATION_WINDOW: usize = 20;
+const STAGNATION_BINS: usize = 10;
+
 /// Optimizer using TPE.
 ///
 /// This try to search out the parameter value which could minimize the evaluation result.
@@ -144,14 +513,27 @@ impl Default for TpeOptimizerBuilder {
 /// Note that an instance of TpeOptimizer can handle only one hyperparameter.
 /// So if you want to optimize multiple hyperparameters simultaneously,
 /// please create an optimizer for each hyperparameter.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TpeOptimizer<T = DefaultEstimatorBuilder> {
     param_range: Range,
     estimator_builder: T,
-    trials: Vec<Trial>,
+    trials: Vec<Observation>,
     is_sorted: bool,
     gamma: f64,
     candidates: NonZeroUsize,
+    prior_density: Option<PriorDensity>,
+    warm_start_trials: Vec<Observation>,
+    warm_start_weight: f64,
+    transform: Option<Arc<dyn ParamTransform>>,
+    out_of_range_policy: OutOfRangePolicy,
+    rank_weights: bool,
+    forgetting_window: Option<usize>,
+    local_penalization: Option<f64>,
+    acquisition: Arc<dyn AcquisitionFunction>,
+    last_ask: Option<LastAsk>,
+    last_ask_stats: Option<AskStats>,
+    initial_param_range: Range,
+    operations: Option<Vec<Operation>>,
 }
 
 impl<T: BuildDensityEstimator> TpeOptimizer<T> {
@@ -170,148 +552,2134 @@ impl<T: BuildDensityEstimator> TpeOptimizer<T> {
     /// results of randomly sampled observations to [`TpeOptimizer`] (via the [`tell`](TpeOptimizer::tell) method)
     /// to reduce bias due to too few samples.
     pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<f64, T::Error> {
-        if !self.is_sorted {
-            self.trials.sort_by_key(|t| OrderedFloat(t.value));
-            self.is_sorted = true;
-        }
-
-        let split_point = self.decide_split_point();
-        let (superiors, inferiors) = self.trials.split_at(split_point);
-
-        let superior_estimator = self.estimator_builder.build_density_estimator(
-            superiors.iter().map(|t| t.param).filter(|p| p.is_finite()),
-            self.param_range,
-        )?;
-        let inferior_estimator = self.estimator_builder.build_density_estimator(
-            inferiors.iter().map(|t| t.param).filter(|p| p.is_finite()),
-            self.param_range,
-        )?;
+        self.ask_filtered(rng, |_| true)
+            .map(|param| param.expect("unreachable"))
+    }
 
-        let param = (&superior_estimator)
-            .sample_iter(rng)
-            .take(self.candidates.get())
-            .map(|candidate| {
-                let superior_log_likelihood = superior_estimator.log_pdf(candidate);
-                let inferior_log_likelihood = inferior_estimator.log_pdf(candidate);
-                let ei = superior_log_likelihood - inferior_log_likelihood;
-                (ei, candidate)
-            })
-            .max_by_key(|(ei, _)| OrderedFloat(*ei))
-            .map(|(_, param)| param)
-            .expect("unreachable");
-        Ok(param)
+    /// Same as [`TpeOptimizer::ask`] except that only the candidates satisfying `predicate` are considered.
+    ///
+    /// If none of the candidates satisfies the predicate, new candidates are sampled
+    /// (up to 100 times, after which `None` is returned).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..20 {
+    ///     // E.g., the memory usage of the configurations with negative values is known to be too large.
+    ///     let x = optim.ask_filtered(&mut rng, |x| x >= 0.0)?.expect("not found");
+    ///     assert!(x >= 0.0);
+    ///     optim.tell(x, (x - 1.0).powi(2))?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ask_filtered<R, F>(&mut self, rng: &mut R, predicate: F) -> Result<Option<f64>, T::Error>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(f64) -> bool,
+    {
+        self.ask_penalized(rng, predicate, &[], None)
     }
 
-    /// Tells the evaluation result of a hyperparameter value to the optimizer.
+    /// Returns `n` parameter values to be evaluated in parallel.
     ///
-    /// Note that the `param` should be NaN if the hyperparameter was not used in the evaluation
-    /// (this could be happen when the entire search space is conditional).
-    pub fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
-        if value.is_nan() {
-            return Err(TellError::NanValue);
+    /// `pending` are the parameters which are being evaluated but whose results haven't been told yet.
+    /// If [`TpeOptimizerBuilder::local_penalization`] is set, candidates close to `pending` and
+    /// the already selected members of the batch are penalized.
+    /// Otherwise, this is equivalent to calling [`TpeOptimizer::ask`] `n` times.
+    pub fn ask_batch<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        n: usize,
+        pending: &[f64],
+    ) -> Result<Vec<f64>, T::Error> {
+        let mut penalized = pending.to_vec();
+        let mut batch = Vec::with_capacity(n);
+        for _ in 0..n {
+            let param = self
+                .ask_penalized(rng, |_| true, &penalized, None)?
+                .expect("unreachable");
+            penalized.push(param);
+            batch.push(param);
         }
+        Ok(batch)
+    }
+
+    fn ask_penalized<R, F>(
+        &mut self,
+        rng: &mut R,
+        mut predicate: F,
+        penalized: &[f64],
+        within: Option<Range>,
+    ) -> Result<Option<f64>, T::Error>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(f64) -> bool,
+    {
+        let start = instant_now();
+
+        let (superior_estimator, inferior_estimator) = self.build_estimators()?;
+        let range = self.param_range;
+        let warped_range = self.warped_range();
+        let penalization = self.local_penalization.map(|width| {
+            let points = penalized
+                .iter()
+                .filter(|p| !p.is_nan())
+                .map(|&p| self.warp(p))
+                .collect::<Vec<_>>();
+            (points, width * warped_range.width())
+        });
+        let within = within.map(|r| {
+            let (start, end) = (self.warp(r.start()), self.warp(r.end()));
+            (start.min(end), start.max(end))
+        });
+
+        let warm_start = self.build_warm_start_estimators()?;
 
-        if !(param.is_nan() || self.param_range.contains(param)) {
-            return Err(TellError::ParamOutOfRange {
+        let prior = self.prior_density.as_ref();
+        let rejected_draws = |warm_start: &Option<WarmStartEstimators<T::Estimator>>| {
+            superior_estimator.rejected_draws()
+                + warm_start.as_ref().map_or(0, |w| w.0.rejected_draws())
+        };
+        for retries in 0..MAX_FILTERED_SAMPLINGS {
+            let mut candidates = Vec::with_capacity(self.candidates.get());
+            if prior.is_none() && warm_start.is_none() {
+                superior_estimator.sample_n(rng, self.candidates.get(), &mut candidates);
+            } else {
+                candidates.extend(
+                    (0..self.candidates.get()).map(|_| match (prior, &warm_start) {
+                        (Some(prior), _) if rng.gen_bool(prior.weight) => {
+                            let candidate = prior.estimator.sample_dyn(&mut &mut *rng);
+                            if warped_range.contains(candidate) {
+                                candidate
+                            } else {
+                                superior_estimator.sample(rng)
+                            }
+                        }
+                        (_, Some((estimator, _, weight))) if rng.gen_bool(*weight) => {
+                            estimator.sample(rng)
+                        }
+                        _ => superior_estimator.sample(rng),
+                    }),
+                );
+            }
+            if let Some((start, end)) = within {
+                // Candidates outside of the sub-range are replaced with uniform samples from it.
+                for candidate in &mut candidates {
+                    if !(start..end).contains(candidate) {
+                        *candidate = if start < end {
+                            rng.gen_range(start..end)
+                        } else {
+                            start
+                        };
+                    }
+                }
+            }
+            let (candidates, params): (Vec<_>, Vec<_>) = candidates
+                .into_iter()
+                .map(|candidate| {
+                    let param = self.unwarp(candidate);
+                    if range.q().is_some() {
+                        // Evaluates the value which will be actually returned.
+                        (self.warp(param), param)
+                    } else {
+                        (candidate, param)
+                    }
+                })
+                .filter(|&(_, param)| predicate(param))
+                .unzip();
+            if candidates.is_empty() {
+                continue;
+            }
+
+            let scores = self.score_candidates(
+                &candidates,
+                &superior_estimator,
+                &inferior_estimator,
+                warm_start.as_ref(),
+            );
+            let mut scored = params
+                .into_iter()
+                .enumerate()
+                .map(|(i, param)| {
+                    let mut ei = scores[i];
+                    if let Some((points, width)) = &penalization {
+                        for p in points {
+                            let d = (candidates[i] - p) / width;
+                            ei += (1.0 - (-0.5 * d * d).exp()).ln();
+                        }
+                    }
+                    if ei.is_nan() {
+                        // Both densities are zero (possible with compactly supported kernels).
+                        ei = f64::NEG_INFINITY;
+                    }
+                    (ei, param)
+                })
+                .collect::<Vec<_>>();
+            let (score, param) = scored
+                .iter()
+                .copied()
+                .max_by_key(|(ei, _)| OrderedFloat(*ei))
+                .expect("unreachable");
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+            self.record_ask_stats(start, retries, &scored, rejected_draws(&warm_start));
+            self.record_operation(|| Operation::Ask { param });
+            self.last_ask = Some(LastAsk {
                 param,
-                range: self.param_range,
+                score,
+                candidates: scored,
             });
+            return Ok(Some(param));
         }
+        self.record_ask_stats(
+            start,
+            MAX_FILTERED_SAMPLINGS,
+            &[],
+            rejected_draws(&warm_start),
+        );
+        Ok(None)
+    }
 
-        self.trials.push(Trial { param, value });
-        self.is_sorted = false;
+    fn record_ask_stats(
+        &mut self,
+        start: Option<std::time::Instant>,
+        retries: usize,
+        scored: &[(f64, f64)],
+        rejected_draws: usize,
+    ) {
+        let mut stats = self.ask_stats(retries, scored, rejected_draws);
+        stats.elapsed = start.map(|t| t.elapsed());
+        self.last_ask_stats = Some(stats);
+    }
 
-        Ok(())
+    fn ask_stats(&self, retries: usize, scored: &[(f64, f64)], rejected_draws: usize) -> AskStats {
+        let split_point = self.decide_split_point();
+        let finite_scores = scored.iter().map(|s| s.0).filter(|s| s.is_finite());
+        AskStats {
+            superiors: split_point,
+            inferiors: self.trials.len() - split_point,
+            candidates: scored.len(),
+            retries,
+            rejected_draws,
+            best_score: finite_scores.clone().fold(f64::NAN, f64::max),
+            worst_score: finite_scores.fold(f64::NAN, f64::min),
+            elapsed: None,
+        }
     }
 
-    /// Retruns all told parameter and objective values.
+    /// Returns the statistics of the last [`TpeOptimizer::ask`] (or its variants).
     ///
-    /// Note that the order of items in the returned iterator doesn't reflect the order [`TpeOptimizer::tell`] called.
+    /// These are useful to monitor whether the sampler degenerates
+    /// (e.g., many retries of [`TpeOptimizer::ask_filtered`] or no spread of the candidate scores).
     ///
     /// # Examples
     ///
-    /// You can this method to store and load the state of a [`TpeOptimizer`].
-    ///
     /// ```
     /// # fn main() -> anyhow::Result<()> {
-    /// let temp_file = tempfile::NamedTempFile::new()?;
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
     /// let mut rng = rand::thread_rng();
-    ///
-    /// fn objective(x: f64) -> f64 {
-    ///     x.powi(2)
+    /// for _ in 0..20 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x.powi(2))?;
     /// }
     ///
-    /// {
-    ///     let mut optim =
-    ///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
-    ///
-    ///     // Runs 100 trials.
-    ///     for _ in 0..100 {
-    ///         let x = optim.ask(&mut rng)?;
-    ///         let v = objective(x);
-    ///         optim.tell(x, v)?;
-    ///     }
-    ///
-    ///     // Stores the trials.
-    ///     let mut file = std::fs::OpenOptions::new()
-    ///         .write(true)
-    ///         .open(temp_file.path())?;
-    ///     serde_json::to_writer(&mut file, &optim.trials().collect::<Vec<_>>())?;
-    /// }
+    /// let stats = optim.last_ask_stats().expect("asked");
+    /// assert_eq!(stats.superiors + stats.inferiors, 19);
+    /// assert_eq!(stats.candidates, 24);
+    /// assert_eq!(stats.retries, 0);
+    /// assert!(stats.score_spread() >= 0.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn last_ask_stats(&self) -> Option<&AskStats> {
+        self.last_ask_stats.as_ref()
+    }
+
+    /// Reports whether the optimization seems to have stopped making progress.
     ///
-    /// {
-    ///     let mut optim =
-    ///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// The report is computed from the last 20 trials (in the order they were told):
+    /// the entropy of their parameters and how far the incumbent (the best trial) moved during them.
+    /// Controllers can react to stagnation, e.g., by restarting, widening the range or stopping.
     ///
-    ///     // Loads the previous trials.
-    ///     let mut file = std::fs::File::open(temp_file.path())?;
-    ///     let trials: Vec<(f64, f64)> = serde_json::from_reader(&mut file)?;
-    ///     for (x, v) in trials {
-    ///         optim.tell(x, v)?;
-    ///     }
+    /// # Examples
     ///
-    ///     // Runs additional 100 trials.
-    ///     for _ in 0..100 {
-    ///         let x = optim.ask(&mut rng)?;
-    ///         let v = objective(x);
-    ///         optim.tell(x, v)?;
-    ///     }
-    ///     assert_eq!(optim.trials().count(), 200);
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// for i in 0..10 {
+    ///     optim.tell(i as f64 - 5.0, 1.0)?;
+    /// }
+    /// for _ in 0..30 {
+    ///     optim.tell(0.0, 2.0)?;
     /// }
+    ///
+    /// let report = optim.stagnation();
+    /// assert_eq!(report.trials_since_improvement, 39);
+    /// assert_eq!(report.suggestion_entropy, 0.0);
+    /// assert_eq!(report.incumbent_shift, 0.0);
+    /// assert!(report.is_stagnant(20, 0.5));
     /// # Ok(())
     /// # }
     /// ```
-    pub fn trials(&self) -> impl '_ + Iterator<Item = (f64, f64)> {
-        self.trials.iter().map(|t| (t.param, t.value))
-    }
+    pub fn stagnation(&self) -> StagnationReport {
+        let mut trials = self.trials.iter().collect::<Vec<_>>();
+        trials.sort_by_key(|t| t.seq);
+        let window = trials.len().min(STAGNATION_WINDOW);
+        let (earlier, recent) = trials.split_at(trials.len() - window);
 
-    fn decide_split_point(&self) -> usize {
-        (self.trials.len() as f64 * self.gamma).ceil() as usize
-    }
-}
+        let best = |trials: &[&Observation]| {
+            trials
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1.value.total_cmp(&b.1.value).then(a.0.cmp(&b.0)))
+                .map(|(i, t)| (i, t.param))
+        };
+        let trials_since_improvement = best(&trials).map_or(0, |(i, _)| trials.len() - 1 - i);
 
-#[derive(Debug, Clone)]
-struct Trial {
-    param: f64,
-    value: f64,
-}
+        let warped = self.warped_range();
+        let normalize = |param: f64| (self.warp(param) - warped.start()) / warped.width();
+        let incumbent_shift = match (best(earlier), best(&trials)) {
+            (Some((_, before)), Some((_, after))) => (normalize(after) - normalize(before)).abs(),
+            _ => f64::NAN,
+        };
 
-/// Possible errors during [`TpeOptimizerBuilder::build`].
-#[derive(Debug, Clone, thiserror::Error)]
-pub enum BuildError {
-    #[error("the value of `gamma` must be in the range from 0.0 to 1.0")]
-    /// The value of `gamma` must be in the range from `0.0` to `1.0`.
-    GammaOutOfRange,
+        let mut counts = [0usize; STAGNATION_BINS];
+        let mut total = 0;
+        for t in recent.iter().filter(|t| !t.param.is_nan()) {
+            let bin = (normalize(t.param) * STAGNATION_BINS as f64).floor() as usize;
+            counts[bin.min(STAGNATION_BINS - 1)] += 1;
+            total += 1;
+        }
+        let max_entropy = (total.min(STAGNATION_BINS) as f64).ln();
+        let suggestion_entropy = if max_entropy > 0.0 {
+            let entropy = counts
+                .iter()
+                .filter(|&&c| c > 0)
+                .map(|&c| {
+                    let p = c as f64 / total as f64;
+                    -p * p.ln()
+                })
+                .sum::<f64>();
+            entropy / max_entropy
+        } else {
+            f64::NAN
+        };
 
-    #[error("the value of `candidates` must be a positive integer")]
-    /// The value of `candidates` must be a positive integer.
-    ZeroCandidates,
-}
+        StagnationReport {
+            window,
+            trials_since_improvement,
+            suggestion_entropy,
+            incumbent_shift,
+        }
+    }
 
-/// Possible errors during [`TpeOptimizer::tell`].
-#[derive(Debug, Clone, thiserror::Error)]
-pub enum TellError {
-    #[error("the parameter value {param} is out of the range {range}")]
+    // Builds the densities of the warm-start trials and their mixing weight.
+    fn build_warm_start_estimators(
+        &self,
+    ) -> Result<Option<WarmStartEstimators<T::Estimator>>, T::Error> {
+        if self.warm_start_weight == 0.0 {
+            return Ok(None);
+        }
+
+        let range = self.param_range;
+        let warped_range = self.warped_range();
+        let split_point = (self.warm_start_trials.len() as f64 * self.gamma).ceil() as usize;
+        let (superiors, inferiors) = self.warm_start_trials.split_at(split_point);
+        let superior_estimator = self.estimator_builder.build_density_estimator(
+            superiors
+                .iter()
+                .filter(|t| range.contains(t.param))
+                .map(|t| self.warp(t.param)),
+            warped_range,
+        )?;
+        let inferior_estimator = self.estimator_builder.build_density_estimator(
+            inferiors
+                .iter()
+                .filter(|t| range.contains(t.param))
+                .map(|t| self.warp(t.param)),
+            warped_range,
+        )?;
+        let weight = self.warm_start_weight / (self.warm_start_weight + self.trials.len() as f64);
+        Ok(Some((superior_estimator, inferior_estimator, weight)))
+    }
+
+    // Returns the acquisition scores of the given (warped) candidates.
+    //
+    // The prior density and the warm-start densities are mixed into `l(x)` and `g(x)`.
+    fn score_candidates(
+        &self,
+        candidates: &[f64],
+        superior_estimator: &T::Estimator,
+        inferior_estimator: &T::Estimator,
+        warm_start: Option<&WarmStartEstimators<T::Estimator>>,
+    ) -> Vec<f64> {
+        // Both densities are evaluated for every candidate, so they are computed in batches.
+        let mut superior_log_likelihoods = vec![0.0; candidates.len()];
+        let mut inferior_log_likelihoods = vec![0.0; candidates.len()];
+        superior_estimator.log_pdf_batch(candidates, &mut superior_log_likelihoods);
+        inferior_estimator.log_pdf_batch(candidates, &mut inferior_log_likelihoods);
+        if let Some((superior, inferior, weight)) = warm_start {
+            let mut log_likelihoods = vec![0.0; candidates.len()];
+            superior.log_pdf_batch(candidates, &mut log_likelihoods);
+            for (l, w) in superior_log_likelihoods.iter_mut().zip(&log_likelihoods) {
+                *l = logaddexp(*l + (1.0 - weight).ln(), w + weight.ln());
+            }
+            inferior.log_pdf_batch(candidates, &mut log_likelihoods);
+            for (l, w) in inferior_log_likelihoods.iter_mut().zip(&log_likelihoods) {
+                *l = logaddexp(*l + (1.0 - weight).ln(), w + weight.ln());
+            }
+        }
+
+        let split_point = self.decide_split_point();
+        let context = AcquisitionContext {
+            gamma: self.gamma,
+            superiors: split_point,
+            inferiors: self.trials.len() - split_point,
+            width: self.warped_range().width(),
+        };
+        superior_log_likelihoods
+            .into_iter()
+            .zip(inferior_log_likelihoods)
+            .zip(candidates)
+            .map(
+                |((mut superior_log_likelihood, inferior_log_likelihood), &x)| {
+                    if let Some(prior) = &self.prior_density {
+                        superior_log_likelihood = logaddexp(
+                            superior_log_likelihood + (1.0 - prior.weight).ln(),
+                            prior.estimator.log_pdf(x) + prior.weight.ln(),
+                        );
+                    }
+                    self.acquisition.score(
+                        superior_log_likelihood,
+                        inferior_log_likelihood,
+                        &context,
+                    )
+                },
+            )
+            .collect()
+    }
+
+    /// Same as [`TpeOptimizer::ask_filtered`] except that only the values in `sub_range` are considered.
+    ///
+    /// Candidates are sampled from the superior density restricted to `sub_range`
+    /// (those falling outside of it are replaced with uniform samples from it),
+    /// while the densities are still estimated from all trials over the current range.
+    ///
+    /// `None` is returned if `sub_range` isn't a subrange of the current range
+    /// or no candidate inside it is found.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..20 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, (x - 1.0).powi(2))?;
+    /// }
+    ///
+    /// // E.g., a deployment temporarily allows only non-positive values.
+    /// let x = optim.ask_within(&mut rng, tpe::range(-5.0, 0.0)?)?.expect("not found");
+    /// assert!(x < 0.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ask_within<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        sub_range: Range,
+    ) -> Result<Option<f64>, T::Error> {
+        if !self.param_range.includes(sub_range) {
+            return Ok(None);
+        }
+        self.ask_penalized(rng, |param| sub_range.contains(param), &[], Some(sub_range))
+    }
+
+    /// Asks a categorical parameter excluding the categories whose elements in `enabled` are `false`.
+    ///
+    /// The `i`-th element of `enabled` corresponds to the `i`-th category (i.e., the parameter value `i`),
+    /// and categories beyond the length of `enabled` are regarded as disabled.
+    /// The trials of disabled categories are kept and still used to estimate densities.
+    ///
+    /// `None` is returned if no enabled category is found (see [`TpeOptimizer::ask_filtered`]).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim =
+    ///     tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(3)?);
+    /// optim.tell(0.0, 0.1)?;
+    /// optim.tell(1.0, 0.5)?;
+    /// optim.tell(2.0, 0.9)?;
+    ///
+    /// // The best category (`0`) isn't available now.
+    /// let mut rng = rand::thread_rng();
+    /// let x = optim.ask_masked(&mut rng, &[false, true, true])?.expect("not found");
+    /// assert_ne!(x, 0.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ask_masked<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        enabled: &[bool],
+    ) -> Result<Option<f64>, T::Error> {
+        if !enabled.contains(&true) {
+            return Ok(None);
+        }
+        self.ask_filtered(rng, |param| {
+            let i = param.floor();
+            i >= 0.0 && enabled.get(i as usize).copied().unwrap_or(false)
+        })
+    }
+
+    /// Tells the evaluation result of a hyperparameter value to the optimizer.
+    ///
+    /// Note that the `param` should be NaN if the hyperparameter was not used in the evaluation
+    /// (this could be happen when the entire search space is conditional).
+    pub fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::NanValue);
+        }
+
+        let param = self.validate(param)?;
+        let told_at = now();
+        self.record_operation(|| Operation::Tell {
+            trial: Trial::new(param, value),
+            told_at,
+        });
+        let seq = self.trials.len();
+        self.trials.push(Observation {
+            param,
+            value,
+            seq,
+            metadata: None,
+            told_at,
+        });
+        self.is_sorted = false;
+
+        Ok(())
+    }
+
+    /// Gives evaluation results of a related task to the optimizer.
+    ///
+    /// The given trials are modeled separately from the ones told via [`TpeOptimizer::tell`],
+    /// and both models are mixed when asking.
+    /// Each historical trial counts as `similarity_weight` native trials,
+    /// so the influence of the history decays as native trials accumulate.
+    ///
+    /// The value of `similarity_weight` must be in the range from `0.0` to `1.0`.
+    /// If this method is called multiple times, the weights of the trials are accumulated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    ///
+    /// // The results of a similar objective function.
+    /// let history = (0..100).map(|i| {
+    ///     let x = i as f64 / 10.0 - 5.0;
+    ///     (x, (x - 1.0).powi(2))
+    /// });
+    /// optim.warm_start(history, 0.5)?;
+    ///
+    /// let x = optim.ask(&mut rand::thread_rng())?;
+    /// optim.tell(x, x.powi(2))?;
+    /// assert_eq!(optim.trials().count(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warm_start<I>(&mut self, trials: I, similarity_weight: f64) -> Result<(), TellError>
+    where
+        I: IntoIterator<Item = (f64, f64)>,
+    {
+        if !(0.0..=1.0).contains(&similarity_weight) {
+            return Err(TellError::SimilarityWeightOutOfRange);
+        }
+
+        let mut trials = trials.into_iter().collect::<Vec<_>>();
+        for (param, value) in &mut trials {
+            if value.is_nan() {
+                return Err(TellError::NanValue);
+            }
+            *param = self.validate(*param)?;
+        }
+
+        self.record_operation(|| Operation::WarmStart {
+            trials: trials.clone(),
+            similarity_weight,
+        });
+        self.warm_start_weight += similarity_weight * trials.len() as f64;
+        self.warm_start_trials
+            .extend(trials.into_iter().map(|(param, value)| Observation {
+                param,
+                value,
+                seq: 0,
+                metadata: None,
+                told_at: None,
+            }));
+        self.warm_start_trials
+            .sort_by_key(|t| OrderedFloat(t.value));
+        Ok(())
+    }
+
+    /// Returns the current parameter range.
+    pub fn param_range(&self) -> Range {
+        self.param_range
+    }
+
+    /// Returns the score which [`TpeOptimizer::ask`] assigns to the given parameter value.
+    ///
+    /// The score is given by the acquisition function (`log(l(x) / g(x))` by default, see [`TpeOptimizerBuilder::acquisition`])
+    /// under the current model (including the prior density and the warm-start trials),
+    /// so externally generated candidates (e.g., grid points or human proposals) can be ranked consistently with the optimizer.
+    /// Values out of the range get negative infinity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// for i in 0..20 {
+    ///     let x = i as f64 / 2.0 - 5.0;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// assert!(optim.acquisition(0.0)? > optim.acquisition(4.0)?);
+    /// assert_eq!(optim.acquisition(10.0)?, f64::NEG_INFINITY);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn acquisition(&mut self, param: f64) -> Result<f64, T::Error> {
+        Ok(self.acquisition_batch(&[param])?[0])
+    }
+
+    /// Same as [`TpeOptimizer::acquisition`] except that the scores of all the given parameter values are computed at once,
+    /// so the densities are estimated only once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// for i in 0..20 {
+    ///     let x = i as f64 / 2.0 - 5.0;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// let scores = optim.acquisition_batch(&[0.0, 4.0, 10.0])?;
+    /// assert!(scores[0] > scores[1]);
+    /// assert_eq!(scores[2], f64::NEG_INFINITY);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn acquisition_batch(&mut self, params: &[f64]) -> Result<Vec<f64>, T::Error> {
+        let (superior_estimator, inferior_estimator) = self.build_estimators()?;
+        let warm_start = self.build_warm_start_estimators()?;
+
+        // Out-of-range values are scored at the midpoint and overwritten below.
+        let midpoint = (self.param_range.start() + self.param_range.end()) * 0.5;
+        let xs = params
+            .iter()
+            .map(|&p| {
+                self.warp(if self.param_range.contains(p) {
+                    p
+                } else {
+                    midpoint
+                })
+            })
+            .collect::<Vec<_>>();
+        let scores = self.score_candidates(
+            &xs,
+            &superior_estimator,
+            &inferior_estimator,
+            warm_start.as_ref(),
+        );
+        Ok(params
+            .iter()
+            .zip(scores)
+            .map(|(&p, score)| {
+                // NaN means that both densities are zero (possible with compactly supported kernels).
+                if !self.param_range.contains(p) || score.is_nan() {
+                    f64::NEG_INFINITY
+                } else {
+                    score
+                }
+            })
+            .collect())
+    }
+
+    /// Explains the parameter value returned by the last [`TpeOptimizer::ask`] (or its variants).
+    ///
+    /// The contribution of each superior trial is the decrease of the log superior density at the chosen value
+    /// when the trial is left out, so the trials with the largest contributions are the ones which attracted the choice.
+    /// The contributions are computed from the trials at the time of this call (so this should be called before telling the result),
+    /// and the trial weights (see [`TpeOptimizerBuilder::rank_weights`]) are not taken into account.
+    ///
+    /// `None` is returned if nothing has been asked yet.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// for i in 0..20 {
+    ///     let x = i as f64 / 2.0 - 5.0;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// let mut rng = rand::thread_rng();
+    /// let x = optim.ask(&mut rng)?;
+    /// let explanation = optim.explain_last()?.expect("asked");
+    /// assert_eq!(explanation.param, x);
+    /// assert_eq!(explanation.contributions.len(), 2);
+    /// assert!(explanation.runners_up.iter().all(|c| c.score <= explanation.score));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain_last(&mut self) -> Result<Option<Explanation>, T::Error> {
+        let Some(last) = self.last_ask.clone() else {
+            return Ok(None);
+        };
+        if !self.is_sorted {
+            self.trials.sort_by_key(|t| OrderedFloat(t.value));
+            self.is_sorted = true;
+        }
+
+        let range = self.param_range;
+        let warped_range = self.warped_range();
+        let x = self.warp(last.param);
+        let superiors = self.trials[..self.decide_split_point()]
+            .iter()
+            .filter(|t| range.contains(t.param))
+            .map(|t| (t.param, t.value, self.warp(t.param)))
+            .collect::<Vec<_>>();
+        let log_density = self
+            .estimator_builder
+            .build_density_estimator(superiors.iter().map(|t| t.2), warped_range)?
+            .log_pdf(x);
+        let mut contributions = Vec::with_capacity(superiors.len());
+        for (i, &(param, value, _)) in superiors.iter().enumerate() {
+            let others = superiors
+                .iter()
+                .enumerate()
+                .filter(move |&(j, _)| j != i)
+                .map(|(_, t)| t.2);
+            let estimator = self
+                .estimator_builder
+                .build_density_estimator(others, warped_range)?;
+            contributions.push(Contribution {
+                param,
+                value,
+                contribution: log_density - estimator.log_pdf(x),
+            });
+        }
+        contributions.sort_by(|a, b| b.contribution.total_cmp(&a.contribution));
+
+        let mut runners_up = Vec::<Candidate>::new();
+        for (score, param) in last.candidates {
+            if param != last.param && runners_up.iter().all(|c| c.param != param) {
+                runners_up.push(Candidate { param, score });
+            }
+        }
+        Ok(Some(Explanation {
+            param: last.param,
+            score: last.score,
+            contributions,
+            runners_up,
+        }))
+    }
+
+    /// Reports how well the superior and inferior densities fit their trials.
+    ///
+    /// A much lower held-out log-likelihood than the in-sample one indicates that the estimator overfits
+    /// (e.g., too narrow kernels), and low values in both indicate that the estimator is mis-specified
+    /// for the parameter.
+    /// Note that the trial weights (see [`TpeOptimizerBuilder::rank_weights`]) are not taken into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// for i in 0..20 {
+    ///     let x = i as f64 / 2.0 - 5.0;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// let diagnostics = optim.diagnostics()?;
+    /// assert_eq!(diagnostics.superior.samples + diagnostics.inferior.samples, 20);
+    /// let loo = diagnostics.superior.loo_log_likelihood.expect("enough samples");
+    /// assert!(loo <= diagnostics.superior.log_likelihood);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diagnostics(&mut self) -> Result<Diagnostics, T::Error> {
+        if !self.is_sorted {
+            self.trials.sort_by_key(|t| OrderedFloat(t.value));
+            self.is_sorted = true;
+        }
+
+        let range = self.param_range;
+        let split_point = self.decide_split_point();
+        let (superiors, inferiors) = self.trials.split_at(split_point);
+        let warp = |trials: &[Observation]| {
+            trials
+                .iter()
+                .filter(|t| range.contains(t.param))
+                .map(|t| self.warp(t.param))
+                .collect::<Vec<_>>()
+        };
+        Ok(Diagnostics {
+            superior: self.fit_diagnostics(&warp(superiors))?,
+            inferior: self.fit_diagnostics(&warp(inferiors))?,
+        })
+    }
+
+    fn fit_diagnostics(&self, xs: &[f64]) -> Result<FitDiagnostics, T::Error> {
+        let warped_range = self.warped_range();
+        let estimator = self
+            .estimator_builder
+            .build_density_estimator(xs.iter().copied(), warped_range)?;
+        let log_likelihood =
+            xs.iter().map(|&x| estimator.log_pdf(x)).sum::<f64>() / xs.len() as f64;
+
+        let loo_log_likelihood = if xs.len() < 2 {
+            None
+        } else {
+            let mut total = 0.0;
+            for (i, &x) in xs.iter().enumerate() {
+                let others = xs
+                    .iter()
+                    .enumerate()
+                    .filter(move |&(j, _)| j != i)
+                    .map(|(_, &x)| x);
+                let estimator = self
+                    .estimator_builder
+                    .build_density_estimator(others, warped_range)?;
+                total += estimator.log_pdf(x);
+            }
+            Some(total / xs.len() as f64)
+        };
+        Ok(FitDiagnostics {
+            samples: xs.len(),
+            log_likelihood,
+            loo_log_likelihood,
+        })
+    }
+
+    /// Shrinks the parameter range (e.g., around the current best parameter).
+    ///
+    /// `new_range` must be contained in the current range and have the same kind (e.g., log or discrete).
+    ///
+    /// Trials told so far are kept.
+    /// However, the parameters outside of the new range are discounted:
+    /// they are not used to estimate densities anymore (same as NaN parameters),
+    /// though their values still affect which trials are regarded as superior.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// optim.narrow_range(tpe::range(-1.0, 1.0)?)?;
+    /// let x = optim.ask(&mut rng)?;
+    /// assert!(-1.0 <= x && x < 1.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn narrow_range(&mut self, new_range: Range) -> Result<(), RangeError> {
+        if !self.param_range.includes(new_range) {
+            return Err(RangeError::NotSubrange);
+        }
+        self.record_operation(|| Operation::NarrowRange(new_range));
+        self.param_range = new_range;
+        Ok(())
+    }
+
+    /// Changes the parameter range, migrating the trials told so far according to `policy`.
+    ///
+    /// Unlike [`TpeOptimizer::narrow_range`], the new range doesn't have to be a subrange of the current one.
+    /// Trials inside the new range are kept as they are (except with [`MigrationPolicy::Rescale`]).
+    /// The trials given via [`TpeOptimizer::warm_start`] are migrated in the same way.
+    ///
+    /// If the transform set by [`TpeOptimizerBuilder::transform`] cannot map the new range
+    /// to a finite non-empty range, [`BuildError::InvalidTransform`] is returned and nothing is changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::MigrationPolicy;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(4)?);
+    /// for i in 0..4 {
+    ///     optim.tell(i as f64, i as f64)?;
+    /// }
+    ///
+    /// // The last category has been removed.
+    /// optim.set_param_range(tpe::categorical_range(3)?, MigrationPolicy::Drop)?;
+    /// assert_eq!(optim.trials().count(), 3);
+    ///
+    /// // Each category has been split into two.
+    /// optim.set_param_range(tpe::categorical_range(6)?, MigrationPolicy::Rescale)?;
+    /// let mut params = optim.trials().map(|t| t.0).collect::<Vec<_>>();
+    /// params.sort_by(|a, b| a.total_cmp(b));
+    /// assert_eq!(params, [0.0, 2.0, 4.0]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_param_range(
+        &mut self,
+        new_range: Range,
+        policy: MigrationPolicy,
+    ) -> Result<(), BuildError> {
+        if let Some(transform) = &self.transform {
+            transformed_range(transform.as_ref(), new_range.warped())
+                .map_err(|_| BuildError::InvalidTransform)?;
+        }
+        self.record_operation(|| Operation::SetParamRange {
+            range: new_range,
+            policy,
+        });
+        let old_range = self.param_range;
+        let migrate = |param: f64| -> Option<f64> {
+            if param.is_nan() {
+                return Some(param);
+            }
+            match policy {
+                MigrationPolicy::Rescale => {
+                    let (old, new) = (old_range.warped(), new_range.warped());
+                    let u = (old_range.warp(param) - old.start()) / old.width();
+                    let v = new_range.unwarp(new.start() + u * new.width());
+                    Some(
+                        new_range
+                            .unwarp(new_range.warp(v.clamp(new_range.start(), new_range.end()))),
+                    )
+                }
+                _ if new_range.contains(param) => Some(param),
+                MigrationPolicy::Keep => Some(param),
+                MigrationPolicy::Clamp => Some(
+                    new_range
+                        .unwarp(new_range.warp(param.clamp(new_range.start(), new_range.end()))),
+                ),
+                MigrationPolicy::Drop => None,
+            }
+        };
+
+        let mut trials = std::mem::take(&mut self.trials);
+        trials.sort_by_key(|t| t.seq);
+        self.trials = trials
+            .into_iter()
+            .filter_map(|t| {
+                Some(Observation {
+                    param: migrate(t.param)?,
+                    ..t
+                })
+            })
+            .enumerate()
+            .map(|(seq, t)| Observation { seq, ..t })
+            .collect();
+        self.is_sorted = false;
+
+        let n = self.warm_start_trials.len();
+        self.warm_start_trials = std::mem::take(&mut self.warm_start_trials)
+            .into_iter()
+            .filter_map(|t| {
+                Some(Observation {
+                    param: migrate(t.param)?,
+                    ..t
+                })
+            })
+            .collect();
+        if n > 0 {
+            self.warm_start_weight *= self.warm_start_trials.len() as f64 / n as f64;
+        }
+
+        self.param_range = new_range;
+        self.last_ask = None;
+        Ok(())
+    }
+
+    /// Restarts the optimization to escape from premature convergence, keeping the trials according to `policy`.
+    ///
+    /// The settings and the warm-start trials given via [`TpeOptimizer::warm_start`] are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::RestartPolicy;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..50 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    ///
+    /// optim.restart(RestartPolicy::KeepTopK(5))?;
+    /// assert_eq!(optim.trials().count(), 5);
+    ///
+    /// optim.restart(RestartPolicy::KeepAll { similarity_weight: 0.5 })?;
+    /// assert_eq!(optim.trials().count(), 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn restart(&mut self, policy: RestartPolicy) -> Result<(), TellError> {
+        match policy {
+            RestartPolicy::KeepNone => {
+                self.record_operation(|| Operation::Restart(policy));
+                self.trials.clear();
+            }
+            RestartPolicy::KeepTopK(k) => {
+                self.record_operation(|| Operation::Restart(policy));
+                self.trials.sort_by_key(|t| OrderedFloat(t.value));
+                self.trials.truncate(k);
+                self.trials.sort_by_key(|t| t.seq);
+                for (seq, t) in self.trials.iter_mut().enumerate() {
+                    t.seq = seq;
+                }
+            }
+            RestartPolicy::KeepAll { similarity_weight } => {
+                if !(0.0..=1.0).contains(&similarity_weight) {
+                    return Err(TellError::SimilarityWeightOutOfRange);
+                }
+                self.record_operation(|| Operation::Restart(policy));
+                let trials = std::mem::take(&mut self.trials);
+                self.warm_start_weight += similarity_weight * trials.len() as f64;
+                self.warm_start_trials
+                    .extend(trials.into_iter().map(|t| Observation { seq: 0, ..t }));
+                self.warm_start_trials
+                    .sort_by_key(|t| OrderedFloat(t.value));
+            }
+        }
+        self.is_sorted = false;
+        self.last_ask = None;
+        self.last_ask_stats = None;
+        Ok(())
+    }
+
+    /// Retruns all told parameter and objective values.
+    ///
+    /// Note that the order of items in the returned iterator doesn't reflect the order [`TpeOptimizer::tell`] called.
+    ///
+    /// # Examples
+    ///
+    /// You can this method to store and load the state of a [`TpeOptimizer`].
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let temp_file = tempfile::NamedTempFile::new()?;
+    /// let mut rng = rand::thread_rng();
+    ///
+    /// fn objective(x: f64) -> f64 {
+    ///     x.powi(2)
+    /// }
+    ///
+    /// {
+    ///     let mut optim =
+    ///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    ///
+    ///     // Runs 100 trials.
+    ///     for _ in 0..100 {
+    ///         let x = optim.ask(&mut rng)?;
+    ///         let v = objective(x);
+    ///         optim.tell(x, v)?;
+    ///     }
+    ///
+    ///     // Stores the trials.
+    ///     let mut file = std::fs::OpenOptions::new()
+    ///         .write(true)
+    ///         .open(temp_file.path())?;
+    ///     serde_json::to_writer(&mut file, &optim.trials().collect::<Vec<_>>())?;
+    /// }
+    ///
+    /// {
+    ///     let mut optim =
+    ///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    ///
+    ///     // Loads the previous trials.
+    ///     let mut file = std::fs::File::open(temp_file.path())?;
+    ///     let trials: Vec<(f64, f64)> = serde_json::from_reader(&mut file)?;
+    ///     for (x, v) in trials {
+    ///         optim.tell(x, v)?;
+    ///     }
+    ///
+    ///     // Runs additional 100 trials.
+    ///     for _ in 0..100 {
+    ///         let x = optim.ask(&mut rng)?;
+    ///         let v = objective(x);
+    ///         optim.tell(x, v)?;
+    ///     }
+    ///     assert_eq!(optim.trials().count(), 200);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn trials(&self) -> impl '_ + Iterator<Item = (f64, f64)> {
+        self.trials.iter().map(|t| (t.param, t.value))
+    }
+
+    /// Returns all told trials in the order [`TpeOptimizer::tell`] (or [`TpeOptimizer::import_trials`]) was called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// optim.tell(1.0, 1.0)?;
+    /// optim.tell(0.5, 0.25)?;
+    ///
+    /// let mut restored = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// restored.import_trials(optim.export_trials())?;
+    /// assert_eq!(restored.export_trials(), optim.export_trials());
+    /// assert_eq!(restored.export_trials()[0], tpe::Trial::new(1.0, 1.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn export_trials(&self) -> Vec<Trial> {
+        let mut trials = self.trials.iter().collect::<Vec<_>>();
+        trials.sort_by_key(|t| t.seq);
+        trials
+            .into_iter()
+            .map(|t| Trial {
+                param: t.param,
+                value: t.value,
+                metadata: t.metadata.clone(),
+            })
+            .collect()
+    }
+
+    /// Removes the last `n` trials told via [`TpeOptimizer::tell`] (or its variants) and returns them
+    /// in the order they were told.
+    ///
+    /// This is useful when the recent results turn out to be invalid (e.g., produced by a misconfigured evaluation).
+    /// If there are fewer than `n` trials, all of them are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// optim.tell(1.0, 1.0)?;
+    /// optim.tell(0.5, 0.25)?;
+    /// optim.tell(2.0, 4.0)?;
+    ///
+    /// let removed = optim.rollback(2);
+    /// assert_eq!(removed, [tpe::Trial::new(0.5, 0.25), tpe::Trial::new(2.0, 4.0)]);
+    /// assert_eq!(optim.export_trials(), [tpe::Trial::new(1.0, 1.0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rollback(&mut self, n: usize) -> Vec<Trial> {
+        self.record_operation(|| Operation::Rollback(n));
+        // `seq` is the position of a trial in the order of the tells.
+        let first_removed = self.trials.len().saturating_sub(n);
+        let (kept, mut removed): (Vec<_>, Vec<_>) = std::mem::take(&mut self.trials)
+            .into_iter()
+            .partition(|t| t.seq < first_removed);
+        self.trials = kept;
+        removed.sort_by_key(|t| t.seq);
+        self.last_ask = None;
+        removed
+            .into_iter()
+            .map(|t| Trial {
+                param: t.param,
+                value: t.value,
+                metadata: t.metadata,
+            })
+            .collect()
+    }
+
+    /// Returns the operations applied to this optimizer so far (in the order they were applied).
+    ///
+    /// The operations are recorded only if [`TpeOptimizerBuilder::record_operations`] is enabled.
+    pub fn operations(&self) -> &[Operation] {
+        self.operations.as_deref().unwrap_or(&[])
+    }
+
+    /// Reconstructs the state of this optimizer right after the first `n` operations
+    /// (see [`TpeOptimizer::operations`]).
+    ///
+    /// The returned optimizer has the same settings and records its own operations,
+    /// so it can be used to debug a past state or to fork the history at an earlier point.
+    /// Note that the statistics of the last ask (e.g., [`TpeOptimizer::last_ask_stats`]) are not reconstructed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rand::SeedableRng as _;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizerBuilder::new()
+    ///     .record_operations(true)
+    ///     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     optim.tell(x, x.powi(2))?;
+    /// }
+    /// assert_eq!(optim.operations().len(), 20);
+    ///
+    /// // Forks the history after the first five trials.
+    /// let mut fork = optim.replay(10);
+    /// assert_eq!(fork.export_trials(), optim.export_trials()[..5]);
+    /// let x = fork.ask(&mut rng)?;
+    /// fork.tell(x, (x - 1.0).powi(2))?;
+    /// assert_eq!(fork.trials().count(), 6);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replay(&self, n: usize) -> Self
+    where
+        T: Clone,
+    {
+        // The settings are kept and the state changed by the operations is reset.
+        let mut optim = self.clone();
+        optim.param_range = self.initial_param_range;
+        optim.trials.clear();
+        optim.is_sorted = false;
+        optim.warm_start_trials.clear();
+        optim.warm_start_weight = 0.0;
+        optim.last_ask = None;
+        optim.last_ask_stats = None;
+        if let Some(operations) = &mut optim.operations {
+            operations.clear();
+        }
+
+        for op in self.operations().iter().take(n) {
+            // The operations succeeded when they were applied, so they succeed again in the same state.
+            match op {
+                Operation::Ask { .. } => optim.record_operation(|| op.clone()),
+                Operation::Tell { trial, told_at } => optim
+                    .import_observations(std::iter::once((trial.clone(), *told_at)))
+                    .expect("unreachable"),
+                Operation::WarmStart {
+                    trials,
+                    similarity_weight,
+                } => optim
+                    .warm_start(trials.iter().copied(), *similarity_weight)
+                    .expect("unreachable"),
+                Operation::NarrowRange(range) => optim.narrow_range(*range).expect("unreachable"),
+                Operation::SetParamRange { range, policy } => {
+                    optim.set_param_range(*range, *policy).expect("unreachable")
+                }
+                Operation::Restart(policy) => optim.restart(*policy).expect("unreachable"),
+                Operation::Rollback(n) => {
+                    optim.rollback(*n);
+                }
+            }
+        }
+        optim
+    }
+
+    fn record_operation<F>(&mut self, f: F)
+    where
+        F: FnOnce() -> Operation,
+    {
+        if let Some(operations) = &mut self.operations {
+            operations.push(f());
+        }
+    }
+
+    /// Tells the given trials (e.g., exported by [`TpeOptimizer::export_trials`]) to the optimizer.
+    ///
+    /// If any of the trials is invalid, no trials are told.
+    pub fn import_trials<I>(&mut self, trials: I) -> Result<(), TellError>
+    where
+        I: IntoIterator<Item = Trial>,
+    {
+        self.import_observations(trials.into_iter().map(|trial| (trial, None)))
+    }
+
+    fn import_observations<I>(&mut self, trials: I) -> Result<(), TellError>
+    where
+        I: IntoIterator<Item = (Trial, Option<std::time::SystemTime>)>,
+    {
+        let mut observations = Vec::new();
+        for (trial, told_at) in trials {
+            if trial.value.is_nan() {
+                return Err(TellError::NanValue);
+            }
+            observations.push(Observation {
+                param: self.validate(trial.param)?,
+                value: trial.value,
+                seq: self.trials.len() + observations.len(),
+                metadata: trial.metadata,
+                told_at,
+            });
+        }
+        for o in &observations {
+            self.record_operation(|| Operation::Tell {
+                trial: Trial {
+                    param: o.param,
+                    value: o.value,
+                    metadata: o.metadata.clone(),
+                },
+                told_at: o.told_at,
+            });
+        }
+        self.trials.extend(observations);
+        self.is_sorted = false;
+        Ok(())
+    }
+
+    /// Writes the told trials to `writer` in CSV format.
+    ///
+    /// The output has a header row `param,value,metadata` followed by the trials
+    /// in the same order as [`TpeOptimizer::export_trials`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// optim.tell(1.0, 1.0)?;
+    /// optim.tell(0.5, 0.25)?;
+    ///
+    /// let mut csv = Vec::new();
+    /// optim.to_csv(&mut csv)?;
+    /// assert_eq!(String::from_utf8(csv.clone())?, "param,value,metadata\n1,1,\n0.5,0.25,\n");
+    ///
+    /// let restored = tpe::TpeOptimizer::from_csv(&csv[..], tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// assert_eq!(restored.export_trials(), optim.export_trials());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: std::io::Write>(&self, writer: W) -> Result<(), CsvError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(["param", "value", "metadata"])?;
+        for trial in self.export_trials() {
+            writer.write_record([
+                trial.param.to_string().as_str(),
+                trial.value.to_string().as_str(),
+                trial.metadata.as_deref().unwrap_or(""),
+            ])?;
+        }
+        writer.flush().map_err(csv::Error::from)?;
+        Ok(())
+    }
+
+    /// Makes a new [`TpeOptimizer`] instance that is warm-started with the trials read from `reader` in CSV format.
+    ///
+    /// The CSV must have a header row containing `param` and `value` columns.
+    /// An optional `metadata` column is kept as [`Trial::metadata`] (empty cells are treated as no metadata),
+    /// and other columns are ignored.
+    #[cfg(feature = "csv")]
+    pub fn from_csv<R: std::io::Read>(
+        reader: R,
+        estimator_builder: T,
+        param_range: Range,
+    ) -> Result<Self, CsvError> {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let column = |name: &str| headers.iter().position(|h| h.trim() == name);
+        let param_column = column("param").ok_or(CsvError::MissingColumn { name: "param" })?;
+        let value_column = column("value").ok_or(CsvError::MissingColumn { name: "value" })?;
+        let metadata_column = column("metadata");
+
+        let mut trials = Vec::new();
+        for (i, record) in reader.records().enumerate() {
+            let record = record?;
+            let number = |column: usize| {
+                record
+                    .get(column)
+                    .and_then(|x| x.trim().parse::<f64>().ok())
+                    .ok_or(CsvError::InvalidRecord { row: i + 1 })
+            };
+            trials.push(Trial {
+                param: number(param_column)?,
+                value: number(value_column)?,
+                metadata: metadata_column
+                    .and_then(|c| record.get(c))
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.to_owned()),
+            });
+        }
+
+        let mut optim = Self::new(estimator_builder, param_range);
+        optim.import_trials(trials)?;
+        Ok(optim)
+    }
+
+    #[cfg(feature = "journal")]
+    /// Makes a new [`TpeOptimizer`] instance that is told the trials recorded in the [`Journal`](journal::Journal) file at `path`.
+    ///
+    /// A truncated last line (e.g., caused by a crash during writing) is ignored.
+    pub fn resume_from_journal<P: AsRef<std::path::Path>>(
+        path: P,
+        estimator_builder: T,
+        param_range: Range,
+    ) -> Result<Self, journal::JournalError> {
+        let content = std::fs::read_to_string(path)?;
+        let mut trials = Vec::new();
+        for (i, line) in content.split_inclusive('\n').enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(journal::Entry::Tell {
+                    param,
+                    value,
+                    metadata,
+                }) => trials.push(Trial {
+                    param,
+                    value,
+                    metadata,
+                }),
+                Ok(_) => {}
+                Err(_) if !line.ends_with('\n') => break,
+                Err(e) => {
+                    return Err(journal::JournalError::Json {
+                        line: i + 1,
+                        source: e,
+                    })
+                }
+            }
+        }
+
+        let mut optim = Self::new(estimator_builder, param_range);
+        optim.import_trials(trials)?;
+        Ok(optim)
+    }
+
+    /// Returns the told trials as an Arrow [`RecordBatch`](arrow_array::RecordBatch).
+    ///
+    /// The batch has the following columns (one row per trial in the order [`TpeOptimizer::export_trials`] returns):
+    ///
+    /// - `trial_id` (`UInt64`): zero-based sequence number of the trial
+    /// - `param` (`Float64`)
+    /// - `value` (`Float64`)
+    /// - `timestamp` (`Timestamp(Millisecond, "UTC")`, nullable): when the trial was told
+    ///   (null for imported trials)
+    /// - `metadata` (`Utf8`, nullable)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// optim.tell(1.0, 1.0)?;
+    /// optim.tell(0.5, 0.25)?;
+    ///
+    /// let batch = optim.to_record_batch();
+    /// assert_eq!(batch.num_rows(), 2);
+    /// assert_eq!(batch.schema().field(1).name(), "param");
+    ///
+    /// let mut parquet = Vec::new();
+    /// optim.write_parquet(&mut parquet)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn to_record_batch(&self) -> arrow_array::RecordBatch {
+        let mut trials = self.trials.iter().collect::<Vec<_>>();
+        trials.sort_by_key(|t| t.seq);
+        arrow::record_batch(&trials)
+    }
+
+    /// Writes the told trials to `writer` as a Parquet file (see [`TpeOptimizer::to_record_batch`] for the columns).
+    #[cfg(feature = "arrow")]
+    pub fn write_parquet<W>(&self, writer: W) -> Result<(), parquet::errors::ParquetError>
+    where
+        W: std::io::Write + Send,
+    {
+        let batch = self.to_record_batch();
+        let mut writer = parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+
+    /// Returns `n` parameter values to be evaluated as an array.
+    ///
+    /// This is equivalent to calling [`TpeOptimizer::ask`] `n` times without telling results in between.
+    #[cfg(feature = "ndarray")]
+    pub fn ask_array<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+        n: usize,
+    ) -> Result<ndarray::Array1<f64>, T::Error> {
+        let mut params = Vec::with_capacity(n);
+        for _ in 0..n {
+            params.push(self.ask(rng)?);
+        }
+        Ok(ndarray::Array1::from(params))
+    }
+
+    /// Tells the evaluation results given as a column of parameter values and a column of objective values.
+    ///
+    /// If any of the trials is invalid, no trials are told.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::array;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?);
+    /// let params = array![-1.0, 0.0, 1.0];
+    /// let values = params.mapv(|x: f64| x.powi(2));
+    /// optim.tell_array(params.view(), values.view())?;
+    /// assert_eq!(optim.trials().count(), 3);
+    ///
+    /// let xs = optim.ask_array(&mut rand::thread_rng(), 4)?;
+    /// assert_eq!(xs.len(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn tell_array(
+        &mut self,
+        params: ndarray::ArrayView1<f64>,
+        values: ndarray::ArrayView1<f64>,
+    ) -> Result<(), TellError> {
+        if params.len() != values.len() {
+            return Err(TellError::LengthMismatch {
+                params: params.len(),
+                values: values.len(),
+            });
+        }
+        self.import_trials(
+            params
+                .iter()
+                .zip(values.iter())
+                .map(|(&param, &value)| Trial::new(param, value)),
+        )
+    }
+
+    /// Same as [`TpeOptimizer::warm_start`] except that the trials are given as columns.
+    #[cfg(feature = "ndarray")]
+    pub fn warm_start_array(
+        &mut self,
+        params: ndarray::ArrayView1<f64>,
+        values: ndarray::ArrayView1<f64>,
+        similarity_weight: f64,
+    ) -> Result<(), TellError> {
+        if params.len() != values.len() {
+            return Err(TellError::LengthMismatch {
+                params: params.len(),
+                values: values.len(),
+            });
+        }
+        self.warm_start(
+            params.iter().copied().zip(values.iter().copied()),
+            similarity_weight,
+        )
+    }
+
+    // Returns the parameter to be stored (which may be clamped according to the policy).
+    pub(crate) fn validate(&self, param: f64) -> Result<f64, TellError> {
+        let range = self.param_range;
+        if param.is_nan() || range.contains(param) {
+            return Ok(param);
+        }
+
+        let clamped = range.unwarp(range.warp(param.clamp(range.start(), range.end())));
+        match self.out_of_range_policy {
+            OutOfRangePolicy::Clamp => Ok(clamped),
+            OutOfRangePolicy::Tolerance(eps) if (clamped - param).abs() <= eps => Ok(clamped),
+            _ => Err(TellError::ParamOutOfRange { param, range }),
+        }
+    }
+
+    // Samples a parameter from the density estimated without any trials.
+    pub(crate) fn sample_prior<R: Rng + ?Sized>(&self, rng: &mut R) -> Result<f64, T::Error> {
+        let estimator = self
+            .estimator_builder
+            .build_density_estimator(std::iter::empty(), self.warped_range())?;
+        Ok(self.unwarp(estimator.sample(rng)))
+    }
+
+    fn build_estimators(&mut self) -> Result<(T::Estimator, T::Estimator), T::Error> {
+        if !self.is_sorted {
+            self.trials.sort_by_key(|t| OrderedFloat(t.value));
+            self.is_sorted = true;
+        }
+
+        let range = self.param_range;
+        let warped_range = self.warped_range();
+        let split_point = self.decide_split_point();
+        let (superiors, inferiors) = self.trials.split_at(split_point);
+
+        if !self.rank_weights && self.forgetting_window.is_none() {
+            let superior_estimator = self.estimator_builder.build_density_estimator(
+                superiors
+                    .iter()
+                    .filter(|t| range.contains(t.param))
+                    .map(|t| self.warp(t.param)),
+                warped_range,
+            )?;
+            let inferior_estimator = self.estimator_builder.build_density_estimator(
+                inferiors
+                    .iter()
+                    .filter(|t| range.contains(t.param))
+                    .map(|t| self.warp(t.param)),
+                warped_range,
+            )?;
+            Ok((superior_estimator, inferior_estimator))
+        } else {
+            let superior_weights = self.trial_weights(superiors, self.rank_weights);
+            let inferior_weights = self.trial_weights(inferiors, false);
+            let superior_estimator = self.estimator_builder.build_density_estimator_weighted(
+                superiors
+                    .iter()
+                    .zip(superior_weights.iter().copied())
+                    .filter(|(t, _)| range.contains(t.param))
+                    .map(|(t, w)| (self.warp(t.param), w)),
+                warped_range,
+            )?;
+            let inferior_estimator = self.estimator_builder.build_density_estimator_weighted(
+                inferiors
+                    .iter()
+                    .zip(inferior_weights.iter().copied())
+                    .filter(|(t, _)| range.contains(t.param))
+                    .map(|(t, w)| (self.warp(t.param), w)),
+                warped_range,
+            )?;
+            Ok((superior_estimator, inferior_estimator))
+        }
+    }
+
+    fn warp(&self, param: f64) -> f64 {
+        let v = self.param_range.warp(param);
+        match &self.transform {
+            None => v,
+            Some(t) => t.warp(v),
+        }
+    }
+
+    fn unwarp(&self, v: f64) -> f64 {
+        let v = match &self.transform {
+            None => v,
+            Some(t) => t.unwarp(v),
+        };
+        self.param_range.unwarp(v)
+    }
+
+    fn warped_range(&self) -> Range {
+        let range = self.param_range.warped();
+        match &self.transform {
+            None => range,
+            Some(t) => transformed_range(t.as_ref(), range).expect("unreachable"),
+        }
+    }
+
+    // `trials` must be sorted by their values.
+    fn trial_weights(&self, trials: &[Observation], rank: bool) -> Vec<f64> {
+        let n = trials.len() as f64;
+        let total = self.trials.len();
+        trials
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let mut w = 1.0;
+                if rank {
+                    w *= (n - i as f64) / n;
+                }
+                if let Some(window) = self.forgetting_window {
+                    let ramp = total.saturating_sub(window);
+                    if t.seq < ramp {
+                        let lowest = 1.0 / total as f64;
+                        let step = if ramp > 1 {
+                            t.seq as f64 / (ramp - 1) as f64
+                        } else {
+                            0.0
+                        };
+                        w *= lowest + (1.0 - lowest) * step;
+                    }
+                }
+                w
+            })
+            .collect()
+    }
+
+    fn decide_split_point(&self) -> usize {
+        (self.trials.len() as f64 * self.gamma).ceil() as usize
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Observation {
+    param: f64,
+    value: f64,
+
+    // The order in which the trial was told.
+    seq: usize,
+    metadata: Option<String>,
+
+    // `None` if the time is unknown (e.g., imported trials).
+    told_at: Option<std::time::SystemTime>,
+}
+
+// `SystemTime::now()` panics on `wasm32-unknown-unknown`.
+fn now() -> Option<std::time::SystemTime> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(std::time::SystemTime::now())
+    }
+}
+
+// `Instant::now()` panics on `wasm32-unknown-unknown`.
+fn instant_now() -> Option<std::time::Instant> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        None
+    } else {
+        Some(std::time::Instant::now())
+    }
+}
+
+/// Wrapper of [`TpeOptimizer`] for an integer parameter which takes a value in `[low, high]`.
+///
+/// The parameter is optimized over the discrete range `Range::discrete(low, high + 1, 1.0)`,
+/// so each integer is handled as the bin centered on it.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::IntTpeOptimizer::new(tpe::parzen_estimator(), 1, 8)?;
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..20 {
+///     let layers = optim.ask(&mut rng)?;
+///     assert!((1..=8).contains(&layers));
+///     optim.tell(layers, (layers - 3).pow(2) as f64)?;
+/// }
+///
+/// // A single value is also allowed.
+/// let mut optim = tpe::IntTpeOptimizer::new(tpe::parzen_estimator(), 3, 3)?;
+/// assert_eq!(optim.ask(&mut rng)?, 3);
+/// assert!(tpe::IntTpeOptimizer::new(tpe::parzen_estimator(), 3, 2).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct IntTpeOptimizer<T = DefaultEstimatorBuilder> {
+    optim: TpeOptimizer<T>,
+}
+
+impl<T: BuildDensityEstimator> IntTpeOptimizer<T> {
+    /// Makes a new [`IntTpeOptimizer`] with the default settings.
+    pub fn new(estimator_builder: T, low: i64, high: i64) -> Result<Self, BuildError> {
+        Self::with_builder(&TpeOptimizerBuilder::new(), estimator_builder, low, high)
+    }
+
+    /// Makes a new [`IntTpeOptimizer`] with the settings of the given builder.
+    pub fn with_builder(
+        builder: &TpeOptimizerBuilder,
+        estimator_builder: T,
+        low: i64,
+        high: i64,
+    ) -> Result<Self, BuildError> {
+        let range = Range::discrete(low as f64, high as f64 + 1.0, 1.0)
+            .map_err(|_| BuildError::InvalidIntRange { low, high })?;
+        Ok(Self {
+            optim: builder.build(estimator_builder, range)?,
+        })
+    }
+
+    /// Returns the next value of the parameter to be evaluated.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<i64, T::Error> {
+        self.optim.ask(rng).map(|param| param.round() as i64)
+    }
+
+    /// Tells the evaluation result of a parameter value to the optimizer.
+    pub fn tell(&mut self, param: i64, value: f64) -> Result<(), TellError> {
+        self.optim.tell(param as f64, value)
+    }
+
+    /// Returns the underlying optimizer.
+    pub fn inner(&self) -> &TpeOptimizer<T> {
+        &self.optim
+    }
+
+    /// Takes the underlying optimizer.
+    pub fn into_inner(self) -> TpeOptimizer<T> {
+        self.optim
+    }
+}
+
+/// Wrapper of [`TpeOptimizer`] for a categorical parameter which takes one of the given choices.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::CategoricalTpeOptimizer::new(&["relu", "gelu", "tanh"])?;
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let act = *optim.ask(&mut rng)?;
+///     let value = if act == "gelu" { 0.0 } else { 1.0 };
+///     optim.tell(&act, value)?;
+/// }
+/// assert!(optim.tell(&"sigmoid", 1.0).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CategoricalTpeOptimizer<C, T = DefaultEstimatorBuilder> {
+    categorical: Categorical<C>,
+    optim: TpeOptimizer<T>,
+}
+
+impl<C: Clone> CategoricalTpeOptimizer<C> {
+    /// Makes a new [`CategoricalTpeOptimizer`] which uses [`histogram_estimator`].
+    pub fn new(choices: &[C]) -> Result<Self, RangeError> {
+        Self::with_estimator(histogram_estimator(), choices.to_vec())
+    }
+}
+
+impl<C, T: BuildDensityEstimator> CategoricalTpeOptimizer<C, T> {
+    /// Makes a new [`CategoricalTpeOptimizer`] which uses the given estimator.
+    pub fn with_estimator(estimator_builder: T, choices: Vec<C>) -> Result<Self, RangeError> {
+        let categorical = Categorical::new(choices)?;
+        let optim = TpeOptimizer::new(estimator_builder, categorical.range());
+        Ok(Self { categorical, optim })
+    }
+
+    /// Returns the next choice to be evaluated.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<&C, T::Error> {
+        self.categorical.ask(&mut self.optim, rng)
+    }
+
+    /// Returns the choices.
+    pub fn choices(&self) -> &[C] {
+        self.categorical.choices()
+    }
+
+    /// Returns the underlying optimizer.
+    pub fn inner(&self) -> &TpeOptimizer<T> {
+        &self.optim
+    }
+
+    /// Takes the underlying optimizer.
+    pub fn into_inner(self) -> TpeOptimizer<T> {
+        self.optim
+    }
+}
+
+impl<C: PartialEq, T: BuildDensityEstimator> CategoricalTpeOptimizer<C, T> {
+    /// Tells the evaluation result of a choice to the optimizer.
+    pub fn tell(&mut self, choice: &C, value: f64) -> Result<(), TellError> {
+        self.categorical.tell(&mut self.optim, choice, value)
+    }
+}
+
+/// Evaluated trial of a [`TpeOptimizer`].
+///
+/// If the `serde` feature is enabled, a trial can be (de)serialized as a map like
+/// `{"param": 0.5, "value": 1.2, "metadata": "run-1"}` (`metadata` is optional).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trial {
+    /// Parameter value.
+    pub param: f64,
+
+    /// Evaluation result.
+    pub value: f64,
+
+    /// Arbitrary user data (e.g., the identifier of the run).
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub metadata: Option<String>,
+}
+
+impl Trial {
+    /// Makes a new [`Trial`] instance without metadata.
+    pub fn new(param: f64, value: f64) -> Self {
+        Self {
+            param,
+            value,
+            metadata: None,
+        }
+    }
+}
+
+/// Operation applied to a [`TpeOptimizer`] (see [`TpeOptimizer::operations`]).
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// A parameter value was asked (informational, as it doesn't change the state).
+    Ask {
+        /// Asked parameter value.
+        param: f64,
+    },
+
+    /// A trial was told (via [`TpeOptimizer::tell`] or [`TpeOptimizer::import_trials`]).
+    Tell {
+        /// Told trial.
+        trial: Trial,
+        /// Time when the trial was told (`None` if unknown, e.g., imported trials).
+        told_at: Option<std::time::SystemTime>,
+    },
+
+    /// [`TpeOptimizer::warm_start`] was called.
+    WarmStart {
+        /// Warm-start trials.
+        trials: Vec<(f64, f64)>,
+        /// Similarity weight.
+        similarity_weight: f64,
+    },
+
+    /// [`TpeOptimizer::narrow_range`] was called.
+    NarrowRange(Range),
+
+    /// [`TpeOptimizer::set_param_range`] was called.
+    SetParamRange {
+        /// New range.
+        range: Range,
+        /// Migration policy.
+        policy: MigrationPolicy,
+    },
+
+    /// [`TpeOptimizer::restart`] was called.
+    Restart(RestartPolicy),
+
+    /// [`TpeOptimizer::rollback`] was called.
+    Rollback(usize),
+}
+
+/// Statistics of an ask (see [`TpeOptimizer::last_ask_stats`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AskStats {
+    /// Number of the superior trials.
+    pub superiors: usize,
+
+    /// Number of the inferior trials.
+    pub inferiors: usize,
+
+    /// Number of the scored candidates (zero if no candidate satisfied the predicate).
+    pub candidates: usize,
+
+    /// Number of the rounds in which all candidates were rejected by the predicate
+    /// (see [`TpeOptimizer::ask_filtered`]).
+    pub retries: usize,
+
+    /// Number of the draws rejected by the estimator while sampling candidates
+    /// (see [`DensityEstimator::rejected_draws`]).
+    pub rejected_draws: usize,
+
+    /// Highest finite score of the candidates (NaN if there are no such candidates).
+    pub best_score: f64,
+
+    /// Lowest finite score of the candidates (NaN if there are no such candidates).
+    pub worst_score: f64,
+
+    /// Elapsed time of the ask (always `None` on `wasm32-unknown-unknown`).
+    pub elapsed: Option<std::time::Duration>,
+}
+
+impl AskStats {
+    /// Returns the difference between the highest and lowest scores of the candidates.
+    pub fn score_spread(&self) -> f64 {
+        self.best_score - self.worst_score
+    }
+}
+
+/// Progress signals of an optimization (see [`TpeOptimizer::stagnation`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagnationReport {
+    /// Number of the recent trials used to compute the signals.
+    pub window: usize,
+
+    /// Number of the trials told after the best one.
+    pub trials_since_improvement: usize,
+
+    /// Normalized entropy (from `0.0` to `1.0`) of the parameters of the recent trials
+    /// over ten equal-width bins of the warped range (NaN if it's undefined, e.g., with fewer than two trials).
+    ///
+    /// Low values mean that the suggestions have concentrated.
+    pub suggestion_entropy: f64,
+
+    /// Distance (relative to the width of the warped range) between the current incumbent parameter
+    /// and the incumbent before the recent trials (NaN if there were no trials before them).
+    pub incumbent_shift: f64,
+}
+
+impl StagnationReport {
+    /// Returns `true` if the best value hasn't improved for `patience` trials
+    /// and the suggestion entropy is below `min_entropy` (or undefined).
+    pub fn is_stagnant(&self, patience: usize, min_entropy: f64) -> bool {
+        self.trials_since_improvement >= patience
+            && (self.suggestion_entropy.is_nan() || self.suggestion_entropy < min_entropy)
+    }
+}
+
+/// Explanation of an asked parameter value (see [`TpeOptimizer::explain_last`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    /// Chosen parameter value.
+    pub param: f64,
+
+    /// Score of the chosen value (including local penalization terms).
+    pub score: f64,
+
+    /// Contributions of the superior trials to the density at the chosen value (in descending order).
+    pub contributions: Vec<Contribution>,
+
+    /// The other candidates (in descending order of their scores).
+    pub runners_up: Vec<Candidate>,
+}
+
+/// Contribution of a superior trial to the density at the chosen value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contribution {
+    /// Parameter value of the trial.
+    pub param: f64,
+
+    /// Evaluation result of the trial.
+    pub value: f64,
+
+    /// Decrease of the log superior density at the chosen value when this trial is left out.
+    pub contribution: f64,
+}
+
+/// Candidate evaluated during asking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    /// Parameter value.
+    pub param: f64,
+
+    /// Score of the value.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+struct LastAsk {
+    param: f64,
+    score: f64,
+    // Pairs of scores and parameters in descending order of the scores.
+    candidates: Vec<(f64, f64)>,
+}
+
+/// Goodness-of-fit of the densities estimated by a [`TpeOptimizer`] (see [`TpeOptimizer::diagnostics`]).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostics {
+    /// Fit of the density of the superior trials.
+    pub superior: FitDiagnostics,
+
+    /// Fit of the density of the inferior trials.
+    pub inferior: FitDiagnostics,
+}
+
+/// Goodness-of-fit of a density estimated from a group of trials.
+///
+/// The log-likelihoods are averaged over the trials and computed in the warped space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FitDiagnostics {
+    /// Number of the trials (excluding ones out of the range).
+    pub samples: usize,
+
+    /// Mean log-likelihood of the trials under the density estimated from all of them (NaN if no trials).
+    pub log_likelihood: f64,
+
+    /// Mean leave-one-out log-likelihood of the trials
+    /// (each trial is evaluated under the density estimated from the others).
+    ///
+    /// `None` if there are fewer than two trials.
+    pub loo_log_likelihood: Option<f64>,
+}
+
+// Superior and inferior densities of the warm-start trials, and their mixing weight.
+type WarmStartEstimators<E> = (E, E, f64);
+
+#[derive(Debug, Clone)]
+struct PriorDensity {
+    estimator: Arc<dyn DynDensityEstimator>,
+    weight: f64,
+}
+
+/// Object safe version of [`DensityEstimator`].
+trait DynDensityEstimator: std::fmt::Debug + Send + Sync {
+    fn log_pdf(&self, x: f64) -> f64;
+    fn sample_dyn(&self, rng: &mut dyn RngCore) -> f64;
+}
+
+impl<D> DynDensityEstimator for D
+where
+    D: DensityEstimator + std::fmt::Debug + Send + Sync,
+{
+    fn log_pdf(&self, x: f64) -> f64 {
+        DensityEstimator::log_pdf(self, x)
+    }
+
+    fn sample_dyn(&self, rng: &mut dyn RngCore) -> f64 {
+        self.sample(rng)
+    }
+}
+
+fn transformed_range(transform: &dyn ParamTransform, range: Range) -> Result<Range, RangeError> {
+    let transformed = Range::new(transform.warp(range.start()), transform.warp(range.end()))?;
+    if range.is_end_inclusive() {
+        Ok(transformed.with_inclusive_end())
+    } else {
+        Ok(transformed)
+    }
+}
+
+fn logaddexp(a: f64, b: f64) -> f64 {
+    let max = a.max(b);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+/// Possible errors during [`TpeOptimizerBuilder::build`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BuildError {
+    #[error("the value of `gamma` must be in the range from 0.0 to 1.0")]
+    /// The value of `gamma` must be in the range from `0.0` to `1.0`.
+    GammaOutOfRange,
+
+    #[error("the value of `candidates` must be a positive integer")]
+    /// The value of `candidates` must be a positive integer.
+    ZeroCandidates,
+
+    #[error("the weight of the prior density must be in the range from 0.0 to 1.0")]
+    /// The weight of the prior density must be in the range from `0.0` to `1.0`.
+    PriorWeightOutOfRange,
+
+    #[error("the transform must map the parameter range to a finite non-empty range")]
+    /// The transform must map the parameter range to a finite non-empty range.
+    InvalidTransform,
+
+    #[error("the width of the local penalization must be positive")]
+    /// The width of the local penalization must be positive.
+    NonPositivePenalizationWidth,
+
+    #[error("the integer range [{low}, {high}] is empty")]
+    /// The integer range is empty.
+    InvalidIntRange {
+        /// Lower bound.
+        low: i64,
+        /// Upper bound.
+        high: i64,
+    },
+}
+
+/// Possible errors during [`TpeOptimizer::tell`] and [`TpeOptimizer::warm_start`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum TellError {
+    #[error("the parameter value {param} is out of the range {range}")]
     /// The parameter value is out of the range.
     ParamOutOfRange {
         /// Actual parameter value.
@@ -323,6 +2691,51 @@ pub enum TellError {
     #[error("NaN value is not allowed")]
     /// NaN value is not allowed.
     NanValue,
+
+    #[error("unknown categorical choice")]
+    /// The choice is not contained in the [`Categorical`](crate::range::Categorical).
+    UnknownChoice,
+
+    #[error("the similarity weight must be in the range from 0.0 to 1.0")]
+    /// The similarity weight must be in the range from `0.0` to `1.0`.
+    SimilarityWeightOutOfRange,
+
+    #[error("the number of parameters ({params}) differs from the expected number ({values})")]
+    /// The number of parameters differs from the number of values
+    /// (or from the number of the optimizers of a [`Study`](crate::study::Study)).
+    LengthMismatch {
+        /// Number of parameters.
+        params: usize,
+        /// Number of values (or optimizers).
+        values: usize,
+    },
+}
+
+/// Possible errors during [`TpeOptimizer::to_csv`] and [`TpeOptimizer::from_csv`].
+#[cfg(feature = "csv")]
+#[derive(Debug, thiserror::Error)]
+pub enum CsvError {
+    #[error(transparent)]
+    /// Failed to read or write the CSV.
+    Csv(#[from] csv::Error),
+
+    #[error("the CSV header doesn't contain the {name:?} column")]
+    /// The CSV header doesn't contain a required column.
+    MissingColumn {
+        /// Name of the missing column.
+        name: &'static str,
+    },
+
+    #[error("the {row}-th record has a non-numeric `param` or `value`")]
+    /// A record has a non-numeric `param` or `value`.
+    InvalidRecord {
+        /// One-based index of the record (excluding the header).
+        row: usize,
+    },
+
+    #[error(transparent)]
+    /// A trial was rejected by the optimizer.
+    Tell(#[from] TellError),
 }
 
 #[cfg(test)]
@@ -330,6 +2743,34 @@ mod tests {
     use super::*;
     use rand::SeedableRng;
 
+    #[test]
+    fn replay_restores_told_times() -> anyhow::Result<()> {
+        let mut optim = TpeOptimizerBuilder::new()
+            .record_operations(true)
+            .build(parzen_estimator(), range(-5.0, 5.0)?)?;
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for _ in 0..5 {
+            let x = optim.ask(&mut rng)?;
+            optim.tell(x, x.powi(2))?;
+        }
+        optim.import_trials([Trial::new(1.0, 1.0)])?;
+
+        let replayed = optim.replay(optim.operations().len());
+        let told_at = |o: &TpeOptimizer| {
+            let mut trials = o
+                .trials
+                .iter()
+                .map(|t| (t.seq, t.told_at))
+                .collect::<Vec<_>>();
+            trials.sort_by_key(|&(seq, _)| seq);
+            trials.into_iter().map(|(_, t)| t).collect::<Vec<_>>()
+        };
+        assert_eq!(told_at(&replayed), told_at(&optim));
+        assert!(told_at(&optim)[5].is_none());
+        assert_eq!(replayed.operations().len(), optim.operations().len());
+        Ok(())
+    }
+
     #[test]
     fn optimizer_works() -> anyhow::Result<()> {
         let choices = [1, 10, 100];
@@ -341,7 +2782,7 @@ mod tests {
             x.powi(2) + y as f64
         }
 
-        let mut best_value = std::f64::INFINITY;
+        let mut best_value = f64::INFINITY;
         let mut rng = rand::rngs::StdRng::from_seed(Default::default());
         for _ in 0..100 {
             let x = optim0.ask(&mut rng)?;
@@ -352,7 +2793,7 @@ mod tests {
             optim1.tell(y, v)?;
             best_value = best_value.min(v);
         }
-        assert_eq!(best_value, 1.000054276671888);
+        assert_eq!(best_value, 1.0001809759527722);
 
         Ok(())
     }
@@ -404,4 +2845,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn set_param_range_rejects_range_invalid_for_transform() -> anyhow::Result<()> {
+        let mut optim = TpeOptimizerBuilder::new()
+            .transform(crate::transform::LogTransform)
+            .build(parzen_estimator(), range(1.0, 10.0)?)?;
+        optim.tell(2.0, 1.0)?;
+
+        let result = optim.set_param_range(range(-1.0, 10.0)?, MigrationPolicy::Keep);
+        assert!(matches!(result, Err(BuildError::InvalidTransform)));
+        assert_eq!(optim.param_range().start(), 1.0);
+
+        let x = optim.ask(&mut rand::rngs::StdRng::seed_from_u64(0))?;
+        assert!((1.0..10.0).contains(&x));
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn trial_serde_works() -> anyhow::Result<()> {
+        let mut optim = TpeOptimizer::new(parzen_estimator(), range(-5.0, 5.0)?);
+        optim.tell(1.0, 1.0)?;
+        optim.import_trials([Trial {
+            param: 2.0,
+            value: 4.0,
+            metadata: Some("run-1".to_owned()),
+        }])?;
+
+        let json = serde_json::to_string(&optim.export_trials())?;
+        assert_eq!(
+            json,
+            r#"[{"param":1.0,"value":1.0},{"param":2.0,"value":4.0,"metadata":"run-1"}]"#
+        );
+
+        let trials: Vec<Trial> = serde_json::from_str(&json)?;
+        assert_eq!(trials, optim.export_trials());
+        Ok(())
+    }
 }
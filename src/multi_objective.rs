@@ -0,0 +1,6 @@
+//! Utilities for multi-objective optimization.
+pub use self::hypervolume::{hypervolume, hypervolume_contributions};
+pub use self::parego::{augmented_tchebycheff, ParEgo, ScalarizationError};
+
+mod hypervolume;
+mod parego;
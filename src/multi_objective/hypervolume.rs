@@ -0,0 +1,91 @@
+use ordered_float::OrderedFloat;
+
+/// Returns the hypervolume dominated by `points` and bounded by `reference` (all objectives are minimized).
+///
+/// Points which don't strictly dominate `reference` don't contribute to the hypervolume.
+/// Any number of objectives is supported, though it's efficient only for a few (e.g., two or three) objectives.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::multi_objective::hypervolume;
+///
+/// let points = [vec![1.0, 3.0], vec![2.0, 2.0], vec![3.0, 1.0]];
+/// assert_eq!(hypervolume(&points, &[4.0, 4.0]), 6.0);
+///
+/// let points = [vec![1.0, 1.0, 1.0], vec![0.5, 2.0, 2.0]];
+/// assert_eq!(hypervolume(&points, &[2.0, 2.0, 2.0]), 1.0);
+/// ```
+pub fn hypervolume<P: AsRef<[f64]>>(points: &[P], reference: &[f64]) -> f64 {
+    let points = points
+        .iter()
+        .map(|p| p.as_ref())
+        .filter(|p| p.len() == reference.len() && p.iter().zip(reference).all(|(x, r)| x < r))
+        .collect::<Vec<_>>();
+    hypervolume_recursive(points, reference)
+}
+
+/// Returns the hypervolume exclusively contributed by each point
+/// (i.e., the decrease of the hypervolume when the point is removed).
+///
+/// Points with larger contributions are preferable when selecting a subset of the trials.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::multi_objective::hypervolume_contributions;
+///
+/// let points = [vec![1.0, 3.0], vec![2.0, 2.0], vec![3.0, 3.0]];
+/// assert_eq!(hypervolume_contributions(&points, &[4.0, 4.0]), [1.0, 2.0, 0.0]);
+/// ```
+pub fn hypervolume_contributions<P: AsRef<[f64]>>(points: &[P], reference: &[f64]) -> Vec<f64> {
+    let total = hypervolume(points, reference);
+    (0..points.len())
+        .map(|i| {
+            let others = points
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, p)| p.as_ref())
+                .collect::<Vec<_>>();
+            (total - hypervolume(&others, reference)).max(0.0)
+        })
+        .collect()
+}
+
+// `points` must strictly dominate `reference`.
+fn hypervolume_recursive(mut points: Vec<&[f64]>, reference: &[f64]) -> f64 {
+    let dim = reference.len();
+    if points.is_empty() || dim == 0 {
+        return 0.0;
+    }
+    if dim == 1 {
+        let min = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min);
+        return reference[0] - min;
+    }
+    if dim == 2 {
+        points.sort_by_key(|p| (OrderedFloat(p[0]), OrderedFloat(p[1])));
+        let mut volume = 0.0;
+        let mut y = reference[1];
+        for p in points {
+            if p[1] < y {
+                volume += (reference[0] - p[0]) * (y - p[1]);
+                y = p[1];
+            }
+        }
+        return volume;
+    }
+
+    // Slices the space along the last objective.
+    points.sort_by_key(|p| OrderedFloat(p[dim - 1]));
+    let mut volume = 0.0;
+    for i in 0..points.len() {
+        let upper = points.get(i + 1).map_or(reference[dim - 1], |p| p[dim - 1]);
+        let depth = upper - points[i][dim - 1];
+        if depth > 0.0 {
+            let projected = points[..=i].iter().map(|p| &p[..dim - 1]).collect();
+            volume += depth * hypervolume_recursive(projected, &reference[..dim - 1]);
+        }
+    }
+    volume
+}
@@ -0,0 +1,180 @@
+use rand::Rng;
+use rand_distr::Exp1;
+
+/// Scalarizer of objective vectors à la ParEGO.
+///
+/// This draws a random weight vector for each trial and converts the (normalized) objective values
+/// into a single value by [`augmented_tchebycheff`] scalarization,
+/// so that the single-objective optimizers can be used for multi-objective problems.
+///
+/// The objective values are normalized by the minimum and maximum values observed so far.
+/// As the bounds change over time, the values scalarized earlier become incomparable with the later ones,
+/// so (as the original ParEGO does) the whole history should be re-scalarized by [`ParEgo::rescalarize`]
+/// with a new weight vector in each iteration, and the single-objective model should be rebuilt from it.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::multi_objective::ParEgo;
+/// use tpe::study::{Study, Trial};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut scalarizer = ParEgo::new(2);
+/// let mut history = Vec::new();
+/// let mut rng = rand::thread_rng();
+/// for i in 0..20 {
+///     let weights = scalarizer.sample_weights(&mut rng);
+///     let values = scalarizer.rescalarize(&weights)?;
+///     let mut study = Study::with_seed(
+///         vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(0.0, 2.0)?)],
+///         i,
+///     );
+///     study.tell_many(
+///         history
+///             .iter()
+///             .cloned()
+///             .zip(values)
+///             .map(|(params, value)| Trial { params, value }),
+///     )?;
+///
+///     let params = study.ask()?;
+///     scalarizer.observe(&[params[0].powi(2), (params[0] - 2.0).powi(2)])?;
+///     history.push(params);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParEgo {
+    rho: f64,
+    min: Vec<f64>,
+    max: Vec<f64>,
+    history: Vec<Vec<f64>>,
+}
+
+impl ParEgo {
+    /// Makes a new [`ParEgo`] instance for `objectives` objectives.
+    pub fn new(objectives: usize) -> Self {
+        Self {
+            rho: 0.05,
+            min: vec![f64::INFINITY; objectives],
+            max: vec![f64::NEG_INFINITY; objectives],
+            history: Vec::new(),
+        }
+    }
+
+    /// Sets the coefficient of the augmentation term of the scalarization.
+    ///
+    /// The default value is `0.05`.
+    pub fn rho(&mut self, rho: f64) -> &mut Self {
+        self.rho = rho;
+        self
+    }
+
+    /// Draws a weight vector uniformly from the unit simplex.
+    pub fn sample_weights<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<f64> {
+        let mut weights = (0..self.min.len())
+            .map(|_| rng.sample::<f64, _>(Exp1))
+            .collect::<Vec<_>>();
+        let sum = weights.iter().sum::<f64>();
+        for w in &mut weights {
+            *w /= sum;
+        }
+        weights
+    }
+
+    /// Records the objective values of a trial and updates the bounds for normalization.
+    pub fn observe(&mut self, values: &[f64]) -> Result<(), ScalarizationError> {
+        self.check_len(values)?;
+        for ((min, max), &v) in self.min.iter_mut().zip(self.max.iter_mut()).zip(values) {
+            if !v.is_nan() {
+                *min = min.min(v);
+                *max = max.max(v);
+            }
+        }
+        self.history.push(values.to_vec());
+        Ok(())
+    }
+
+    /// Records `values` (see [`ParEgo::observe`]) and returns their scalarized value with the given weights.
+    ///
+    /// Note that the returned value is normalized by the current bounds,
+    /// which may differ from the bounds used for the values returned before (see [`ParEgo::rescalarize`]).
+    pub fn scalarize(
+        &mut self,
+        values: &[f64],
+        weights: &[f64],
+    ) -> Result<f64, ScalarizationError> {
+        self.check_len(weights)?;
+        self.observe(values)?;
+        Ok(self.scalarize_with_current_bounds(values, weights))
+    }
+
+    /// Returns the scalarized values of all the recorded trials (in the order of recording)
+    /// normalized by the current bounds.
+    pub fn rescalarize(&self, weights: &[f64]) -> Result<Vec<f64>, ScalarizationError> {
+        self.check_len(weights)?;
+        Ok(self
+            .history
+            .iter()
+            .map(|values| self.scalarize_with_current_bounds(values, weights))
+            .collect())
+    }
+
+    /// Returns the recorded objective values.
+    pub fn history(&self) -> &[Vec<f64>] {
+        &self.history
+    }
+
+    fn scalarize_with_current_bounds(&self, values: &[f64], weights: &[f64]) -> f64 {
+        let normalized = values
+            .iter()
+            .zip(self.min.iter().zip(&self.max))
+            .map(|(&v, (&min, &max))| {
+                if max > min {
+                    (v - min) / (max - min)
+                } else {
+                    0.0
+                }
+            })
+            .collect::<Vec<_>>();
+        augmented_tchebycheff(&normalized, weights, self.rho)
+    }
+
+    fn check_len(&self, xs: &[f64]) -> Result<(), ScalarizationError> {
+        if xs.len() != self.min.len() {
+            return Err(ScalarizationError::DimensionMismatch {
+                expected: self.min.len(),
+                actual: xs.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Returns `max_i(w_i * f_i) + rho * sum_i(w_i * f_i)`.
+///
+/// # Examples
+///
+/// ```
+/// let value = tpe::multi_objective::augmented_tchebycheff(&[1.0, 2.0], &[0.5, 0.5], 0.1);
+/// assert!((value - 1.15).abs() < 1e-12);
+/// ```
+pub fn augmented_tchebycheff(values: &[f64], weights: &[f64], rho: f64) -> f64 {
+    let weighted = values.iter().zip(weights).map(|(v, w)| v * w);
+    let max = weighted.clone().fold(f64::NEG_INFINITY, f64::max);
+    max + rho * weighted.sum::<f64>()
+}
+
+/// Possible errors during the scalarization of [`ParEgo`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ScalarizationError {
+    #[error("expected {expected} elements, but got {actual}")]
+    /// The number of elements differs from the number of objectives.
+    DimensionMismatch {
+        /// Number of objectives.
+        expected: usize,
+        /// Actual number of elements.
+        actual: usize,
+    },
+}
@@ -0,0 +1,547 @@
+//! Importers of trial histories recorded by [Optuna](https://optuna.org/)'s `JournalStorage` and RDB storage (the latter requires the `sqlite` feature).
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::study::Study;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let journal = r#"
+//! {"op_code": 0, "worker_id": "w", "study_name": "foo", "directions": [1]}
+//! {"op_code": 4, "worker_id": "w", "study_id": 0, "datetime_start": "2024-01-01T00:00:00"}
+//! {"op_code": 5, "worker_id": "w", "trial_id": 0, "param_name": "x", "param_value_internal": 0.5, "distribution": "{}"}
+//! {"op_code": 5, "worker_id": "w", "trial_id": 0, "param_name": "act", "param_value_internal": 1, "distribution": "{}"}
+//! {"op_code": 6, "worker_id": "w", "trial_id": 0, "state": 1, "values": [0.25]}
+//! "#;
+//! let trials = tpe::optuna::read_journal(journal.as_bytes(), "foo", &["x", "act"])?;
+//! assert_eq!(trials.len(), 1);
+//! assert_eq!(trials[0].params, [0.5, 1.0]);
+//!
+//! let mut study = Study::new(vec![
+//!     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!     tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(2)?),
+//! ]);
+//! study.tell_many(trials)?;
+//! assert_eq!(study.best_trial().map(|t| t.value), Some(0.25));
+//! # Ok(())
+//! # }
+//! ```
+#[cfg(feature = "sqlite")]
+use crate::range::{Range, RangeError};
+#[cfg(feature = "sqlite")]
+use crate::study::Study;
+use crate::study::Trial;
+#[cfg(feature = "sqlite")]
+use crate::{categorical_range, histogram_estimator, parzen_estimator, TpeOptimizer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::BufRead;
+#[cfg(feature = "sqlite")]
+use std::path::Path;
+
+const CREATE_STUDY: u64 = 0;
+const DELETE_STUDY: u64 = 1;
+const CREATE_TRIAL: u64 = 4;
+const SET_TRIAL_PARAM: u64 = 5;
+const SET_TRIAL_STATE_VALUES: u64 = 6;
+
+const DIRECTION_MAXIMIZE: u64 = 2;
+const STATE_COMPLETE: u64 = 1;
+
+/// Reads the completed trials of the study named `study_name` from an Optuna journal log.
+///
+/// The `i`-th element of the params of each returned trial is the internal representation of
+/// the parameter named `param_names[i]` (i.e., the value itself for numerical parameters and
+/// the index of the choice for categorical ones), or NaN if the trial didn't use the parameter.
+/// Parameters not contained in `param_names` are ignored.
+///
+/// The values of maximization studies are negated because [`TpeOptimizer`](crate::TpeOptimizer) minimizes.
+/// Trials which are not complete (e.g., pruned or failed ones) are skipped.
+/// If the study was deleted and then created again, only the trials of the last one are read.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let journal = r#"
+/// {"op_code": 0, "worker_id": "w", "study_name": "foo", "directions": [1]}
+/// {"op_code": 4, "worker_id": "w", "study_id": 0, "params": {"x": 0.1}, "state": 1, "values": [1.0]}
+/// {"op_code": 1, "worker_id": "w", "study_id": 0}
+/// {"op_code": 0, "worker_id": "w", "study_name": "foo", "directions": [1]}
+/// {"op_code": 4, "worker_id": "w", "study_id": 1, "params": {"x": 0.2}, "state": 1, "values": [2.0]}
+/// "#;
+/// let trials = tpe::optuna::read_journal(journal.as_bytes(), "foo", &["x"])?;
+/// assert_eq!(trials.len(), 1);
+/// assert_eq!(trials[0].params, [0.2]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn read_journal<R: BufRead>(
+    reader: R,
+    study_name: &str,
+    param_names: &[&str],
+) -> Result<Vec<Trial>, JournalImportError> {
+    // Names of the studies indexed by their identifiers (`None` if deleted).
+    let mut study_names = Vec::<Option<String>>::new();
+    let mut study = None;
+    let mut trials = Vec::<JournalTrial>::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || JournalImportError::InvalidOperation { line: i + 1 };
+        let op: Value = serde_json::from_str(&line).map_err(|e| JournalImportError::Json {
+            line: i + 1,
+            source: e,
+        })?;
+        match op
+            .get("op_code")
+            .and_then(Value::as_u64)
+            .ok_or_else(invalid)?
+        {
+            CREATE_STUDY => {
+                let name = op.get("study_name").and_then(Value::as_str);
+                let name = name.ok_or_else(invalid)?;
+                if study_names.iter().flatten().any(|n| n == name) {
+                    continue;
+                }
+                if name == study_name {
+                    let directions = op.get("directions").and_then(Value::as_array);
+                    let directions = directions.ok_or_else(invalid)?;
+                    if directions.len() != 1 {
+                        return Err(JournalImportError::MultiObjective);
+                    }
+                    let maximize = directions[0].as_u64() == Some(DIRECTION_MAXIMIZE);
+                    study = Some((study_names.len() as u64, maximize));
+                }
+                study_names.push(Some(name.to_owned()));
+            }
+            DELETE_STUDY => {
+                let study_id = op.get("study_id").and_then(Value::as_u64);
+                let study_id = study_id.ok_or_else(invalid)?;
+                if let Some(name) = study_names.get_mut(study_id as usize) {
+                    *name = None;
+                }
+                if study.is_some_and(|(id, _)| id == study_id) {
+                    study = None;
+                }
+            }
+            CREATE_TRIAL => {
+                let study_id = op.get("study_id").and_then(Value::as_u64);
+                let study_id = study_id.ok_or_else(invalid)?;
+                if !matches!(study_names.get(study_id as usize), Some(Some(_))) {
+                    // Optuna doesn't create a trial (nor consume its identifier) for a missing study.
+                    continue;
+                }
+                let mut trial = JournalTrial {
+                    study_id,
+                    params: HashMap::new(),
+                    state: None,
+                    value: None,
+                };
+                if let Some(params) = op.get("params").and_then(Value::as_object) {
+                    for (name, v) in params {
+                        trial
+                            .params
+                            .insert(name.clone(), v.as_f64().ok_or_else(invalid)?);
+                    }
+                }
+                trial.set_state_values(&op).ok_or_else(invalid)?;
+                trials.push(trial);
+            }
+            SET_TRIAL_PARAM => {
+                let trial = op.get("trial_id").and_then(Value::as_u64);
+                let trial = trial
+                    .and_then(|id| trials.get_mut(id as usize))
+                    .ok_or_else(invalid)?;
+                let name = op.get("param_name").and_then(Value::as_str);
+                let v = op.get("param_value_internal").and_then(Value::as_f64);
+                trial
+                    .params
+                    .insert(name.ok_or_else(invalid)?.to_owned(), v.ok_or_else(invalid)?);
+            }
+            SET_TRIAL_STATE_VALUES => {
+                let trial = op.get("trial_id").and_then(Value::as_u64);
+                let trial = trial
+                    .and_then(|id| trials.get_mut(id as usize))
+                    .ok_or_else(invalid)?;
+                trial.set_state_values(&op).ok_or_else(invalid)?;
+            }
+            _ => {}
+        }
+    }
+
+    let (study_id, maximize) = study.ok_or_else(|| JournalImportError::UnknownStudy {
+        name: study_name.to_owned(),
+    })?;
+    Ok(trials
+        .into_iter()
+        .filter(|t| t.study_id == study_id && t.state == Some(STATE_COMPLETE))
+        .filter_map(|t| {
+            let value = t.value?;
+            let params = param_names
+                .iter()
+                .map(|&name| t.params.get(name).copied().unwrap_or(f64::NAN))
+                .collect();
+            Some(Trial {
+                params,
+                value: if maximize { -value } else { value },
+            })
+        })
+        .collect())
+}
+
+#[derive(Debug)]
+struct JournalTrial {
+    study_id: u64,
+    params: HashMap<String, f64>,
+    state: Option<u64>,
+    value: Option<f64>,
+}
+
+impl JournalTrial {
+    // Applies the `state` and `value(s)` fields of the operation if they exist.
+    fn set_state_values(&mut self, op: &Value) -> Option<()> {
+        if let Some(state) = op.get("state") {
+            self.state = Some(state.as_u64()?);
+        }
+        match (op.get("values"), op.get("value")) {
+            (Some(Value::Array(values)), _) => match values.as_slice() {
+                [] => {}
+                [v] => self.value = Some(v.as_f64()?),
+                _ => return None,
+            },
+            (_, Some(v)) if !v.is_null() => self.value = Some(v.as_f64()?),
+            _ => {}
+        }
+        Some(())
+    }
+}
+
+/// Study read from Optuna's RDB storage by [`read_rdb`].
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone)]
+pub struct RdbStudy {
+    /// Parameters (in the order they first appear in the database).
+    pub params: Vec<RdbParam>,
+
+    /// Completed trials.
+    ///
+    /// The `i`-th element of the params of each trial corresponds to `params[i]`
+    /// (see [`read_journal`] for the representation).
+    pub trials: Vec<Trial>,
+}
+
+/// Parameter of an [`RdbStudy`].
+#[cfg(feature = "sqlite")]
+#[derive(Debug, Clone)]
+pub struct RdbParam {
+    /// Parameter name.
+    pub name: String,
+
+    /// Range converted from the distribution of the parameter.
+    pub range: Range,
+
+    /// Whether the parameter has a `CategoricalDistribution`.
+    pub is_categorical: bool,
+}
+
+#[cfg(feature = "sqlite")]
+impl RdbStudy {
+    /// Makes a new [`Study`] which has an optimizer for each parameter and is told the trials.
+    ///
+    /// Categorical parameters are optimized by [`histogram_estimator`] and the others by [`parzen_estimator`].
+    pub fn into_study(self) -> Result<Study, crate::TellError> {
+        let optimizers = self
+            .params
+            .iter()
+            .map(|p| {
+                if p.is_categorical {
+                    TpeOptimizer::new(histogram_estimator(), p.range)
+                } else {
+                    TpeOptimizer::new(parzen_estimator(), p.range)
+                }
+            })
+            .collect();
+        let mut study = Study::new(optimizers);
+        study.tell_many(self.trials)?;
+        Ok(study)
+    }
+}
+
+/// Reads the completed trials of the study named `study_name` from an Optuna RDB storage (SQLite database).
+///
+/// The distributions of the parameters are converted into [`Range`]s.
+/// If the distribution of a parameter varies among trials, the first one is used.
+/// As with [`read_journal`], the values of maximization studies are negated.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let dir = tempfile::tempdir()?;
+/// let path = dir.path().join("optuna.db");
+/// let conn = rusqlite::Connection::open(&path)?;
+/// conn.execute_batch(
+///     r#"
+///     CREATE TABLE studies (study_id INTEGER PRIMARY KEY, study_name TEXT);
+///     CREATE TABLE study_directions (study_direction_id INTEGER PRIMARY KEY, direction TEXT, study_id INTEGER, objective INTEGER);
+///     CREATE TABLE trials (trial_id INTEGER PRIMARY KEY, number INTEGER, study_id INTEGER, state TEXT);
+///     CREATE TABLE trial_params (param_id INTEGER PRIMARY KEY, trial_id INTEGER, param_name TEXT, param_value REAL, distribution_json TEXT);
+///     CREATE TABLE trial_values (trial_value_id INTEGER PRIMARY KEY, trial_id INTEGER, objective INTEGER, value REAL);
+///     INSERT INTO studies VALUES (1, 'foo');
+///     INSERT INTO study_directions VALUES (1, 'MAXIMIZE', 1, 0);
+///     INSERT INTO trials VALUES (1, 0, 1, 'COMPLETE'), (2, 1, 1, 'PRUNED');
+///     INSERT INTO trial_params VALUES
+///         (1, 1, 'lr', 0.01, '{"name": "FloatDistribution", "attributes": {"low": 1e-05, "high": 0.1, "log": true, "step": null}}'),
+///         (2, 1, 'act', 1, '{"name": "CategoricalDistribution", "attributes": {"choices": ["relu", "gelu"]}}'),
+///         (3, 2, 'lr', 0.001, '{"name": "FloatDistribution", "attributes": {"low": 1e-05, "high": 0.1, "log": true, "step": null}}');
+///     INSERT INTO trial_values VALUES (1, 1, 0, 0.9);
+///     "#,
+/// )?;
+///
+/// let study = tpe::optuna::read_rdb(&path, "foo")?;
+/// assert_eq!(study.params[0].name, "lr");
+/// assert_eq!(study.params[1].range.end(), 2.0);
+/// assert!(study.params[1].is_categorical);
+/// assert_eq!(study.trials.len(), 1);
+/// assert_eq!(study.trials[0].params, [0.01, 1.0]);
+/// assert_eq!(study.trials[0].value, -0.9);
+///
+/// let study = study.into_study()?;
+/// assert_eq!(study.trials().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "sqlite")]
+pub fn read_rdb<P: AsRef<Path>>(path: P, study_name: &str) -> Result<RdbStudy, RdbError> {
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let study_id: i64 = conn
+        .query_row(
+            "SELECT study_id FROM studies WHERE study_name = ?1",
+            [study_name],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => RdbError::UnknownStudy {
+                name: study_name.to_owned(),
+            },
+            e => e.into(),
+        })?;
+
+    let mut stmt = conn.prepare("SELECT direction FROM study_directions WHERE study_id = ?1")?;
+    let directions = stmt
+        .query_map([study_id], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    if directions.len() > 1 {
+        return Err(RdbError::MultiObjective);
+    }
+    let maximize = directions.first().is_some_and(|d| d == "MAXIMIZE");
+
+    let mut params = Vec::<RdbParam>::new();
+    let mut stmt = conn.prepare(
+        "SELECT p.param_name, p.distribution_json FROM trial_params p
+         JOIN trials t ON p.trial_id = t.trial_id
+         WHERE t.study_id = ?1 ORDER BY p.param_id",
+    )?;
+    let mut rows = stmt.query([study_id])?;
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(0)?;
+        if params.iter().any(|p| p.name == name) {
+            continue;
+        }
+        let distribution: String = row.get(1)?;
+        let (range, is_categorical) = distribution_to_range(&distribution)
+            .ok_or_else(|| RdbError::InvalidDistribution { name: name.clone() })?;
+        params.push(RdbParam {
+            name,
+            range: range?,
+            is_categorical,
+        });
+    }
+
+    let mut trials = Vec::new();
+    let mut stmt = conn.prepare(
+        "SELECT t.trial_id, v.value FROM trials t
+         JOIN trial_values v ON t.trial_id = v.trial_id AND v.objective = 0
+         WHERE t.study_id = ?1 AND t.state = 'COMPLETE' AND v.value IS NOT NULL
+         ORDER BY t.number",
+    )?;
+    let mut param_stmt =
+        conn.prepare("SELECT param_name, param_value FROM trial_params WHERE trial_id = ?1")?;
+    let mut rows = stmt.query([study_id])?;
+    while let Some(row) = rows.next()? {
+        let trial_id: i64 = row.get(0)?;
+        let value: f64 = row.get(1)?;
+        let mut values = vec![f64::NAN; params.len()];
+        let mut param_rows = param_stmt.query([trial_id])?;
+        while let Some(param_row) = param_rows.next()? {
+            let name: String = param_row.get(0)?;
+            if let Some(i) = params.iter().position(|p| p.name == name) {
+                values[i] = param_row.get(1)?;
+            }
+        }
+        trials.push(Trial {
+            params: values,
+            value: if maximize { -value } else { value },
+        });
+    }
+    Ok(RdbStudy { params, trials })
+}
+
+// Returns the range and whether the distribution is categorical, or `None` if the distribution is unknown.
+#[cfg(feature = "sqlite")]
+fn distribution_to_range(json: &str) -> Option<(Result<Range, RangeError>, bool)> {
+    let distribution: Value = serde_json::from_str(json).ok()?;
+    let attrs = distribution.get("attributes")?;
+    let number = |key| attrs.get(key).and_then(Value::as_f64);
+    let is_log = attrs.get("log").and_then(Value::as_bool) == Some(true);
+    let name = distribution.get("name")?.as_str()?;
+    if name == "CategoricalDistribution" {
+        let choices = attrs.get("choices")?.as_array()?.len();
+        return Some((categorical_range(choices), true));
+    }
+    if let (Some(low), Some(high)) = (number("low"), number("high")) {
+        if low == high {
+            // A distribution with a single value (the step doesn't matter).
+            return Some((Range::discrete(low, low + 1.0, 1.0), false));
+        }
+    }
+
+    // Log-scaled integers are estimated in the log space and rounded to the integer grid.
+    let int_log_range = || -> Option<Result<Range, RangeError>> {
+        let range = Range::log_uniform(number("low")?, number("high")?);
+        let step = number("step").unwrap_or(1.0);
+        Some(range.and_then(|r| r.with_inclusive_end().quantize(step)))
+    };
+    let range = match name {
+        "IntDistribution" if is_log => int_log_range()?,
+        "FloatDistribution" if is_log => {
+            Range::log_uniform(number("low")?, number("high")?).map(Range::with_inclusive_end)
+        }
+        "FloatDistribution" | "IntDistribution" => match number("step") {
+            Some(step) => Range::discrete(number("low")?, number("high")?, step)
+                .map(Range::with_inclusive_end),
+            None => Range::inclusive(number("low")?, number("high")?),
+        },
+        "UniformDistribution" => Range::inclusive(number("low")?, number("high")?),
+        "LogUniformDistribution" => {
+            Range::log_uniform(number("low")?, number("high")?).map(Range::with_inclusive_end)
+        }
+        "IntLogUniformDistribution" => int_log_range()?,
+        "DiscreteUniformDistribution" => {
+            Range::discrete(number("low")?, number("high")?, number("q")?)
+                .map(Range::with_inclusive_end)
+        }
+        "IntUniformDistribution" => Range::discrete(
+            number("low")?,
+            number("high")?,
+            number("step").unwrap_or(1.0),
+        )
+        .map(Range::with_inclusive_end),
+        _ => return None,
+    };
+    Some((range, false))
+}
+
+/// Possible errors during [`read_rdb`].
+#[cfg(feature = "sqlite")]
+#[derive(Debug, thiserror::Error)]
+pub enum RdbError {
+    #[error(transparent)]
+    /// Database error.
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("the database doesn't contain the study {name:?}")]
+    /// The database doesn't contain the study.
+    UnknownStudy {
+        /// Study name.
+        name: String,
+    },
+
+    #[error("multi-objective studies are not supported")]
+    /// Multi-objective studies are not supported.
+    MultiObjective,
+
+    #[error("the distribution of the parameter {name:?} is not supported")]
+    /// The distribution of the parameter is unknown or malformed.
+    InvalidDistribution {
+        /// Parameter name.
+        name: String,
+    },
+
+    #[error(transparent)]
+    /// The distribution can't be converted into a [`Range`].
+    Range(#[from] RangeError),
+}
+
+/// Possible errors during [`read_journal`].
+#[derive(Debug, thiserror::Error)]
+pub enum JournalImportError {
+    #[error(transparent)]
+    /// I/O error.
+    Io(#[from] std::io::Error),
+
+    #[error("line {line} is not a valid JSON")]
+    /// The line is not a valid JSON.
+    Json {
+        /// One-based line number.
+        line: usize,
+        /// Underlying error.
+        source: serde_json::Error,
+    },
+
+    #[error("line {line} is not a valid journal operation")]
+    /// The line is not a valid journal operation.
+    InvalidOperation {
+        /// One-based line number.
+        line: usize,
+    },
+
+    #[error("the journal doesn't contain the study {name:?}")]
+    /// The journal doesn't contain the study.
+    UnknownStudy {
+        /// Study name.
+        name: String,
+    },
+
+    #[error("multi-objective studies are not supported")]
+    /// Multi-objective studies are not supported.
+    MultiObjective,
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+
+    fn range(json: &str) -> Range {
+        let (range, _) = distribution_to_range(json).expect("unreachable");
+        range.expect("unreachable")
+    }
+
+    #[test]
+    fn int_log_distribution_is_discrete() {
+        let range = range(
+            r#"{"name": "IntDistribution", "attributes": {"log": true, "step": 1, "low": 1, "high": 100}}"#,
+        );
+        assert!(range.contains(1.0) && range.contains(100.0));
+        assert_eq!(range.q(), Some(1.0));
+        assert!((range.warp(10.0) - 10f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn single_value_distributions_are_accepted() {
+        for json in [
+            r#"{"name": "IntDistribution", "attributes": {"log": false, "step": 1, "low": 3, "high": 3}}"#,
+            r#"{"name": "IntDistribution", "attributes": {"log": true, "step": 1, "low": 3, "high": 3}}"#,
+            r#"{"name": "FloatDistribution", "attributes": {"log": false, "step": null, "low": 3.0, "high": 3.0}}"#,
+        ] {
+            let range = range(json);
+            assert!(range.contains(3.0));
+            let mut rng = rand::thread_rng();
+            assert_eq!(range.sample(&mut rng), 3.0);
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! Optimization over permutations (e.g., the ordering of passes in a compiler pipeline).
+//!
+//! A permutation of `n` items is encoded as its Lehmer code:
+//! the `i`-th digit is the number of items after the `i`-th position which are smaller than the `i`-th item
+//! (so the sum of the digits is the number of inversions).
+//! Each digit is a categorical parameter taking a value in `[0, n - i)`,
+//! and any assignment of the digits is decoded into a valid permutation.
+//!
+//! The digits can be optimized by a [`Study`] like any other parameters
+//! (see [`optimizers`], [`encode`] and [`decode`]),
+//! or [`PermutationOptimizer`] can be used when the permutation is the only parameter.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::permutation::PermutationOptimizer;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut optim = PermutationOptimizer::with_seed(4, 0);
+//! for _ in 0..50 {
+//!     let perm = optim.ask()?;
+//!     let displacement = perm.iter().enumerate().filter(|&(i, &x)| i != x).count();
+//!     optim.tell(&perm, displacement as f64)?;
+//! }
+//! let best = optim.best_permutation().expect("unreachable");
+//! assert_eq!(best.len(), 4);
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::EstimatorBuildError;
+use crate::study::Study;
+use crate::{categorical_range, histogram_estimator, TellError, TpeOptimizer};
+
+/// Makes the optimizers of the Lehmer code digits of a permutation of `n` items.
+///
+/// The `i`-th optimizer uses a histogram estimator over `n - i` categories.
+pub fn optimizers(n: usize) -> Vec<TpeOptimizer> {
+    (0..n)
+        .map(|i| {
+            let range = categorical_range(n - i).expect("unreachable");
+            TpeOptimizer::new(histogram_estimator(), range)
+        })
+        .collect()
+}
+
+/// Encodes a permutation of `0..perm.len()` into its Lehmer code.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// assert_eq!(tpe::permutation::encode(&[2, 0, 1])?, [2.0, 0.0, 0.0]);
+/// assert!(tpe::permutation::encode(&[0, 0, 1]).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode(perm: &[usize]) -> Result<Vec<f64>, PermutationError> {
+    let mut seen = vec![false; perm.len()];
+    for &x in perm {
+        if x >= perm.len() || std::mem::replace(&mut seen[x], true) {
+            return Err(PermutationError::NotPermutation { len: perm.len() });
+        }
+    }
+    Ok(perm
+        .iter()
+        .enumerate()
+        .map(|(i, &x)| perm[i + 1..].iter().filter(|&&y| y < x).count() as f64)
+        .collect())
+}
+
+/// Decodes a Lehmer code into a permutation of `0..code.len()`.
+///
+/// Digits out of their ranges are clamped, and NaN digits are regarded as zero.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(tpe::permutation::decode(&[2.0, 0.0, 0.0]), [2, 0, 1]);
+/// ```
+pub fn decode(code: &[f64]) -> Vec<usize> {
+    let mut remaining = (0..code.len()).collect::<Vec<_>>();
+    code.iter()
+        .map(|&d| {
+            let i = (d.max(0.0) as usize).min(remaining.len() - 1);
+            remaining.remove(i)
+        })
+        .collect()
+}
+
+/// Optimizer of a permutation of `n` items backed by a [`Study`] over its Lehmer code.
+#[derive(Debug)]
+pub struct PermutationOptimizer {
+    study: Study,
+}
+
+impl PermutationOptimizer {
+    /// Makes a new [`PermutationOptimizer`] instance seeded from the system entropy.
+    pub fn new(n: usize) -> Self {
+        Self {
+            study: Study::new(optimizers(n)),
+        }
+    }
+
+    /// Makes a new [`PermutationOptimizer`] instance with a deterministic seed.
+    pub fn with_seed(n: usize, seed: u64) -> Self {
+        Self {
+            study: Study::with_seed(optimizers(n), seed),
+        }
+    }
+
+    /// Returns the number of the items.
+    pub fn len(&self) -> usize {
+        self.study.optimizers().len()
+    }
+
+    /// Returns `true` if the number of the items is zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the next permutation to be evaluated.
+    pub fn ask(&mut self) -> Result<Vec<usize>, EstimatorBuildError> {
+        Ok(decode(&self.study.ask()?))
+    }
+
+    /// Tells the evaluation result of a permutation.
+    pub fn tell(&mut self, perm: &[usize], value: f64) -> Result<(), PermutationError> {
+        if perm.len() != self.len() {
+            return Err(PermutationError::LengthMismatch {
+                expected: self.len(),
+                actual: perm.len(),
+            });
+        }
+        self.study.tell(encode(perm)?, value)?;
+        Ok(())
+    }
+
+    /// Returns the best permutation told so far.
+    pub fn best_permutation(&self) -> Option<Vec<usize>> {
+        self.study.best_trial().map(|t| decode(&t.params))
+    }
+
+    /// Returns the underlying [`Study`].
+    pub fn study(&self) -> &Study {
+        &self.study
+    }
+
+    /// Returns the underlying [`Study`], consuming this optimizer.
+    pub fn into_study(self) -> Study {
+        self.study
+    }
+}
+
+/// Possible errors during telling a permutation.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum PermutationError {
+    #[error("not a permutation of 0..{len}")]
+    /// The items are not a permutation.
+    NotPermutation {
+        /// Number of the items.
+        len: usize,
+    },
+
+    #[error("expected a permutation of {expected} items, but got {actual} items")]
+    /// The number of the items differs from the optimizer's one.
+    LengthMismatch {
+        /// Expected number of the items.
+        expected: usize,
+        /// Actual number of the items.
+        actual: usize,
+    },
+
+    #[error(transparent)]
+    /// Failed to tell the encoded permutation.
+    Tell(#[from] TellError),
+}
@@ -0,0 +1,242 @@
+//! Quick visualization of optimization results via [`plotters`].
+//!
+//! Each function renders a figure to the given path.
+//! The image format is decided by the extension of the path: `.svg` for SVG and PNG otherwise.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::study::Study;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let dir = tempfile::tempdir()?;
+//! let mut study = Study::with_seed(
+//!     vec![
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+//!     ],
+//!     0,
+//! );
+//! for _ in 0..30 {
+//!     let params = study.ask()?;
+//!     let value = params[0].powi(2) + params[1].powi(2);
+//!     study.tell(params, value)?;
+//! }
+//!
+//! let values = study.trials().iter().map(|t| t.value).collect::<Vec<_>>();
+//! tpe::plot::history(&values, dir.path().join("history.svg"))?;
+//! tpe::plot::slice(study.trials(), &["x", "y"], dir.path().join("slice.svg"))?;
+//!
+//! let mut optim = study.optimizers()[0].clone();
+//! tpe::plot::density_ratio(&mut optim, dir.path().join("ratio.svg"))?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::BuildDensityEstimator;
+use crate::study::Trial;
+use crate::TpeOptimizer;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use std::path::Path;
+
+const SIZE: (u32, u32) = (800, 600);
+const GRID_POINTS: usize = 200;
+
+/// Plots the objective values in the order of the trials, together with the best value so far.
+pub fn history<P: AsRef<Path>>(values: &[f64], path: P) -> Result<(), PlotError> {
+    render(&History { values }, path.as_ref())
+}
+
+/// Plots the acquisition scores of an optimizer over its parameter range.
+///
+/// The scores are the log density ratio `log(l(x) / g(x))` by default.
+/// See [`TpeOptimizer::acquisition`] for details.
+pub fn density_ratio<T, P>(optim: &mut TpeOptimizer<T>, path: P) -> Result<(), PlotError>
+where
+    T: BuildDensityEstimator,
+    T::Error: Send + Sync + 'static,
+    P: AsRef<Path>,
+{
+    let range = optim.param_range();
+    let warped = range.warped();
+    let xs = (0..GRID_POINTS)
+        .map(|i| range.unwarp(warped.start() + warped.width() * i as f64 / GRID_POINTS as f64))
+        .collect::<Vec<_>>();
+    let ratios = optim
+        .acquisition_batch(&xs)
+        .map_err(|e| PlotError::Estimator(Box::new(e)))?;
+    let points = xs
+        .into_iter()
+        .zip(ratios)
+        .filter(|(_, r)| r.is_finite())
+        .collect::<Vec<_>>();
+    render(&DensityRatio { points: &points }, path.as_ref())
+}
+
+/// Plots the objective values against each parameter (one panel for each name).
+///
+/// The `i`-th element of `names` labels the `i`-th parameter of the trials.
+pub fn slice<P: AsRef<Path>>(trials: &[Trial], names: &[&str], path: P) -> Result<(), PlotError> {
+    render(&Slice { trials, names }, path.as_ref())
+}
+
+/// Possible errors during plotting.
+#[derive(Debug, thiserror::Error)]
+pub enum PlotError {
+    #[error("failed to draw a figure: {0}")]
+    /// Failed to draw a figure.
+    Drawing(String),
+
+    #[error("failed to estimate densities")]
+    /// Failed to estimate densities.
+    Estimator(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+type DrawResult<DB> = Result<(), DrawingAreaErrorKind<<DB as DrawingBackend>::ErrorType>>;
+
+trait Figure {
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> DrawResult<DB>;
+}
+
+fn render<F: Figure>(figure: &F, path: &Path) -> Result<(), PlotError> {
+    let drawing = |e: &dyn std::fmt::Display| PlotError::Drawing(e.to_string());
+    if path.extension().is_some_and(|ext| ext == "svg") {
+        let root = SVGBackend::new(path, SIZE).into_drawing_area();
+        figure.draw(&root).map_err(|e| drawing(&e))?;
+        root.present().map_err(|e| drawing(&e))
+    } else {
+        let root = BitMapBackend::new(path, SIZE).into_drawing_area();
+        figure.draw(&root).map_err(|e| drawing(&e))?;
+        root.present().map_err(|e| drawing(&e))
+    }
+}
+
+// Returns the bounds of the given values with a small margin.
+fn bounds<I: Iterator<Item = f64>>(xs: I) -> std::ops::Range<f64> {
+    let (min, max) = xs
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+    if min > max {
+        return 0.0..1.0;
+    }
+    let margin = ((max - min) * 0.05).max(1e-6);
+    (min - margin)..(max + margin)
+}
+
+struct History<'a> {
+    values: &'a [f64],
+}
+
+impl Figure for History<'_> {
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(root)
+            .caption("Optimization History", ("sans-serif", 24))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                0.0..self.values.len().max(1) as f64,
+                bounds(self.values.iter().copied()),
+            )?;
+        chart
+            .configure_mesh()
+            .x_desc("trial")
+            .y_desc("value")
+            .draw()?;
+
+        chart
+            .draw_series(
+                self.values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &v)| Circle::new((i as f64, v), 3, BLUE.filled())),
+            )?
+            .label("value")
+            .legend(|(x, y)| Circle::new((x, y), 3, BLUE.filled()));
+
+        let best = self.values.iter().scan(f64::INFINITY, |best, &v| {
+            *best = best.min(v);
+            Some(*best)
+        });
+        chart
+            .draw_series(LineSeries::new(
+                best.enumerate().map(|(i, v)| (i as f64, v)),
+                &RED,
+            ))?
+            .label("best value")
+            .legend(|(x, y)| PathElement::new(vec![(x - 10, y), (x + 10, y)], RED));
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()
+    }
+}
+
+struct DensityRatio<'a> {
+    points: &'a [(f64, f64)],
+}
+
+impl Figure for DensityRatio<'_> {
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        root.fill(&WHITE)?;
+        let mut chart = ChartBuilder::on(root)
+            .caption("Density Ratio", ("sans-serif", 24))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                bounds(self.points.iter().map(|p| p.0)),
+                bounds(self.points.iter().map(|p| p.1)),
+            )?;
+        chart
+            .configure_mesh()
+            .x_desc("param")
+            .y_desc("log(l(x) / g(x))")
+            .draw()?;
+        chart.draw_series(LineSeries::new(self.points.iter().copied(), &BLUE))?;
+        Ok(())
+    }
+}
+
+struct Slice<'a> {
+    trials: &'a [Trial],
+    names: &'a [&'a str],
+}
+
+impl Figure for Slice<'_> {
+    fn draw<DB: DrawingBackend>(&self, root: &DrawingArea<DB, Shift>) -> DrawResult<DB> {
+        root.fill(&WHITE)?;
+        let panels = root.split_evenly((1, self.names.len().max(1)));
+        let ys = bounds(self.trials.iter().map(|t| t.value));
+        for (i, (panel, name)) in panels.iter().zip(self.names).enumerate() {
+            let points = self
+                .trials
+                .iter()
+                .filter_map(|t| t.params.get(i).map(|&x| (x, t.value)))
+                .filter(|(x, _)| !x.is_nan())
+                .collect::<Vec<_>>();
+            let mut chart = ChartBuilder::on(panel)
+                .caption(*name, ("sans-serif", 20))
+                .margin(10)
+                .x_label_area_size(40)
+                .y_label_area_size(60)
+                .build_cartesian_2d(bounds(points.iter().map(|p| p.0)), ys.clone())?;
+            chart
+                .configure_mesh()
+                .x_desc(*name)
+                .y_desc("value")
+                .draw()?;
+            chart.draw_series(
+                points
+                    .into_iter()
+                    .map(|p| Circle::new(p, 3, BLUE.mix(0.6).filled())),
+            )?;
+        }
+        Ok(())
+    }
+}
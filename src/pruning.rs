@@ -0,0 +1,231 @@
+//! Early stopping of unpromising trials.
+use ordered_float::OrderedFloat;
+use std::collections::BTreeMap;
+
+/// This trait allows deciding whether a running trial should be stopped early.
+pub trait Pruner {
+    /// Returns `true` if the trial having the given intermediate values should be pruned.
+    fn should_prune(&self, values: &IntermediateValues) -> bool;
+
+    /// Records the intermediate values of a finished (completed or pruned) trial.
+    fn finish_trial(&mut self, values: IntermediateValues);
+}
+
+/// Intermediate values reported by a trial (smaller is better).
+#[derive(Debug, Default, Clone)]
+pub struct IntermediateValues {
+    values: BTreeMap<u64, f64>,
+}
+
+impl IntermediateValues {
+    /// Makes a new empty [`IntermediateValues`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the value at the given step.
+    ///
+    /// If a value has already been reported at the step, it is overwritten.
+    pub fn report(&mut self, step: u64, value: f64) {
+        self.values.insert(step, value);
+    }
+
+    /// Returns the value reported at the given step.
+    pub fn get(&self, step: u64) -> Option<f64> {
+        self.values.get(&step).copied()
+    }
+
+    /// Returns the step and value which have been reported last (in step order).
+    pub fn last(&self) -> Option<(u64, f64)> {
+        self.values.iter().next_back().map(|(&s, &v)| (s, v))
+    }
+
+    /// Returns an iterator over the reported step and value pairs in step order.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (u64, f64)> {
+        self.values.iter().map(|(&s, &v)| (s, v))
+    }
+}
+
+/// A running trial that reports intermediate values to a [`Pruner`].
+#[derive(Debug)]
+pub struct PruningTrial<'a, P: ?Sized> {
+    pruner: &'a P,
+    values: IntermediateValues,
+}
+
+impl<'a, P: Pruner + ?Sized> PruningTrial<'a, P> {
+    /// Makes a new [`PruningTrial`] instance.
+    pub fn new(pruner: &'a P) -> Self {
+        Self {
+            pruner,
+            values: IntermediateValues::new(),
+        }
+    }
+
+    /// Reports an intermediate value at the given step.
+    pub fn report(&mut self, step: u64, value: f64) {
+        self.values.report(step, value);
+    }
+
+    /// Returns `true` if the trial should be stopped now.
+    pub fn should_prune(&self) -> bool {
+        self.pruner.should_prune(&self.values)
+    }
+
+    /// Returns the intermediate values reported so far.
+    pub fn intermediate_values(&self) -> &IntermediateValues {
+        &self.values
+    }
+
+    /// Consumes the trial and returns the reported intermediate values.
+    pub fn into_intermediate_values(self) -> IntermediateValues {
+        self.values
+    }
+}
+
+/// Final state of a trial evaluated with a [`Pruner`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrialOutcome {
+    /// The evaluation completed with the given value.
+    Completed(f64),
+
+    /// The evaluation was stopped early.
+    Pruned,
+}
+
+/// Builder of [`MedianPruner`].
+#[derive(Debug)]
+pub struct MedianPrunerBuilder {
+    startup_trials: usize,
+    warmup_steps: u64,
+}
+
+impl MedianPrunerBuilder {
+    /// Makes a new [`MedianPrunerBuilder`] instance with the default settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of finished trials required before pruning starts.
+    ///
+    /// The default value is `5`.
+    pub fn startup_trials(&mut self, n: usize) -> &mut Self {
+        self.startup_trials = n;
+        self
+    }
+
+    /// Sets the number of steps at the beginning of each trial during which pruning is disabled.
+    ///
+    /// The default value is `0`.
+    pub fn warmup_steps(&mut self, n: u64) -> &mut Self {
+        self.warmup_steps = n;
+        self
+    }
+
+    /// Builds a [`MedianPruner`] with the given settings.
+    pub fn build(&self) -> MedianPruner {
+        MedianPruner {
+            startup_trials: self.startup_trials,
+            warmup_steps: self.warmup_steps,
+            finished: Vec::new(),
+        }
+    }
+}
+
+impl Default for MedianPrunerBuilder {
+    fn default() -> Self {
+        Self {
+            startup_trials: 5,
+            warmup_steps: 0,
+        }
+    }
+}
+
+/// Pruner which stops a trial if its latest intermediate value is worse than
+/// the median of the values of the previous trials at the same step.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::pruning::{IntermediateValues, MedianPruner, Pruner as _};
+///
+/// let mut pruner = MedianPruner::new();
+/// for i in 0..5 {
+///     let mut values = IntermediateValues::new();
+///     values.report(0, i as f64);
+///     pruner.finish_trial(values);
+/// }
+///
+/// let mut good = IntermediateValues::new();
+/// good.report(0, 1.0);
+/// assert!(!pruner.should_prune(&good));
+///
+/// // No previous trials reported at step 1.
+/// good.report(1, 3.0);
+/// assert!(!pruner.should_prune(&good));
+///
+/// let mut bad = IntermediateValues::new();
+/// bad.report(0, 3.0);
+/// assert!(pruner.should_prune(&bad));
+/// ```
+#[derive(Debug)]
+pub struct MedianPruner {
+    startup_trials: usize,
+    warmup_steps: u64,
+    finished: Vec<IntermediateValues>,
+}
+
+impl MedianPruner {
+    /// Makes a new [`MedianPruner`] with the default settings.
+    ///
+    /// If you want to customize the settings, please use [`MedianPrunerBuilder`] instead.
+    pub fn new() -> Self {
+        MedianPrunerBuilder::new().build()
+    }
+}
+
+impl Default for MedianPruner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pruner for MedianPruner {
+    fn should_prune(&self, values: &IntermediateValues) -> bool {
+        if self.finished.len() < self.startup_trials {
+            return false;
+        }
+
+        let Some((step, value)) = values.last() else {
+            return false;
+        };
+        if step < self.warmup_steps {
+            return false;
+        }
+        if value.is_nan() {
+            return true;
+        }
+
+        let mut others = self
+            .finished
+            .iter()
+            .filter_map(|t| t.get(step))
+            .filter(|v| !v.is_nan())
+            .collect::<Vec<_>>();
+        if others.is_empty() {
+            return false;
+        }
+        others.sort_by_key(|&v| OrderedFloat(v));
+        let n = others.len();
+        let median = if n % 2 == 0 {
+            (others[n / 2 - 1] + others[n / 2]) * 0.5
+        } else {
+            others[n / 2]
+        };
+        value > median
+    }
+
+    fn finish_trial(&mut self, values: IntermediateValues) {
+        self.finished.push(values);
+    }
+}
@@ -0,0 +1,237 @@
+//! Python bindings based on [PyO3](https://pyo3.rs/).
+//!
+//! This module defines the `tpe` extension module, which can be built and installed by `maturin`
+//! (see `pyproject.toml`):
+//!
+//! ```console
+//! $ maturin develop --release
+//! ```
+//!
+//! Other extension modules can also embed the bindings by calling [`register`] from their `#[pymodule]` function.
+//!
+//! The optimizer can be used from Python as follows:
+//!
+//! ```python
+//! import tpe
+//!
+//! optim = tpe.TpeOptimizerBuilder().gamma(0.2).build(tpe.parzen_estimator(), tpe.Range(-5.0, 5.0))
+//! for _ in range(100):
+//!     x = optim.ask()
+//!     optim.tell(x, x ** 2)
+//! ```
+use crate::density_estimation::DefaultEstimatorBuilder;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// The `tpe` extension module.
+#[pymodule]
+fn tpe(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    register(m)
+}
+
+/// Adds the classes and functions of this module to the given Python module.
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Range>()?;
+    m.add_class::<EstimatorBuilder>()?;
+    m.add_class::<TpeOptimizerBuilder>()?;
+    m.add_class::<TpeOptimizer>()?;
+    m.add_function(wrap_pyfunction!(parzen_estimator, m)?)?;
+    m.add_function(wrap_pyfunction!(discrete_parzen_estimator, m)?)?;
+    m.add_function(wrap_pyfunction!(histogram_estimator, m)?)?;
+    m.add_function(wrap_pyfunction!(gmm_estimator, m)?)?;
+    m.add_function(wrap_pyfunction!(beta_estimator, m)?)?;
+    Ok(())
+}
+
+fn value_error<E: std::fmt::Display>(e: E) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Python wrapper of [`crate::range::Range`].
+#[pyclass(frozen)]
+#[derive(Debug, Clone, Copy)]
+pub struct Range(crate::range::Range);
+
+#[pymethods]
+impl Range {
+    #[new]
+    fn new(start: f64, end: f64) -> PyResult<Self> {
+        crate::range::Range::new(start, end)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn log_uniform(start: f64, end: f64) -> PyResult<Self> {
+        crate::range::Range::log_uniform(start, end)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn discrete(start: f64, end: f64, step: f64) -> PyResult<Self> {
+        crate::range::Range::discrete(start, end, step)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    #[staticmethod]
+    fn categorical(cardinality: usize) -> PyResult<Self> {
+        crate::categorical_range(cardinality)
+            .map(Self)
+            .map_err(value_error)
+    }
+
+    #[getter]
+    fn start(&self) -> f64 {
+        self.0.start()
+    }
+
+    #[getter]
+    fn end(&self) -> f64 {
+        self.0.end()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Range({:?})", self.0.to_string())
+    }
+}
+
+/// Python wrapper of [`DefaultEstimatorBuilder`].
+#[pyclass(frozen)]
+#[derive(Debug, Clone)]
+pub struct EstimatorBuilder(DefaultEstimatorBuilder);
+
+/// Python wrapper of [`crate::parzen_estimator`].
+#[pyfunction]
+pub fn parzen_estimator() -> EstimatorBuilder {
+    EstimatorBuilder(crate::parzen_estimator())
+}
+
+/// Python wrapper of [`crate::discrete_parzen_estimator`].
+#[pyfunction]
+pub fn discrete_parzen_estimator() -> EstimatorBuilder {
+    EstimatorBuilder(crate::discrete_parzen_estimator())
+}
+
+/// Python wrapper of [`crate::histogram_estimator`].
+#[pyfunction]
+pub fn histogram_estimator() -> EstimatorBuilder {
+    EstimatorBuilder(crate::histogram_estimator())
+}
+
+/// Python wrapper of [`crate::gmm_estimator`].
+#[pyfunction]
+pub fn gmm_estimator() -> EstimatorBuilder {
+    EstimatorBuilder(crate::gmm_estimator())
+}
+
+/// Python wrapper of [`crate::beta_estimator`].
+#[pyfunction]
+pub fn beta_estimator() -> EstimatorBuilder {
+    EstimatorBuilder(crate::beta_estimator())
+}
+
+/// Python wrapper of [`crate::TpeOptimizerBuilder`].
+///
+/// The setters return the builder itself, so they can be chained as in Rust.
+#[pyclass(unsendable)]
+#[derive(Debug)]
+pub struct TpeOptimizerBuilder {
+    inner: crate::TpeOptimizerBuilder,
+    seed: Option<u64>,
+}
+
+#[pymethods]
+impl TpeOptimizerBuilder {
+    #[new]
+    fn new() -> Self {
+        Self {
+            inner: crate::TpeOptimizerBuilder::new(),
+            seed: None,
+        }
+    }
+
+    fn gamma(mut slf: PyRefMut<'_, Self>, gamma: f64) -> PyRefMut<'_, Self> {
+        slf.inner.gamma(gamma);
+        slf
+    }
+
+    fn candidates(mut slf: PyRefMut<'_, Self>, candidates: usize) -> PyRefMut<'_, Self> {
+        slf.inner.candidates(candidates);
+        slf
+    }
+
+    fn rank_weights(mut slf: PyRefMut<'_, Self>, enabled: bool) -> PyRefMut<'_, Self> {
+        slf.inner.rank_weights(enabled);
+        slf
+    }
+
+    #[pyo3(signature = (window=None))]
+    fn linear_forgetting(mut slf: PyRefMut<'_, Self>, window: Option<usize>) -> PyRefMut<'_, Self> {
+        slf.inner.linear_forgetting(window);
+        slf
+    }
+
+    /// Sets the seed of the random number generator used by `TpeOptimizer.ask`.
+    fn seed(mut slf: PyRefMut<'_, Self>, seed: u64) -> PyRefMut<'_, Self> {
+        slf.seed = Some(seed);
+        slf
+    }
+
+    fn build(&self, estimator: &EstimatorBuilder, param_range: &Range) -> PyResult<TpeOptimizer> {
+        let inner = self
+            .inner
+            .build(estimator.0.clone(), param_range.0)
+            .map_err(value_error)?;
+        Ok(TpeOptimizer::with_seed(inner, self.seed))
+    }
+}
+
+/// Python wrapper of [`crate::TpeOptimizer`].
+#[pyclass(unsendable)]
+#[derive(Debug)]
+pub struct TpeOptimizer {
+    inner: crate::TpeOptimizer,
+    rng: StdRng,
+}
+
+impl TpeOptimizer {
+    fn with_seed(inner: crate::TpeOptimizer, seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { inner, rng }
+    }
+}
+
+#[pymethods]
+impl TpeOptimizer {
+    #[new]
+    #[pyo3(signature = (estimator, param_range, seed=None))]
+    fn new(estimator: &EstimatorBuilder, param_range: &Range, seed: Option<u64>) -> Self {
+        let inner = crate::TpeOptimizer::new(estimator.0.clone(), param_range.0);
+        Self::with_seed(inner, seed)
+    }
+
+    fn ask(&mut self) -> PyResult<f64> {
+        self.inner.ask(&mut self.rng).map_err(value_error)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> PyResult<()> {
+        self.inner.tell(param, value).map_err(value_error)
+    }
+
+    /// Returns the told trials as a list of `(param, value)` tuples.
+    fn trials(&self) -> Vec<(f64, f64)> {
+        self.inner.trials().collect()
+    }
+
+    #[getter]
+    fn param_range(&self) -> Range {
+        Range(self.inner.param_range())
+    }
+}
@@ -1,18 +1,210 @@
 //! Parameter range.
+use crate::density_estimation::BuildDensityEstimator;
+use crate::{TellError, TpeOptimizer};
+use rand::Rng;
+use std::time::Duration;
 
 /// Range which has represents (inclusive) and end (exclusive) as floating values.
+///
+/// The end can be made inclusive by [`Range::inclusive`] or [`Range::with_inclusive_end`].
+///
+/// A range also decides the space in which densities are estimated (see [`Range::warp`]).
+///
+/// If the `serde` feature is enabled, a range can be (de)serialized as a map like
+/// `{"start": 0.00001, "end": 0.1, "log": true}`
+/// (optional keys are `log`, `step` (for discrete ranges), `ratio` (for geometric ranges), `cyclic`, `q`, and `inclusive_end`).
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RangeRepr", into = "RangeRepr")
+)]
 pub struct Range {
     start: f64,
     end: f64,
+    kind: Kind,
+    q: Option<f64>,
+    inclusive_end: bool,
 }
 
 impl std::fmt::Display for Range {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}..{}", self.start, self.end)
+        let dots = if self.inclusive_end { "..=" } else { ".." };
+        match self.kind {
+            Kind::Linear => write!(f, "{}{}{}", self.start, dots, self.end)?,
+            Kind::Cyclic => write!(f, "cyc:{}{}{}", self.start, dots, self.end)?,
+            Kind::Log => write!(f, "log:{}{}{}", self.start, dots, self.end)?,
+            Kind::Discrete { step } => write!(f, "{}{}{}:{}", self.start, dots, self.end, step)?,
+            Kind::Geometric { ratio } => {
+                write!(f, "geom:{}{}{}:{}", self.start, dots, self.end, ratio)?
+            }
+        }
+        if let Some(q) = self.q {
+            write!(f, "/{}", q)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a range from the format used by [`Display`](std::fmt::Display).
+///
+/// Geometric ranges are written as `geom:start..end:ratio` and cyclic ones as `cyc:start..end`.
+/// Additionally, `cat:N` is parsed as a categorical range of `N` choices (i.e., `0..N`).
+///
+/// # Examples
+///
+/// ```
+/// use tpe::range::Range;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let range: Range = "log:1e-5..1e-1".parse()?;
+/// assert_eq!(range.to_string(), "log:0.00001..0.1");
+///
+/// let range: Range = "-5.0..=5.0".parse()?;
+/// assert!(range.contains(5.0));
+///
+/// let range: Range = "cat:3".parse()?;
+/// assert_eq!(range.to_string(), "0..3");
+///
+/// let range: Range = "0..10:2".parse()?;
+/// assert!(range.contains(4.0));
+/// assert!(!range.contains(5.0));
+///
+/// assert!("0..1:0.5/0.1".parse::<Range>().is_err());
+///
+/// let range: Range = "geom:16..=4096:2".parse()?;
+/// assert!(range.contains(4096.0));
+/// assert_eq!(range.to_string(), "geom:16..=4096:2");
+///
+/// let range: Range = "cyc:0..360".parse()?;
+/// assert!(range.is_cyclic());
+/// # Ok(())
+/// # }
+/// ```
+impl std::str::FromStr for Range {
+    type Err = RangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn parse<T: std::str::FromStr>(s: &str) -> Result<T, RangeError> {
+            s.trim().parse().map_err(|_| RangeError::InvalidFormat)
+        }
+
+        let s = s.trim();
+        if let Some(n) = s.strip_prefix("cat:") {
+            let n: usize = parse(n)?;
+            return Range::new(0.0, n as f64);
+        }
+
+        let (log, geometric, cyclic, s) = if let Some(s) = s.strip_prefix("log:") {
+            (true, false, false, s)
+        } else if let Some(s) = s.strip_prefix("geom:") {
+            (false, true, false, s)
+        } else if let Some(s) = s.strip_prefix("cyc:") {
+            (false, false, true, s)
+        } else {
+            (false, false, false, s)
+        };
+        let (s, q) = match s.split_once('/') {
+            Some((s, q)) => (s, Some(parse(q)?)),
+            None => (s, None),
+        };
+        let (s, step) = match s.split_once(':') {
+            Some((s, step)) => (s, Some(parse(step)?)),
+            None => (s, None),
+        };
+        let (start, end, inclusive_end) = if let Some((start, end)) = s.split_once("..=") {
+            (start, end, true)
+        } else if let Some((start, end)) = s.split_once("..") {
+            (start, end, false)
+        } else {
+            return Err(RangeError::InvalidFormat);
+        };
+        let (step, ratio) = match (geometric, step) {
+            (false, step) => (step, None),
+            (true, Some(ratio)) => (None, Some(ratio)),
+            (true, None) => return Err(RangeError::InvalidFormat),
+        };
+        Range::from_parts(
+            parse(start)?,
+            parse(end)?,
+            log,
+            step,
+            ratio,
+            cyclic,
+            q,
+            inclusive_end,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RangeRepr {
+    start: f64,
+    end: f64,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    log: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    step: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ratio: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    cyclic: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    q: Option<f64>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    inclusive_end: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RangeRepr> for Range {
+    type Error = RangeError;
+
+    fn try_from(r: RangeRepr) -> Result<Self, Self::Error> {
+        Range::from_parts(
+            r.start,
+            r.end,
+            r.log,
+            r.step,
+            r.ratio,
+            r.cyclic,
+            r.q,
+            r.inclusive_end,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Range> for RangeRepr {
+    fn from(r: Range) -> Self {
+        Self {
+            start: r.start,
+            end: r.end,
+            log: r.kind == Kind::Log,
+            step: match r.kind {
+                Kind::Discrete { step } => Some(step),
+                _ => None,
+            },
+            ratio: match r.kind {
+                Kind::Geometric { ratio } => Some(ratio),
+                _ => None,
+            },
+            cyclic: r.kind == Kind::Cyclic,
+            q: r.q,
+            inclusive_end: r.inclusive_end,
+        }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Linear,
+    Log,
+    Discrete { step: f64 },
+    Geometric { ratio: f64 },
+    Cyclic,
+}
+
 impl Range {
     /// Makes a new [`Range`] instance.
     pub fn new(start: f64, end: f64) -> Result<Self, RangeError> {
@@ -24,7 +216,196 @@ impl Range {
             return Err(RangeError::EmptyRange);
         }
 
-        Ok(Self { start, end })
+        Ok(Self {
+            start,
+            end,
+            kind: Kind::Linear,
+            q: None,
+            inclusive_end: false,
+        })
+    }
+
+    /// Makes a new [`Range`] instance of which end is inclusive (i.e., `[start, end]`).
+    ///
+    /// This is equivalent to `Range::new(start, end)?.with_inclusive_end()`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), Range::inclusive(0.0, 1.0)?);
+    /// optim.tell(1.0, 0.5)?;
+    /// assert!(optim.tell(1.1, 0.5).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn inclusive(start: f64, end: f64) -> Result<Self, RangeError> {
+        Ok(Self::new(start, end)?.with_inclusive_end())
+    }
+
+    /// Makes a new [`Range`] instance of which densities are estimated in the log space.
+    ///
+    /// This is suitable for parameters such as learning rates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), Range::log_uniform(1e-5, 1e-1)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let lr = optim.ask(&mut rng)?;
+    ///     assert!(1e-5 <= lr && lr < 1e-1);
+    ///     optim.tell(lr, (lr.log10() + 3.0).abs())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn log_uniform(start: f64, end: f64) -> Result<Self, RangeError> {
+        if start.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+            return Err(RangeError::NonPositiveLogRange);
+        }
+        Self::new(start.ln(), end.ln())?;
+        Ok(Self {
+            kind: Kind::Log,
+            ..Self::new(start, end)?
+        })
+    }
+
+    /// Makes a new [`Range`] instance which only contains the points `start + step * i` (`i = 0, 1, ..`) less than `end`.
+    ///
+    /// Values returned by [`TpeOptimizer::ask`](crate::TpeOptimizer::ask) are snapped to the grid,
+    /// and values which are not on the grid are rejected by [`TpeOptimizer::tell`](crate::TpeOptimizer::tell).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), Range::discrete(0.0, 10.0, 2.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     assert!([0.0, 2.0, 4.0, 6.0, 8.0].contains(&x));
+    ///     optim.tell(x, (x - 4.0).abs())?;
+    /// }
+    /// assert!(optim.tell(3.0, 1.0).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn discrete(start: f64, end: f64, step: f64) -> Result<Self, RangeError> {
+        let range = Self::new(start, end)?;
+        if !(step.is_finite() && step > 0.0) {
+            return Err(RangeError::InvalidStep);
+        }
+        Ok(Self {
+            kind: Kind::Discrete { step },
+            ..range
+        })
+    }
+
+    /// Makes a new [`Range`] instance which only contains the points `start * ratio^i` (`i = 0, 1, ..`) less than `end`.
+    ///
+    /// Densities are estimated on the exponent `i`, and values returned by
+    /// [`TpeOptimizer::ask`](crate::TpeOptimizer::ask) are snapped to the grid.
+    /// This is suitable for parameters such as batch sizes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let range = Range::geometric(16.0, 4096.0, 2.0)?.with_inclusive_end();
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), range);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let batch_size = optim.ask(&mut rng)?;
+    ///     assert!([16.0, 32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0, 4096.0].contains(&batch_size));
+    ///     optim.tell(batch_size, (batch_size.log2() - 8.0).abs())?;
+    /// }
+    /// assert!(optim.tell(100.0, 1.0).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn geometric(start: f64, end: f64, ratio: f64) -> Result<Self, RangeError> {
+        let range = Self::log_uniform(start, end)?;
+        if !(ratio.is_finite() && ratio > 1.0) {
+            return Err(RangeError::InvalidStep);
+        }
+        Ok(Self {
+            kind: Kind::Geometric { ratio },
+            ..range
+        })
+    }
+
+    /// Makes a new [`Range`] instance of which end is adjacent to its start (e.g., angles).
+    ///
+    /// [`ParzenEstimator`](crate::density_estimation::ParzenEstimator) uses von Mises kernels on a cyclic range,
+    /// so that the densities wrap around the boundaries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), Range::cyclic(0.0, 360.0)?);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..20 {
+    ///     let angle = optim.ask(&mut rng)?;
+    ///     assert!((0.0..360.0).contains(&angle));
+    ///
+    ///     // The optimum is at 0 (= 360) degrees.
+    ///     let distance = angle.min(360.0 - angle);
+    ///     optim.tell(angle, distance)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cyclic(start: f64, end: f64) -> Result<Self, RangeError> {
+        Ok(Self {
+            kind: Kind::Cyclic,
+            ..Self::new(start, end)?
+        })
+    }
+
+    /// Returns `true` if this range was made by [`Range::cyclic`].
+    pub fn is_cyclic(self) -> bool {
+        self.kind == Kind::Cyclic
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn from_parts(
+        start: f64,
+        end: f64,
+        log: bool,
+        step: Option<f64>,
+        ratio: Option<f64>,
+        cyclic: bool,
+        q: Option<f64>,
+        inclusive_end: bool,
+    ) -> Result<Self, RangeError> {
+        let mut range = match (log, step, ratio, cyclic) {
+            (false, None, None, false) => Range::new(start, end)?,
+            (true, None, None, false) => Range::log_uniform(start, end)?,
+            (false, Some(step), None, false) => Range::discrete(start, end, step)?,
+            (false, None, Some(ratio), false) => Range::geometric(start, end, ratio)?,
+            (false, None, None, true) => Range::cyclic(start, end)?,
+            _ => return Err(RangeError::InvalidStep),
+        };
+        if let Some(q) = q {
+            range = range.quantize(q)?;
+        }
+        if inclusive_end {
+            range = range.with_inclusive_end();
+        }
+        Ok(range)
     }
 
     /// Returns the start point of the range.
@@ -42,13 +423,593 @@ impl Range {
         self.end - self.start
     }
 
+    /// Returns a version of this range of which end is inclusive.
+    ///
+    /// For discrete and quantized ranges, `end` becomes a candidate value if it is on the grid.
+    pub fn with_inclusive_end(self) -> Self {
+        Self {
+            inclusive_end: true,
+            ..self
+        }
+    }
+
+    /// Returns `true` if the end of the range is inclusive.
+    pub fn is_end_inclusive(self) -> bool {
+        self.inclusive_end
+    }
+
+    /// Returns the quantization step of the range.
+    pub fn q(self) -> Option<f64> {
+        self.q
+    }
+
+    /// Returns a quantized version of this range.
+    ///
+    /// Candidates sampled by [`TpeOptimizer::ask`](crate::TpeOptimizer::ask) are rounded to
+    /// the nearest point `start + q * i` (`i = 0, 1, ..`) less than `end` before their scores are evaluated,
+    /// so the optimizer only considers values that can actually be evaluated.
+    ///
+    /// Discrete and geometric ranges cannot be quantized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let range = Range::new(0.0, 1.0)?.quantize(0.25)?;
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), range);
+    /// let mut rng = rand::thread_rng();
+    /// for _ in 0..10 {
+    ///     let x = optim.ask(&mut rng)?;
+    ///     assert!([0.0, 0.25, 0.5, 0.75].contains(&x));
+    ///     optim.tell(x, (x - 0.5).abs())?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn quantize(self, q: f64) -> Result<Self, RangeError> {
+        if matches!(self.kind, Kind::Discrete { .. } | Kind::Geometric { .. }) {
+            return Err(RangeError::QuantizedDiscreteRange);
+        }
+        if !(q.is_finite() && q > 0.0) {
+            return Err(RangeError::InvalidStep);
+        }
+        Ok(Self { q: Some(q), ..self })
+    }
+
     /// Return `true` if the given point is contained in the range, otherwise `false`.
     pub fn contains(self, v: f64) -> bool {
-        self.start <= v && v < self.end
+        if !(self.start <= v && (v < self.end || (self.inclusive_end && v == self.end))) {
+            return false;
+        }
+        match self.kind {
+            Kind::Linear | Kind::Log | Kind::Cyclic => true,
+            Kind::Discrete { step } => {
+                let i = (v - self.start) / step;
+                (i - i.round()).abs() < 1e-9
+            }
+            Kind::Geometric { ratio } => {
+                let i = (v / self.start).ln() / ratio.ln();
+                (i - i.round()).abs() < 1e-9
+            }
+        }
+    }
+
+    /// Returns `true` if `other` is a subrange of this range.
+    ///
+    /// Both ranges must have the same kind (e.g., log or discrete with the same step).
+    pub fn includes(self, other: Self) -> bool {
+        if self.kind != other.kind {
+            return false;
+        }
+        let end_ok = if other.inclusive_end && !self.inclusive_end {
+            other.end < self.end
+        } else {
+            other.end <= self.end
+        };
+        self.start <= other.start && end_ok
+    }
+
+    /// Samples a value uniformly from the range.
+    ///
+    /// Sampling is performed in the warped space (see [`Range::warp`]),
+    /// so log ranges are sampled log-uniformly and discrete or quantized ranges return only grid points.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::range::Range;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut rng = rand::thread_rng();
+    /// let range = Range::discrete(0.0, 10.0, 2.0)?;
+    /// for _ in 0..10 {
+    ///     assert!(range.contains(range.sample(&mut rng)));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn sample<R: Rng + ?Sized>(self, rng: &mut R) -> f64 {
+        let warped = self.warped();
+        let x = if warped.inclusive_end {
+            rng.gen_range(warped.start..=warped.end)
+        } else {
+            rng.gen_range(warped.start..warped.end)
+        };
+        self.unwarp(x)
+    }
+
+    /// Converts the given value into the space in which densities are estimated.
+    ///
+    /// Points of a discrete range are mapped to the centers of the corresponding bins.
+    /// Points of a geometric range are mapped to the centers of the bins of their exponents.
+    pub fn warp(self, v: f64) -> f64 {
+        match self.kind {
+            Kind::Linear | Kind::Cyclic => v,
+            Kind::Log => v.ln(),
+            Kind::Discrete { step } => v + step * 0.5,
+            Kind::Geometric { ratio } => (v / self.start).ln() / ratio.ln() + 0.5,
+        }
+    }
+
+    /// Inverse of [`Range::warp`].
+    ///
+    /// The result is guaranteed to be contained in the range if `v` is contained in [`Range::warped`].
+    /// If the range is quantized, the result is rounded to the nearest multiple of `q` (see [`Range::quantize`]).
+    pub fn unwarp(self, v: f64) -> f64 {
+        match self.kind {
+            Kind::Linear | Kind::Log | Kind::Cyclic => {
+                let v = if self.kind == Kind::Log { v.exp() } else { v };
+                match self.q {
+                    None => self.clamp(v),
+                    Some(q) => {
+                        let max = (self.grid_points(q) - 1) as f64;
+                        let i = ((v - self.start) / q).round().clamp(0.0, max);
+                        self.clamp(self.start + i * q)
+                    }
+                }
+            }
+            Kind::Discrete { step } => {
+                let i = ((v - self.start) / step).floor();
+                let i = i.clamp(0.0, (self.grid_points(step) - 1) as f64);
+                self.clamp(self.start + i * step)
+            }
+            Kind::Geometric { ratio } => {
+                let i = v.floor().clamp(0.0, (self.exponents(ratio) - 1) as f64);
+                self.clamp(self.start * ratio.powi(i as i32))
+            }
+        }
+    }
+
+    /// Returns the range in the space in which densities are estimated.
+    pub fn warped(self) -> Self {
+        match self.kind {
+            Kind::Linear | Kind::Cyclic => Self { q: None, ..self },
+            Kind::Log => Self {
+                start: self.start.ln(),
+                end: self.end.ln(),
+                kind: Kind::Linear,
+                q: None,
+                inclusive_end: self.inclusive_end,
+            },
+            Kind::Discrete { step } => Self {
+                start: self.start,
+                end: self.start + self.grid_points(step) as f64 * step,
+                kind: Kind::Linear,
+                q: None,
+                inclusive_end: false,
+            },
+            Kind::Geometric { ratio } => Self {
+                start: 0.0,
+                end: self.exponents(ratio) as f64,
+                kind: Kind::Linear,
+                q: None,
+                inclusive_end: false,
+            },
+        }
+    }
+
+    // Number of the points `start + step * i` contained in the range.
+    fn grid_points(self, step: f64) -> usize {
+        self.grid_count((self.end - self.start) / step)
+    }
+
+    // Number of the points `start * ratio^i` contained in the range.
+    fn exponents(self, ratio: f64) -> usize {
+        self.grid_count((self.end / self.start).ln() / ratio.ln())
     }
+
+    fn grid_count(self, n: f64) -> usize {
+        if self.inclusive_end {
+            (n + 1e-9).floor() as usize + 1
+        } else {
+            n.ceil() as usize
+        }
+    }
+
+    pub(crate) fn clamp(self, v: f64) -> f64 {
+        if v < self.start {
+            self.start
+        } else if self.inclusive_end && v >= self.end {
+            self.end
+        } else if v >= self.end {
+            let end = self.end;
+            let prev = if end > 0.0 {
+                f64::from_bits(end.to_bits() - 1)
+            } else if end == 0.0 {
+                -f64::from_bits(1)
+            } else {
+                f64::from_bits(end.to_bits() + 1)
+            };
+            prev.max(self.start)
+        } else {
+            v
+        }
+    }
+}
+
+/// Categorical parameter which owns the list of its choices.
+///
+/// This maps choices to indices (which are passed to the underlying [`TpeOptimizer`]) and vice versa.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::range::Categorical;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let activation = Categorical::new(vec!["relu", "gelu", "tanh"])?;
+/// let mut optim = tpe::TpeOptimizer::new(tpe::histogram_estimator(), activation.range());
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let choice = *activation.ask(&mut optim, &mut rng)?;
+///     let value = if choice == "gelu" { 0.0 } else { 1.0 };
+///     activation.tell(&mut optim, &choice, value)?;
+/// }
+/// assert!(activation.tell(&mut optim, &"sigmoid", 1.0).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Categorical<T> {
+    choices: Vec<T>,
 }
 
-/// Possible errors during [`Range`] construction.
+impl<T> Categorical<T> {
+    /// Makes a new [`Categorical`] instance.
+    pub fn new(choices: Vec<T>) -> Result<Self, RangeError> {
+        if choices.is_empty() {
+            return Err(RangeError::EmptyRange);
+        }
+        Ok(Self { choices })
+    }
+
+    /// Returns the choices.
+    pub fn choices(&self) -> &[T] {
+        &self.choices
+    }
+
+    /// Returns the [`Range`] of the indices of the choices.
+    pub fn range(&self) -> Range {
+        Range::new(0.0, self.choices.len() as f64).expect("unreachable")
+    }
+
+    /// Returns the [`Range`] of the indices of the choices which are regarded as ordered levels.
+    ///
+    /// See [`ordinal_range`](crate::ordinal_range).
+    pub fn ordinal_range(&self) -> Range {
+        crate::ordinal_range(self.choices.len()).expect("unreachable")
+    }
+
+    /// Returns the choice corresponding to the given parameter value (index).
+    pub fn choice(&self, param: f64) -> Option<&T> {
+        if param < 0.0 {
+            return None;
+        }
+        self.choices.get(param as usize)
+    }
+
+    /// Asks the next choice to `optim`.
+    pub fn ask<B, R>(&self, optim: &mut TpeOptimizer<B>, rng: &mut R) -> Result<&T, B::Error>
+    where
+        B: BuildDensityEstimator,
+        R: Rng + ?Sized,
+    {
+        let param = optim.ask(rng)?;
+        Ok(self.choice(param).expect("unreachable"))
+    }
+}
+
+impl<T: PartialEq> Categorical<T> {
+    /// Returns the index of the given choice.
+    pub fn index_of(&self, choice: &T) -> Option<usize> {
+        self.choices.iter().position(|c| c == choice)
+    }
+
+    /// Returns the parameter value (index) corresponding to the given choice.
+    pub fn param(&self, choice: &T) -> Option<f64> {
+        self.index_of(choice).map(|i| i as f64)
+    }
+
+    /// Tells the evaluation result of the given choice to `optim`.
+    pub fn tell<B>(
+        &self,
+        optim: &mut TpeOptimizer<B>,
+        choice: &T,
+        value: f64,
+    ) -> Result<(), TellError>
+    where
+        B: BuildDensityEstimator,
+    {
+        let param = self.param(choice).ok_or(TellError::UnknownChoice)?;
+        optim.tell(param, value)
+    }
+}
+
+/// Union of disjoint ranges (e.g., frequency bands).
+///
+/// The ranges are concatenated (without the gaps between them) into a single [`Range`]
+/// which is passed to the underlying [`TpeOptimizer`],
+/// so densities are estimated and candidates are sampled only within the union.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::range::MultiRange;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let bands = MultiRange::new(&[(88.0, 108.0), (470.0, 710.0)])?;
+/// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), bands.range());
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let freq = bands.ask(&mut optim, &mut rng)?;
+///     assert!(bands.contains(freq));
+///     bands.tell(&mut optim, freq, (freq - 500.0).abs())?;
+/// }
+/// assert!(bands.tell(&mut optim, 200.0, 1.0).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MultiRange {
+    ranges: Vec<Range>,
+    offsets: Vec<f64>,
+    range: Range,
+}
+
+impl MultiRange {
+    /// Makes a new [`MultiRange`] instance from `(start, end)` pairs.
+    ///
+    /// Each pair represents the range `[start, end)`, and the pairs must not overlap.
+    pub fn new(intervals: &[(f64, f64)]) -> Result<Self, RangeError> {
+        let mut ranges = intervals
+            .iter()
+            .map(|&(start, end)| Range::new(start, end))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ranges.is_empty() {
+            return Err(RangeError::EmptyRange);
+        }
+        ranges.sort_by(|a, b| a.start.total_cmp(&b.start));
+        if ranges.windows(2).any(|w| w[1].start < w[0].end) {
+            return Err(RangeError::OverlappingRanges);
+        }
+
+        let mut offsets = Vec::with_capacity(ranges.len());
+        let mut width = 0.0;
+        for r in &ranges {
+            offsets.push(width);
+            width += r.width();
+        }
+        let range = Range::new(0.0, width)?;
+        Ok(Self {
+            ranges,
+            offsets,
+            range,
+        })
+    }
+
+    /// Returns the ranges in ascending order.
+    pub fn ranges(&self) -> &[Range] {
+        &self.ranges
+    }
+
+    /// Returns the [`Range`] of the concatenated ranges.
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    /// Returns `true` if the given point is contained in any of the ranges.
+    pub fn contains(&self, v: f64) -> bool {
+        self.ranges.iter().any(|r| r.contains(v))
+    }
+
+    /// Samples a value uniformly from the union.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        self.value(self.range.sample(rng))
+    }
+
+    /// Returns the value corresponding to the given parameter value (a point of [`MultiRange::range`]).
+    pub fn value(&self, param: f64) -> f64 {
+        let i = self
+            .offsets
+            .partition_point(|&o| o <= param)
+            .saturating_sub(1);
+        let r = self.ranges[i];
+        r.clamp(r.start + (param - self.offsets[i]))
+    }
+
+    /// Returns the parameter value corresponding to the given value.
+    ///
+    /// `None` is returned if the value is not contained in the union.
+    pub fn param(&self, v: f64) -> Option<f64> {
+        let i = self.ranges.iter().position(|r| r.contains(v))?;
+        Some(
+            self.range
+                .clamp(self.offsets[i] + (v - self.ranges[i].start)),
+        )
+    }
+
+    /// Asks the next value to `optim`.
+    pub fn ask<B, R>(&self, optim: &mut TpeOptimizer<B>, rng: &mut R) -> Result<f64, B::Error>
+    where
+        B: BuildDensityEstimator,
+        R: Rng + ?Sized,
+    {
+        optim.ask(rng).map(|param| self.value(param))
+    }
+
+    /// Tells the evaluation result of the given value to `optim`.
+    pub fn tell<B>(&self, optim: &mut TpeOptimizer<B>, v: f64, value: f64) -> Result<(), TellError>
+    where
+        B: BuildDensityEstimator,
+    {
+        let param = self.param(v).ok_or_else(|| TellError::ParamOutOfRange {
+            param: v,
+            range: Range {
+                end: self.ranges[self.ranges.len() - 1].end,
+                ..self.ranges[0]
+            },
+        })?;
+        optim.tell(param, value)
+    }
+}
+
+/// Duration-valued parameter.
+///
+/// Parameter values passed to the underlying [`TpeOptimizer`] are counts of `resolution`,
+/// so asked durations are always multiples of the resolution.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use tpe::range::DurationRange;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let timeout = DurationRange::log_uniform(
+///     Duration::from_millis(10),
+///     Duration::from_secs(10),
+///     Duration::from_millis(1),
+/// )?;
+/// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), timeout.range());
+///
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let d = timeout.ask(&mut optim, &mut rng)?;
+///     assert!(Duration::from_millis(10) <= d && d <= Duration::from_secs(10));
+///     assert_eq!(d.subsec_nanos() % 1_000_000, 0);
+///     timeout.tell(&mut optim, d, (d.as_secs_f64() - 1.0).abs())?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DurationRange {
+    range: Range,
+    resolution: Duration,
+}
+
+impl DurationRange {
+    /// Makes a new [`DurationRange`] instance which takes a value in `[min, max]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use tpe::range::DurationRange;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // A duration fixed to a single value.
+    /// let fixed = DurationRange::new(Duration::from_secs(1), Duration::from_secs(1), Duration::from_millis(1))?;
+    /// let mut optim = tpe::TpeOptimizer::new(tpe::parzen_estimator(), fixed.range());
+    /// assert_eq!(fixed.ask(&mut optim, &mut rand::thread_rng())?, Duration::from_secs(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(min: Duration, max: Duration, resolution: Duration) -> Result<Self, RangeError> {
+        Self::with_range(min, max, resolution, Range::new)
+    }
+
+    /// Makes a new [`DurationRange`] instance of which densities are estimated in the log space.
+    pub fn log_uniform(
+        min: Duration,
+        max: Duration,
+        resolution: Duration,
+    ) -> Result<Self, RangeError> {
+        Self::with_range(min, max, resolution, Range::log_uniform)
+    }
+
+    fn with_range<F>(
+        min: Duration,
+        max: Duration,
+        resolution: Duration,
+        f: F,
+    ) -> Result<Self, RangeError>
+    where
+        F: FnOnce(f64, f64) -> Result<Range, RangeError>,
+    {
+        if resolution.is_zero() {
+            return Err(RangeError::InvalidStep);
+        }
+        let start = (min.as_nanos() as f64 / resolution.as_nanos() as f64).ceil();
+        let end = (max.as_nanos() as f64 / resolution.as_nanos() as f64).floor();
+        if start == end {
+            // A single value (the log scale has no effect on it).
+            let range = Range::discrete(start, start + 1.0, 1.0)?;
+            return Ok(Self { range, resolution });
+        }
+        let range = f(start, end)?.with_inclusive_end().quantize(1.0)?;
+        Ok(Self { range, resolution })
+    }
+
+    /// Returns the [`Range`] of the parameter values (counts of the resolution).
+    pub fn range(&self) -> Range {
+        self.range
+    }
+
+    /// Returns the resolution.
+    pub fn resolution(&self) -> Duration {
+        self.resolution
+    }
+
+    /// Returns the duration corresponding to the given parameter value.
+    pub fn duration(&self, param: f64) -> Duration {
+        let nanos = self.resolution.as_nanos() as u64;
+        Duration::from_nanos((param.round() as u64).saturating_mul(nanos))
+    }
+
+    /// Returns the parameter value corresponding to the given duration.
+    pub fn param(&self, duration: Duration) -> f64 {
+        duration.as_nanos() as f64 / self.resolution.as_nanos() as f64
+    }
+
+    /// Asks the next duration to `optim`.
+    pub fn ask<B, R>(&self, optim: &mut TpeOptimizer<B>, rng: &mut R) -> Result<Duration, B::Error>
+    where
+        B: BuildDensityEstimator,
+        R: Rng + ?Sized,
+    {
+        optim.ask(rng).map(|param| self.duration(param))
+    }
+
+    /// Tells the evaluation result of the given duration to `optim`.
+    pub fn tell<B>(
+        &self,
+        optim: &mut TpeOptimizer<B>,
+        duration: Duration,
+        value: f64,
+    ) -> Result<(), TellError>
+    where
+        B: BuildDensityEstimator,
+    {
+        optim.tell(self.param(duration), value)
+    }
+}
+
+/// Possible errors related to [`Range`].
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum RangeError {
     #[error("not a finite range")]
@@ -58,4 +1019,51 @@ pub enum RangeError {
     #[error("an empty range")]
     /// An empty range.
     EmptyRange,
+
+    #[error("the step of a discrete or quantized range must be a positive finite number")]
+    /// The step of a discrete or quantized range must be a positive finite number.
+    InvalidStep,
+
+    #[error("not a subrange of the current range")]
+    /// Not a subrange of the current range.
+    NotSubrange,
+
+    #[error("a discrete range cannot be quantized")]
+    /// A discrete range cannot be quantized.
+    QuantizedDiscreteRange,
+
+    #[error("the start of a log range must be positive")]
+    /// The start of a log range must be positive.
+    NonPositiveLogRange,
+
+    #[error("ranges must not overlap")]
+    /// Ranges must not overlap.
+    OverlappingRanges,
+
+    #[error("invalid range format")]
+    /// Invalid range format.
+    InvalidFormat,
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_works() -> anyhow::Result<()> {
+        let range: Range = serde_json::from_str(r#"{"start": 0.00001, "end": 0.1, "log": true}"#)?;
+        assert_eq!(range.to_string(), "log:0.00001..0.1");
+
+        let range = Range::discrete(0.0, 10.0, 2.0)?.with_inclusive_end();
+        let json = serde_json::to_string(&range)?;
+        assert_eq!(
+            json,
+            r#"{"start":0.0,"end":10.0,"step":2.0,"inclusive_end":true}"#
+        );
+        let range: Range = serde_json::from_str(&json)?;
+        assert!(range.contains(10.0));
+
+        assert!(serde_json::from_str::<Range>(r#"{"start": 1.0, "end": 0.0}"#).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,125 @@
+//! Samplers sharing the ask/tell interface of [`TpeOptimizer`].
+//!
+//! [`Sampler`] abstracts over the samplers, so the search strategy can be swapped via generics or trait objects.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::convert::Infallible;
+//! use tpe::sampler::{GridSampler, RandomSampler, Sampler};
+//!
+//! fn minimize<S: Sampler>(sampler: &mut S) -> anyhow::Result<()>
+//! where
+//!     S::Error: std::error::Error + Send + Sync + 'static,
+//! {
+//!     let mut rng = rand::thread_rng();
+//!     for _ in 0..20 {
+//!         let x = sampler.ask(&mut rng)?;
+//!         sampler.tell(x, x * x)?;
+//!     }
+//!     Ok(())
+//! }
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let range = tpe::range(-5.0, 5.0)?;
+//! minimize(&mut tpe::TpeOptimizer::new(tpe::parzen_estimator(), range))?;
+//!
+//! let mut samplers: Vec<Box<dyn Sampler<Error = Infallible>>> = vec![
+//!     Box::new(RandomSampler::new(range)),
+//!     Box::new(GridSampler::evenly_spaced(range, 10)?),
+//! ];
+//! for sampler in &mut samplers {
+//!     minimize(sampler)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::BuildDensityEstimator;
+use crate::range::Range;
+use crate::{TellError, TpeOptimizer};
+use rand::RngCore;
+
+pub use self::grid::{GridError, GridSampler};
+pub use self::random::RandomSampler;
+
+mod grid;
+mod random;
+
+/// Strategy to sample a parameter value in a range by the ask/tell interface.
+pub trait Sampler {
+    /// Possible error during asking a parameter value.
+    type Error;
+
+    /// Returns the next parameter value to be evaluated.
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error>;
+
+    /// Tells the evaluation result of a parameter value to the sampler.
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError>;
+
+    /// Returns the range of the parameter.
+    fn param_range(&self) -> Range;
+}
+
+impl<T: BuildDensityEstimator> Sampler for TpeOptimizer<T> {
+    type Error = T::Error;
+
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error> {
+        TpeOptimizer::ask(self, rng)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        TpeOptimizer::tell(self, param, value)
+    }
+
+    fn param_range(&self) -> Range {
+        TpeOptimizer::param_range(self)
+    }
+}
+
+impl Sampler for RandomSampler {
+    type Error = std::convert::Infallible;
+
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error> {
+        RandomSampler::ask(self, rng)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        RandomSampler::tell(self, param, value)
+    }
+
+    fn param_range(&self) -> Range {
+        RandomSampler::param_range(self)
+    }
+}
+
+impl Sampler for GridSampler {
+    type Error = std::convert::Infallible;
+
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error> {
+        GridSampler::ask(self, rng)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        GridSampler::tell(self, param, value)
+    }
+
+    fn param_range(&self) -> Range {
+        GridSampler::param_range(self)
+    }
+}
+
+impl<S: Sampler + ?Sized> Sampler for Box<S> {
+    type Error = S::Error;
+
+    fn ask(&mut self, rng: &mut dyn RngCore) -> Result<f64, Self::Error> {
+        (**self).ask(rng)
+    }
+
+    fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        (**self).tell(param, value)
+    }
+
+    fn param_range(&self) -> Range {
+        (**self).param_range()
+    }
+}
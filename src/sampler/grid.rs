@@ -0,0 +1,169 @@
+use crate::range::{Range, RangeError};
+use crate::TellError;
+use rand::Rng;
+use std::convert::Infallible;
+
+/// Sampler that walks the given points of a range in order.
+///
+/// After all points have been asked, the walk starts over from the first point.
+/// Multi-dimensional grids can be made by [`GridSampler::product`].
+///
+/// # Examples
+///
+/// ```
+/// use tpe::sampler::GridSampler;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut rng = rand::thread_rng();
+/// let mut axes = GridSampler::product(vec![
+///     GridSampler::new(tpe::range(0.0, 1.0)?, vec![0.0, 0.5])?,
+///     GridSampler::new(tpe::categorical_range(3)?, vec![0.0, 1.0, 2.0])?,
+/// ]);
+///
+/// let mut visited = Vec::new();
+/// while !axes[0].is_exhausted() {
+///     let params = axes
+///         .iter_mut()
+///         .map(|a| a.ask(&mut rng))
+///         .collect::<Result<Vec<_>, _>>()?;
+///     for (a, &p) in axes.iter_mut().zip(&params) {
+///         a.tell(p, params.iter().sum())?;
+///     }
+///     visited.push(params);
+/// }
+/// assert_eq!(visited.len(), 6);
+/// assert_eq!(visited[1], [0.0, 1.0]);
+/// assert_eq!(visited[3], [0.5, 0.0]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GridSampler {
+    range: Range,
+    points: Vec<f64>,
+    stride: usize,
+    grid_size: usize,
+    asked: usize,
+    trials: Vec<(f64, f64)>,
+}
+
+impl GridSampler {
+    /// Makes a new [`GridSampler`] instance which walks `points` in order.
+    pub fn new(range: Range, points: Vec<f64>) -> Result<Self, GridError> {
+        if points.is_empty() {
+            return Err(GridError::Empty);
+        }
+        if let Some(&point) = points.iter().find(|&&p| !range.contains(p)) {
+            return Err(GridError::PointOutOfRange { point, range });
+        }
+        Ok(Self {
+            range,
+            grid_size: points.len(),
+            points,
+            stride: 1,
+            asked: 0,
+            trials: Vec::new(),
+        })
+    }
+
+    /// Makes a new [`GridSampler`] instance which walks `n` evenly spaced points of the range.
+    ///
+    /// The points are the centers of `n` bins of equal width in the warped space (see [`Range::warped`]).
+    /// Duplicate points (e.g., in a discrete range with fewer than `n` values) are removed.
+    pub fn evenly_spaced(range: Range, n: usize) -> Result<Self, GridError> {
+        let warped = range.warped();
+        let mut points = (0..n)
+            .map(|i| range.unwarp(warped.start() + warped.width() * (i as f64 + 0.5) / n as f64))
+            .collect::<Vec<_>>();
+        points.dedup();
+        Self::new(range, points)
+    }
+
+    /// Combines one-dimensional grids into their Cartesian product.
+    ///
+    /// When each returned sampler is asked once per trial, the trials enumerate all combinations of the points
+    /// (the last axis changes fastest).
+    pub fn product(mut axes: Vec<GridSampler>) -> Vec<GridSampler> {
+        let grid_size = axes.iter().map(|a| a.points.len()).product();
+        let mut stride = 1;
+        for axis in axes.iter_mut().rev() {
+            axis.stride = stride;
+            axis.grid_size = grid_size;
+            axis.asked = 0;
+            stride *= axis.points.len();
+        }
+        axes
+    }
+
+    /// Returns the next grid point to be evaluated.
+    ///
+    /// `rng` is unused and only exists for compatibility with [`TpeOptimizer::ask`](crate::TpeOptimizer::ask).
+    pub fn ask<R: Rng + ?Sized>(&mut self, _rng: &mut R) -> Result<f64, Infallible> {
+        let i = (self.asked / self.stride) % self.points.len();
+        self.asked += 1;
+        Ok(self.points[i])
+    }
+
+    /// Tells the evaluation result of a parameter value to the sampler.
+    ///
+    /// The result doesn't affect the walk, but is kept in [`GridSampler::trials`].
+    pub fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::NanValue);
+        }
+        if !param.is_nan() && !self.range.contains(param) {
+            return Err(TellError::ParamOutOfRange {
+                param,
+                range: self.range,
+            });
+        }
+        self.trials.push((param, value));
+        Ok(())
+    }
+
+    /// Returns the range of the parameter.
+    pub fn param_range(&self) -> Range {
+        self.range
+    }
+
+    /// Returns the points of the grid.
+    pub fn points(&self) -> &[f64] {
+        &self.points
+    }
+
+    /// Returns the number of the points of the whole (possibly multi-dimensional) grid.
+    pub fn grid_size(&self) -> usize {
+        self.grid_size
+    }
+
+    /// Returns `true` if all points of the whole grid have been asked.
+    pub fn is_exhausted(&self) -> bool {
+        self.asked >= self.grid_size
+    }
+
+    /// Returns an iterator over the told trials.
+    pub fn trials(&self) -> impl '_ + Iterator<Item = (f64, f64)> {
+        self.trials.iter().copied()
+    }
+}
+
+/// Possible errors during building [`GridSampler`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum GridError {
+    #[error("the grid has no points")]
+    /// The grid has no points.
+    Empty,
+
+    #[error("the grid point {point} is out of the range {range}")]
+    /// A grid point is out of the range.
+    PointOutOfRange {
+        /// Grid point.
+        point: f64,
+        /// Range of the parameter.
+        range: Range,
+    },
+
+    #[error(transparent)]
+    /// Invalid parameter range.
+    Range(#[from] RangeError),
+}
@@ -0,0 +1,74 @@
+use crate::range::Range;
+use crate::TellError;
+use rand::Rng;
+use std::convert::Infallible;
+
+/// Sampler that draws parameter values uniformly from a range (in its warped space).
+///
+/// Told results don't affect the sampling, so this can be used as a random search baseline
+/// in place of [`TpeOptimizer`](crate::TpeOptimizer).
+///
+/// # Examples
+///
+/// ```
+/// use tpe::sampler::RandomSampler;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut rng = rand::thread_rng();
+/// let mut sampler = RandomSampler::new(tpe::range(-5.0, 5.0)?);
+/// for _ in 0..100 {
+///     let x = sampler.ask(&mut rng)?;
+///     assert!(-5.0 <= x && x < 5.0);
+///     sampler.tell(x, x * x)?;
+/// }
+/// assert_eq!(sampler.trials().count(), 100);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RandomSampler {
+    range: Range,
+    trials: Vec<(f64, f64)>,
+}
+
+impl RandomSampler {
+    /// Makes a new [`RandomSampler`] instance.
+    pub fn new(param_range: Range) -> Self {
+        Self {
+            range: param_range,
+            trials: Vec::new(),
+        }
+    }
+
+    /// Returns the next parameter value to be evaluated.
+    pub fn ask<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Result<f64, Infallible> {
+        Ok(self.range.sample(rng))
+    }
+
+    /// Tells the evaluation result of a parameter value to the sampler.
+    ///
+    /// The result doesn't affect the sampling, but is kept in [`RandomSampler::trials`].
+    pub fn tell(&mut self, param: f64, value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::NanValue);
+        }
+        if !param.is_nan() && !self.range.contains(param) {
+            return Err(TellError::ParamOutOfRange {
+                param,
+                range: self.range,
+            });
+        }
+        self.trials.push((param, value));
+        Ok(())
+    }
+
+    /// Returns the range of the parameter.
+    pub fn param_range(&self) -> Range {
+        self.range
+    }
+
+    /// Returns an iterator over the told trials.
+    pub fn trials(&self) -> impl '_ + Iterator<Item = (f64, f64)> {
+        self.trials.iter().copied()
+    }
+}
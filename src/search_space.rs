@@ -0,0 +1,342 @@
+//! Named multi-dimensional search spaces.
+use crate::density_estimation::{DefaultEstimatorBuilder, EstimatorBuildError};
+use crate::range::{Range, RangeError};
+use crate::study::{DimensionAwareDefaults, Study};
+use crate::{histogram_estimator, ordinal_range, parzen_estimator, TellError, TpeOptimizerBuilder};
+
+/// Builder of [`SearchSpace`].
+///
+/// Each parameter is optimized by a [`TpeOptimizer`] with an estimator suitable for its kind
+/// (a Parzen estimator for numerical and ordinal parameters and a histogram estimator for categorical ones).
+#[derive(Debug)]
+pub struct SearchSpaceBuilder {
+    params: Vec<(String, Kind)>,
+    seed: Option<u64>,
+    dimension_aware_defaults: bool,
+}
+
+impl SearchSpaceBuilder {
+    /// Makes a new empty [`SearchSpaceBuilder`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a floating point parameter which takes a value in `[low, high)`.
+    pub fn add_float(&mut self, name: &str, low: f64, high: f64) -> Result<&mut Self, BuildError> {
+        let range = Range::new(low, high)?;
+        self.add(name, Kind::Float(range))
+    }
+
+    /// Adds a floating point parameter which takes a value in `[low, high)` and is optimized in the log space.
+    pub fn add_log_float(
+        &mut self,
+        name: &str,
+        low: f64,
+        high: f64,
+    ) -> Result<&mut Self, BuildError> {
+        let range = Range::log_uniform(low, high)?;
+        self.add(name, Kind::Float(range))
+    }
+
+    /// Adds an integer parameter which takes a value in `[low, high]`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::search_space::SearchSpaceBuilder;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// // A parameter fixed to a single value.
+    /// let mut space = SearchSpaceBuilder::new().add_int("layers", 3, 3)?.build();
+    /// assert_eq!(space.ask()?.int("layers"), Some(3));
+    ///
+    /// assert!(SearchSpaceBuilder::new().add_int("layers", 3, 2).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn add_int(&mut self, name: &str, low: i64, high: i64) -> Result<&mut Self, BuildError> {
+        let range = Range::discrete(low as f64, high as f64 + 1.0, 1.0)?;
+        self.add(name, Kind::Int(range))
+    }
+
+    /// Adds a categorical parameter which takes one of the given choices.
+    pub fn add_categorical(
+        &mut self,
+        name: &str,
+        choices: &[&str],
+    ) -> Result<&mut Self, BuildError> {
+        let range = Range::new(0.0, choices.len() as f64)?;
+        let choices = choices.iter().map(|c| c.to_string()).collect();
+        self.add(name, Kind::Categorical(range, choices))
+    }
+
+    /// Adds an ordinal parameter which takes one of the given levels (in ascending order).
+    pub fn add_ordinal(&mut self, name: &str, levels: &[&str]) -> Result<&mut Self, BuildError> {
+        let range = ordinal_range(levels.len())?;
+        let levels = levels.iter().map(|c| c.to_string()).collect();
+        self.add(name, Kind::Ordinal(range, levels))
+    }
+
+    /// Adds a parameter described by the given [`ParamSpec`].
+    pub fn add_param(&mut self, name: &str, spec: &ParamSpec) -> Result<&mut Self, BuildError> {
+        match spec {
+            ParamSpec::Float { low, high } => self.add_float(name, *low, *high),
+            ParamSpec::LogFloat { low, high } => self.add_log_float(name, *low, *high),
+            ParamSpec::Int { low, high } => self.add_int(name, *low, *high),
+            ParamSpec::Categorical { choices } => {
+                let choices = choices.iter().map(|c| c.as_str()).collect::<Vec<_>>();
+                self.add_categorical(name, &choices)
+            }
+            ParamSpec::Ordinal { levels } => {
+                let levels = levels.iter().map(|c| c.as_str()).collect::<Vec<_>>();
+                self.add_ordinal(name, &levels)
+            }
+        }
+    }
+
+    /// Sets the seed of the random number generator.
+    ///
+    /// If not set, the generator is seeded from the system entropy.
+    pub fn seed(&mut self, seed: u64) -> &mut Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets whether the hyperparameters of the optimizers are scaled with the number of the parameters
+    /// (see [`DimensionAwareDefaults`]).
+    ///
+    /// The default value is `true`.
+    pub fn dimension_aware_defaults(&mut self, enabled: bool) -> &mut Self {
+        self.dimension_aware_defaults = enabled;
+        self
+    }
+
+    /// Builds a [`SearchSpace`] with the given settings.
+    pub fn build(&self) -> SearchSpace {
+        let defaults = DimensionAwareDefaults::new(self.params.len());
+        let builder = if self.dimension_aware_defaults {
+            defaults.optimizer_builder()
+        } else {
+            TpeOptimizerBuilder::new()
+        };
+        let optimizers = self
+            .params
+            .iter()
+            .map(|(_, kind)| match kind {
+                Kind::Float(range) | Kind::Int(range) | Kind::Ordinal(range, _) => {
+                    builder.build(parzen_estimator(), *range)
+                }
+                Kind::Categorical(range, _) => builder.build(histogram_estimator(), *range),
+            })
+            .collect::<Result<_, _>>()
+            .expect("unreachable");
+        let mut study = match self.seed {
+            Some(seed) => Study::with_seed(optimizers, seed),
+            None => Study::new(optimizers),
+        };
+        if self.dimension_aware_defaults {
+            study.set_startup_trials(defaults.startup_trials);
+        }
+        SearchSpace {
+            params: self.params.clone(),
+            study,
+        }
+    }
+
+    fn add(&mut self, name: &str, kind: Kind) -> Result<&mut Self, BuildError> {
+        if self.params.iter().any(|(n, _)| n == name) {
+            return Err(BuildError::DuplicateName {
+                name: name.to_owned(),
+            });
+        }
+        self.params.push((name.to_owned(), kind));
+        Ok(self)
+    }
+}
+
+impl Default for SearchSpaceBuilder {
+    fn default() -> Self {
+        Self {
+            params: Vec::new(),
+            seed: None,
+            dimension_aware_defaults: true,
+        }
+    }
+}
+
+/// Named search space of which parameters are optimized together.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::search_space::SearchSpaceBuilder;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut space = SearchSpaceBuilder::new()
+///     .add_log_float("lr", 1e-5, 1e-1)?
+///     .add_int("layers", 1, 4)?
+///     .add_categorical("act", &["relu", "gelu"])?
+///     .seed(0)
+///     .build();
+///
+/// for _ in 0..10 {
+///     let params = space.ask()?;
+///     let lr = params.float("lr").expect("unreachable");
+///     let layers = params.int("layers").expect("unreachable");
+///     let act = params.categorical("act").expect("unreachable");
+///     assert!(1e-5 <= lr && lr < 1e-1);
+///     assert!((1..=4).contains(&layers));
+///     assert!(["relu", "gelu"].contains(&act));
+///
+///     let value = (lr.log10() + 3.0).abs() + layers as f64 + if act == "relu" { 1.0 } else { 0.0 };
+///     space.tell(params, value)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct SearchSpace {
+    params: Vec<(String, Kind)>,
+    study: Study<DefaultEstimatorBuilder>,
+}
+
+impl SearchSpace {
+    /// Returns the next parameter values to be evaluated.
+    pub fn ask(&mut self) -> Result<ParamSet, EstimatorBuildError> {
+        let raw = self.study.ask()?;
+        let values = self
+            .params
+            .iter()
+            .zip(raw.iter())
+            .map(|((name, kind), &p)| {
+                let value = match kind {
+                    Kind::Float(_) => ParamValue::Float(p),
+                    Kind::Int(_) => ParamValue::Int(p as i64),
+                    Kind::Categorical(_, choices) | Kind::Ordinal(_, choices) => {
+                        ParamValue::Categorical(choices[p as usize].clone())
+                    }
+                };
+                (name.clone(), value)
+            })
+            .collect();
+        Ok(ParamSet { values, raw })
+    }
+
+    /// Tells the evaluation result of a parameter set returned by [`SearchSpace::ask`].
+    pub fn tell(&mut self, params: ParamSet, value: f64) -> Result<(), TellError> {
+        self.study.tell(params.raw, value)
+    }
+
+    /// Returns the underlying [`Study`].
+    pub fn study(&self) -> &Study<DefaultEstimatorBuilder> {
+        &self.study
+    }
+}
+
+/// Parameter values returned by [`SearchSpace::ask`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamSet {
+    values: Vec<(String, ParamValue)>,
+    raw: Vec<f64>,
+}
+
+impl ParamSet {
+    /// Returns the value of the given parameter.
+    pub fn get(&self, name: &str) -> Option<&ParamValue> {
+        self.values.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Returns the value of the given floating point parameter.
+    pub fn float(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            ParamValue::Float(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the given integer parameter.
+    pub fn int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            ParamValue::Int(v) => Some(*v),
+            _ => None,
+        }
+    }
+
+    /// Returns the choice of the given categorical (or ordinal) parameter.
+    pub fn categorical(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            ParamValue::Categorical(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns an iterator over the parameter names and values in the order they were added.
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (&str, &ParamValue)> {
+        self.values.iter().map(|(n, v)| (n.as_str(), v))
+    }
+}
+
+/// Value of a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    /// Floating point value.
+    Float(f64),
+
+    /// Integer value.
+    Int(i64),
+
+    /// Categorical choice (or ordinal level).
+    Categorical(String),
+}
+
+/// Description of a parameter (see [`SearchSpaceBuilder::add_param`]).
+///
+/// If the `serde` feature is enabled, this can be (de)serialized as a map tagged by `type`
+/// (e.g., `{"type": "log_float", "low": 1e-5, "high": 1e-1}`).
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum ParamSpec {
+    /// See [`SearchSpaceBuilder::add_float`].
+    #[allow(missing_docs)]
+    Float { low: f64, high: f64 },
+
+    /// See [`SearchSpaceBuilder::add_log_float`].
+    #[allow(missing_docs)]
+    LogFloat { low: f64, high: f64 },
+
+    /// See [`SearchSpaceBuilder::add_int`].
+    #[allow(missing_docs)]
+    Int { low: i64, high: i64 },
+
+    /// See [`SearchSpaceBuilder::add_categorical`].
+    #[allow(missing_docs)]
+    Categorical { choices: Vec<String> },
+
+    /// See [`SearchSpaceBuilder::add_ordinal`].
+    #[allow(missing_docs)]
+    Ordinal { levels: Vec<String> },
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+    Float(Range),
+    Int(Range),
+    Categorical(Range, Vec<String>),
+    Ordinal(Range, Vec<String>),
+}
+
+/// Possible errors during building [`SearchSpace`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum BuildError {
+    #[error(transparent)]
+    /// Invalid parameter range.
+    Range(#[from] RangeError),
+
+    #[error("duplicate parameter name {name:?}")]
+    /// The parameter name has already been added.
+    DuplicateName {
+        /// Parameter name.
+        name: String,
+    },
+}
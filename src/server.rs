@@ -0,0 +1,204 @@
+//! HTTP service that exposes ask/tell of named studies.
+//!
+//! The service has the following endpoints (request and response bodies are JSON):
+//!
+//! | Endpoint | Request | Response |
+//! |----------|---------|----------|
+//! | `POST /studies` | `{"name": "foo", "params": [{"name": "x", "type": "float", "low": 0.0, "high": 1.0}], "seed": 0}` | `{"name": "foo"}` |
+//! | `POST /studies/{name}/ask` | - | `{"trial_id": 0, "params": {"x": 0.5}}` |
+//! | `POST /studies/{name}/tell` | `{"trial_id": 0, "value": 1.2}` | `{}` |
+//! | `GET /studies/{name}` | - | `{"name": "foo", "trials": 1, "pending_trials": 0, "best_value": 1.2}` |
+//!
+//! The `params` of a study are [`ParamSpec`]s with `name`s, and `seed` is optional.
+//! Errors are returned with 4xx status codes and bodies like `{"error": "unknown study \"foo\""}`.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::server::TpeServer;
+//!
+//! let mut server = TpeServer::new();
+//! let (status, _) = server.handle(
+//!     "POST",
+//!     "/studies",
+//!     r#"{"name": "foo", "params": [{"name": "act", "type": "categorical", "choices": ["relu"]}]}"#,
+//! );
+//! assert_eq!(status, 201);
+//!
+//! let (status, body) = server.handle("POST", "/studies/foo/ask", "");
+//! assert_eq!(status, 200);
+//! assert_eq!(body, r#"{"params":{"act":"relu"},"trial_id":0}"#);
+//!
+//! let (status, _) = server.handle("POST", "/studies/foo/tell", r#"{"trial_id": 0, "value": 1.0}"#);
+//! assert_eq!(status, 200);
+//! ```
+//!
+//! Use [`TpeServer::serve`] to listen on a socket.
+use crate::search_space::{ParamSet, ParamSpec, ParamValue, SearchSpace, SearchSpaceBuilder};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::ToSocketAddrs;
+
+/// HTTP ask/tell service backed by [`SearchSpace`]s.
+#[derive(Debug, Default)]
+pub struct TpeServer {
+    studies: HashMap<String, StudyEntry>,
+}
+
+#[derive(Debug)]
+struct StudyEntry {
+    space: SearchSpace,
+    pending: HashMap<u64, ParamSet>,
+    next_trial_id: u64,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CreateStudyRequest {
+    name: String,
+    params: Vec<NamedParamSpec>,
+    seed: Option<u64>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct NamedParamSpec {
+    name: String,
+    #[serde(flatten)]
+    spec: ParamSpec,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct TellRequest {
+    trial_id: u64,
+    value: f64,
+}
+
+impl TpeServer {
+    /// Makes a new [`TpeServer`] instance without studies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves requests on the given address until an I/O error occurs.
+    ///
+    /// Requests are handled one at a time.
+    pub fn serve<A: ToSocketAddrs>(mut self, addr: A) -> std::io::Result<()> {
+        let server = tiny_http::Server::http(addr).map_err(std::io::Error::other)?;
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            let (status, body) = match request.as_reader().read_to_string(&mut body) {
+                Ok(_) => self.handle(request.method().as_str(), request.url(), &body),
+                Err(e) => error(400, e),
+            };
+            let header = tiny_http::Header::from_bytes("Content-Type", "application/json")
+                .expect("unreachable");
+            let response = tiny_http::Response::from_string(body)
+                .with_status_code(status)
+                .with_header(header);
+            request.respond(response)?;
+        }
+        Ok(())
+    }
+
+    /// Handles a request and returns the status code and the (JSON) body of the response.
+    pub fn handle(&mut self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let path = path.split('?').next().unwrap_or(path);
+        let segments = path
+            .trim_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        match (method, segments.as_slice()) {
+            ("POST", ["studies"]) => self.create_study(body),
+            ("GET", ["studies", name]) => self.with_study(name, |name, s| {
+                let best = s.space.study().best_trial().map(|t| t.value);
+                ok(json!({
+                    "name": name,
+                    "trials": s.space.study().trials().len(),
+                    "pending_trials": s.pending.len(),
+                    "best_value": best,
+                }))
+            }),
+            ("POST", ["studies", name, "ask"]) => self.with_study(name, |_, s| {
+                let params = match s.space.ask() {
+                    Ok(params) => params,
+                    Err(e) => return error(500, e),
+                };
+                let values = params
+                    .iter()
+                    .map(|(name, v)| {
+                        let v = match v {
+                            ParamValue::Float(v) => json!(v),
+                            ParamValue::Int(v) => json!(v),
+                            ParamValue::Categorical(v) => json!(v),
+                        };
+                        (name.to_owned(), v)
+                    })
+                    .collect::<serde_json::Map<_, _>>();
+                let trial_id = s.next_trial_id;
+                s.next_trial_id += 1;
+                s.pending.insert(trial_id, params);
+                ok(json!({"trial_id": trial_id, "params": values}))
+            }),
+            ("POST", ["studies", name, "tell"]) => self.with_study(name, |_, s| {
+                let request: TellRequest = match serde_json::from_str(body) {
+                    Ok(r) => r,
+                    Err(e) => return error(400, e),
+                };
+                let Some(params) = s.pending.remove(&request.trial_id) else {
+                    return error(404, format!("unknown trial {}", request.trial_id));
+                };
+                if let Err(e) = s.space.tell(params.clone(), request.value) {
+                    s.pending.insert(request.trial_id, params);
+                    return error(400, e);
+                }
+                ok(json!({}))
+            }),
+            _ => error(404, format!("unknown endpoint {} {}", method, path)),
+        }
+    }
+
+    fn create_study(&mut self, body: &str) -> (u16, String) {
+        let request: CreateStudyRequest = match serde_json::from_str(body) {
+            Ok(r) => r,
+            Err(e) => return error(400, e),
+        };
+        if self.studies.contains_key(&request.name) {
+            return error(409, format!("study {:?} already exists", request.name));
+        }
+
+        let mut builder = SearchSpaceBuilder::new();
+        for p in &request.params {
+            if let Err(e) = builder.add_param(&p.name, &p.spec) {
+                return error(400, e);
+            }
+        }
+        if let Some(seed) = request.seed {
+            builder.seed(seed);
+        }
+        let entry = StudyEntry {
+            space: builder.build(),
+            pending: HashMap::new(),
+            next_trial_id: 0,
+        };
+        self.studies.insert(request.name.clone(), entry);
+        (201, json!({"name": request.name}).to_string())
+    }
+
+    fn with_study<F>(&mut self, name: &str, f: F) -> (u16, String)
+    where
+        F: FnOnce(&str, &mut StudyEntry) -> (u16, String),
+    {
+        match self.studies.get_mut(name) {
+            Some(s) => f(name, s),
+            None => error(404, format!("unknown study {:?}", name)),
+        }
+    }
+}
+
+fn ok(body: Value) -> (u16, String) {
+    (200, body.to_string())
+}
+
+fn error<E: std::fmt::Display>(status: u16, e: E) -> (u16, String) {
+    (status, json!({"error": e.to_string()}).to_string())
+}
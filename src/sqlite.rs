@@ -0,0 +1,204 @@
+//! SQLite storage backend that makes a [`Study`](crate::study::Study) crash-safe.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::sqlite::SqliteStorage;
+//! use tpe::study::Study;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let dir = tempfile::tempdir()?;
+//! let path = dir.path().join("study.db");
+//! let new_study = || -> anyhow::Result<Study> {
+//!     Ok(Study::with_seed(
+//!         vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)],
+//!         0,
+//!     ))
+//! };
+//!
+//! {
+//!     let mut storage = SqliteStorage::open(&path)?;
+//!     let mut study = new_study()?;
+//!     for _ in 0..10 {
+//!         let params = study.ask_and_store(&mut storage)?;
+//!         let value = params[0].powi(2);
+//!         study.tell_and_store(&mut storage, params, value)?;
+//!     }
+//!
+//!     // Crashes during the evaluation.
+//!     study.ask_and_store(&mut storage)?;
+//! }
+//!
+//! // Resumes the study.
+//! let storage = SqliteStorage::open(&path)?;
+//! let mut study = new_study()?;
+//! study.restore(&storage)?;
+//! assert_eq!(study.trials().len(), 10);
+//! assert_eq!(storage.pending_trials()?.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+use crate::study::{StudyStorage, Trial};
+use rusqlite::{params, Connection};
+use std::path::Path;
+
+/// [`StudyStorage`] that persists asked and told trials to a SQLite database.
+///
+/// Each trial is recorded when it is asked by [`Study::ask_and_store`](crate::study::Study::ask_and_store)
+/// and completed when its result is told by [`Study::tell_and_store`](crate::study::Study::tell_and_store).
+/// Every record is committed immediately, so completed trials survive crashes.
+#[derive(Debug)]
+pub struct SqliteStorage {
+    conn: Connection,
+}
+
+impl SqliteStorage {
+    /// Opens (or creates) the database at the given path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, SqliteError> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    /// Opens a new in-memory database.
+    pub fn open_in_memory() -> Result<Self, SqliteError> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, SqliteError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS trials (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                params TEXT NOT NULL,
+                value REAL
+            );
+            CREATE TABLE IF NOT EXISTS best_trials (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                params TEXT NOT NULL,
+                value REAL NOT NULL
+            )",
+        )?;
+        Ok(Self { conn })
+    }
+
+    /// Returns the completed trials in the order they were asked.
+    pub fn completed_trials(&self) -> Result<Vec<Trial>, SqliteError> {
+        self.query("SELECT id, params, value FROM trials WHERE value IS NOT NULL ORDER BY id")
+            .map(|trials| {
+                trials
+                    .into_iter()
+                    .map(|t| Trial {
+                        params: t.params,
+                        value: t.value.expect("unreachable"),
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns the parameters of the trials which were asked but not told
+    /// (e.g., because the process crashed during the evaluation).
+    pub fn pending_trials(&self) -> Result<Vec<Vec<f64>>, SqliteError> {
+        self.query("SELECT id, params, value FROM trials WHERE value IS NULL ORDER BY id")
+            .map(|trials| trials.into_iter().map(|t| t.params).collect())
+    }
+
+    fn query(&self, sql: &str) -> Result<Vec<StoredTrial>, SqliteError> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get::<_, String>(1)?, row.get(2)?))
+        })?;
+        let mut trials = Vec::new();
+        for row in rows {
+            let (id, params, value) = row?;
+            let params = if params.is_empty() {
+                Vec::new()
+            } else {
+                params
+                    .split(',')
+                    .map(|p| p.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| SqliteError::Corrupted { trial_id: id })?
+            };
+            trials.push(StoredTrial { params, value });
+        }
+        Ok(trials)
+    }
+}
+
+impl StudyStorage for SqliteStorage {
+    type Error = SqliteError;
+
+    fn record_ask(&mut self, params: &[f64]) -> Result<(), Self::Error> {
+        self.conn.execute(
+            "INSERT INTO trials (params) VALUES (?1)",
+            params![encode_params(params)],
+        )?;
+        Ok(())
+    }
+
+    // Completes the oldest pending trial with the same parameters if it exists.
+    fn append_trial(&mut self, trial: &Trial) -> Result<(), Self::Error> {
+        let params = encode_params(&trial.params);
+        let updated = self.conn.execute(
+            "UPDATE trials SET value = ?1 WHERE id = (
+                SELECT MIN(id) FROM trials WHERE value IS NULL AND params = ?2
+            )",
+            params![trial.value, params],
+        )?;
+        if updated == 0 {
+            self.conn.execute(
+                "INSERT INTO trials (params, value) VALUES (?1, ?2)",
+                params![params, trial.value],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn load_trials(&self) -> Result<Vec<Trial>, Self::Error> {
+        self.completed_trials()
+    }
+
+    fn record_best(&mut self, trial: &Trial) -> Result<(), Self::Error> {
+        self.conn.execute(
+            "INSERT INTO best_trials (params, value) VALUES (?1, ?2)",
+            params![encode_params(&trial.params), trial.value],
+        )?;
+        Ok(())
+    }
+
+    fn load_best(&self) -> Result<Option<Trial>, Self::Error> {
+        let trials =
+            self.query("SELECT id, params, value FROM best_trials ORDER BY id DESC LIMIT 1")?;
+        Ok(trials.into_iter().next().map(|t| Trial {
+            params: t.params,
+            value: t.value.expect("unreachable"),
+        }))
+    }
+}
+
+fn encode_params(params: &[f64]) -> String {
+    params
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug)]
+struct StoredTrial {
+    params: Vec<f64>,
+    value: Option<f64>,
+}
+
+/// Possible errors during [`SqliteStorage`] operations.
+#[derive(Debug, thiserror::Error)]
+pub enum SqliteError {
+    #[error(transparent)]
+    /// Database error.
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("the parameters of the trial {trial_id} are corrupted")]
+    /// The stored parameters of the trial can't be parsed.
+    Corrupted {
+        /// Trial identifier.
+        trial_id: i64,
+    },
+}
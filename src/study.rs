@@ -0,0 +1,898 @@
+//! Multi-parameter optimization loop.
+//!
+//! The constants of this module are the names of the metrics emitted if the `metrics` feature is enabled.
+use crate::density_estimation::{BuildDensityEstimator, DefaultEstimatorBuilder};
+use crate::pruning::{Pruner, PruningTrial, TrialOutcome};
+use crate::subset::{self, SubsetError};
+use crate::{TellError, TpeOptimizer, TpeOptimizerBuilder};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::num::NonZeroUsize;
+
+pub use self::storage::{MemoryStorage, StorageError, StudyStorage};
+
+mod storage;
+
+/// Name of the counter of the trials told to a [`Study`].
+#[cfg(feature = "metrics")]
+pub const TRIALS_COMPLETED_TOTAL: &str = "tpe_trials_completed_total";
+
+/// Name of the counter of the trials pruned by [`Study::optimize_with_pruner`].
+#[cfg(feature = "metrics")]
+pub const TRIALS_PRUNED_TOTAL: &str = "tpe_trials_pruned_total";
+
+/// Name of the gauge of the ratio of the pruned trials to all the finished trials.
+#[cfg(feature = "metrics")]
+pub const PRUNE_RATE: &str = "tpe_prune_rate";
+
+/// Name of the gauge of the best value of a [`Study`].
+#[cfg(feature = "metrics")]
+pub const BEST_VALUE: &str = "tpe_best_value";
+
+/// Name of the histogram of the time taken by [`Study::ask`] in seconds.
+#[cfg(feature = "metrics")]
+pub const ASK_DURATION_SECONDS: &str = "tpe_ask_duration_seconds";
+
+/// A set of [`TpeOptimizer`]s (one per parameter) driven together.
+///
+/// If the `metrics` feature is enabled, the progress of the study is reported via the
+/// [`metrics`](https://docs.rs/metrics) facade (see the constants of this module for the names).
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// let mut study = tpe::study::Study::with_seed(
+///     vec![
+///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+///     ],
+///     0,
+/// );
+/// let best = study.optimize(|p| p[0].powi(2) + p[1].powi(2), 100)?;
+/// assert!(best.value < 1.0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Study<T = DefaultEstimatorBuilder> {
+    optimizers: Vec<TpeOptimizer<T>>,
+    conditions: Vec<Option<Condition>>,
+    subsets: Vec<Subset>,
+    rng: StdRng,
+    trials: Vec<Trial>,
+    best_trial: Option<usize>,
+    pruned_trials: usize,
+    startup_trials: usize,
+}
+
+impl<T: BuildDensityEstimator> Study<T> {
+    /// Makes a new [`Study`] instance seeded from the system entropy.
+    ///
+    /// On `wasm32-unknown-unknown`, the `js` feature is required to obtain the entropy.
+    pub fn new(optimizers: Vec<TpeOptimizer<T>>) -> Self {
+        Self::with_rng(optimizers, StdRng::from_entropy())
+    }
+
+    /// Makes a new [`Study`] instance with a deterministic seed.
+    pub fn with_seed(optimizers: Vec<TpeOptimizer<T>>, seed: u64) -> Self {
+        Self::with_rng(optimizers, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(optimizers: Vec<TpeOptimizer<T>>, rng: StdRng) -> Self {
+        Self {
+            conditions: vec![None; optimizers.len()],
+            subsets: Vec::new(),
+            optimizers,
+            rng,
+            trials: Vec::new(),
+            best_trial: None,
+            pruned_trials: 0,
+            startup_trials: 0,
+        }
+    }
+
+    /// Sets the number of the initial trials whose parameters are sampled independently of the results.
+    ///
+    /// During the startup trials, each parameter is sampled from the density estimated without any trials
+    /// (i.e., uniformly for the bundled estimators).
+    ///
+    /// The default value is `0`.
+    pub fn set_startup_trials(&mut self, n: usize) {
+        self.startup_trials = n;
+    }
+
+    /// Returns the optimizers of the parameters.
+    pub fn optimizers(&self) -> &[TpeOptimizer<T>] {
+        &self.optimizers
+    }
+
+    /// Makes the `param`-th parameter active only when the value of the `parent`-th parameter
+    /// is one of `active_values` (e.g., "momentum" is used only when "optimizer" is "sgd").
+    ///
+    /// The parent must precede the parameter.
+    /// If the parent itself is inactive, the parameter is also inactive.
+    ///
+    /// Inactive parameters are returned as NaN by [`Study::ask`], and
+    /// the optimizer of a parameter is told only the trials in which the parameter was active.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut study = tpe::study::Study::with_seed(
+    ///     vec![
+    ///         // 0: "sgd", 1: "adam"
+    ///         tpe::TpeOptimizer::new(tpe::histogram_estimator(), tpe::categorical_range(2)?),
+    ///         // momentum
+    ///         tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(0.0, 1.0)?),
+    ///     ],
+    ///     0,
+    /// );
+    /// study.set_condition(1, 0, vec![0.0])?;
+    ///
+    /// for _ in 0..10 {
+    ///     let params = study.ask()?;
+    ///     assert_eq!(params[0] == 0.0, !params[1].is_nan());
+    ///     study.tell(params, 1.0)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_condition(
+        &mut self,
+        param: usize,
+        parent: usize,
+        active_values: Vec<f64>,
+    ) -> Result<(), ConditionError> {
+        if param >= self.optimizers.len() {
+            return Err(ConditionError::UnknownParam { index: param });
+        }
+        if parent >= param {
+            return Err(ConditionError::ParentNotPreceding { param, parent });
+        }
+        self.conditions[param] = Some(Condition {
+            parent,
+            active_values,
+        });
+        Ok(())
+    }
+
+    /// Makes the parameters in `params` the inclusion indicators of a subset of `k` items
+    /// (see the [`subset`](crate::subset) module).
+    ///
+    /// [`Study::ask`] sets exactly `k` of them to `1.0` and the others to `0.0`,
+    /// sampling the items by their inclusion log odds learned by the optimizers.
+    /// Conditions of these parameters (see [`Study::set_condition`]) are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// // The first parameter is continuous, and the others select 2 of 5 features.
+    /// let mut optimizers = vec![tpe::TpeOptimizer::new(
+    ///     tpe::parzen_estimator(),
+    ///     tpe::range(0.0, 1.0)?,
+    /// )];
+    /// optimizers.extend(tpe::subset::optimizers(5));
+    /// let mut study = tpe::study::Study::with_seed(optimizers, 0);
+    /// study.set_subset(1..6, 2)?;
+    ///
+    /// for _ in 0..20 {
+    ///     let params = study.ask()?;
+    ///     let features = tpe::subset::decode(&params[1..]);
+    ///     assert_eq!(features.len(), 2);
+    ///     study.tell(params, features.iter().sum::<usize>() as f64)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_subset(
+        &mut self,
+        params: std::ops::Range<usize>,
+        k: usize,
+    ) -> Result<(), SubsetError> {
+        for index in params.clone() {
+            let range = self
+                .optimizers
+                .get(index)
+                .ok_or(SubsetError::UnknownParam { index })?
+                .param_range();
+            if !range.contains(0.0) || !range.contains(1.0) {
+                return Err(SubsetError::NotIndicator { index });
+            }
+            if self.subsets.iter().any(|s| s.params.contains(&index)) {
+                return Err(SubsetError::Overlapping { index });
+            }
+        }
+        if k > params.len() {
+            return Err(SubsetError::TooManyItems {
+                k,
+                items: params.len(),
+            });
+        }
+        if !params.is_empty() {
+            self.subsets.push(Subset { params, k });
+        }
+        Ok(())
+    }
+
+    /// Returns the next parameter values to be evaluated.
+    ///
+    /// Inactive parameters (see [`Study::set_condition`]) are set to NaN.
+    pub fn ask(&mut self) -> Result<Vec<f64>, T::Error> {
+        self.ask_partial(|_| None, &[])
+    }
+
+    /// Same as [`Study::ask`] except that `pending` are the parameter values of the trials
+    /// which are being evaluated but whose results haven't been told yet.
+    ///
+    /// Each optimizer asks its parameter by [`TpeOptimizer::ask_batch`] with the corresponding values of `pending`,
+    /// so if [`TpeOptimizerBuilder::local_penalization`] is set,
+    /// the values close to the pending ones are avoided.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::study::Study;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let optim = tpe::TpeOptimizerBuilder::new()
+    ///     .local_penalization(Some(0.05))
+    ///     .build(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)?;
+    /// let mut study = Study::with_seed(vec![optim], 0);
+    /// for _ in 0..20 {
+    ///     let params = study.ask()?;
+    ///     study.tell(params.clone(), params[0].powi(2))?;
+    /// }
+    ///
+    /// let first = study.ask()?;
+    /// let second = study.ask_with_pending(&[first.clone()])?;
+    /// assert_ne!(first, second);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn ask_with_pending(&mut self, pending: &[Vec<f64>]) -> Result<Vec<f64>, T::Error> {
+        self.ask_partial(|_| None, pending)
+    }
+
+    // Same as `ask_with_pending`, but the active parameters for which `fixed` returns a value are not sampled.
+    pub(crate) fn ask_partial<F>(
+        &mut self,
+        mut fixed: F,
+        pending: &[Vec<f64>],
+    ) -> Result<Vec<f64>, T::Error>
+    where
+        F: FnMut(usize) -> Option<f64>,
+    {
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+
+        let mut params = Vec::with_capacity(self.optimizers.len());
+        while params.len() < self.optimizers.len() {
+            let i = params.len();
+            if let Some(s) = self.subsets.iter().find(|s| s.params.start == i) {
+                let startup = self.trials.len() < self.startup_trials;
+                let optimizers = &mut self.optimizers[s.params.clone()];
+                params.extend(subset::select(optimizers, s.k, startup, &mut self.rng)?);
+                continue;
+            }
+
+            let o = &mut self.optimizers[i];
+            if !is_active(&self.conditions, &params, i) {
+                params.push(f64::NAN);
+            } else if let Some(p) = fixed(i) {
+                params.push(p);
+            } else if self.trials.len() < self.startup_trials {
+                params.push(o.sample_prior(&mut self.rng)?);
+            } else if pending.is_empty() {
+                params.push(o.ask(&mut self.rng)?);
+            } else {
+                let pending = pending
+                    .iter()
+                    .filter_map(|p| p.get(i).copied())
+                    .collect::<Vec<_>>();
+                params.extend(o.ask_batch(&mut self.rng, 1, &pending)?);
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        metrics::histogram!(ASK_DURATION_SECONDS).record(start.elapsed().as_secs_f64());
+        Ok(params)
+    }
+
+    /// Tells the evaluation result of the given parameter values to the optimizers.
+    ///
+    /// The number of `params` must be the same as the number of the optimizers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::study::Study;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut study = Study::new(vec![
+    ///     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(0.0, 1.0)?),
+    ///     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(0.0, 1.0)?),
+    /// ]);
+    /// assert!(study.tell(vec![0.5], 1.0).is_err());
+    /// study.tell(vec![0.5, 0.5], 1.0)?;
+    /// assert_eq!(study.trials().len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tell(&mut self, params: Vec<f64>, value: f64) -> Result<(), TellError> {
+        self.tell_optimizers(&params, value)?;
+
+        if self.best_trial().is_none_or(|t| value < t.value) {
+            self.best_trial = Some(self.trials.len());
+        }
+        self.trials.push(Trial { params, value });
+
+        #[cfg(feature = "metrics")]
+        {
+            metrics::counter!(TRIALS_COMPLETED_TOTAL).increment(1);
+            metrics::gauge!(BEST_VALUE).set(self.best_trial().map_or(value, |t| t.value));
+        }
+        Ok(())
+    }
+
+    // Validates all the parameters, and then tells the result to the optimizers of the active ones.
+    fn tell_optimizers(&mut self, params: &[f64], value: f64) -> Result<(), TellError> {
+        if value.is_nan() {
+            return Err(TellError::NanValue);
+        }
+        if params.len() != self.optimizers.len() {
+            return Err(TellError::LengthMismatch {
+                params: params.len(),
+                values: self.optimizers.len(),
+            });
+        }
+        for (o, &p) in self.optimizers.iter().zip(params.iter()) {
+            o.validate(p)?;
+        }
+        for (i, (o, &p)) in self.optimizers.iter_mut().zip(params.iter()).enumerate() {
+            if is_active(&self.conditions, params, i) {
+                o.tell(p, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tells the evaluation results of multiple trials (e.g., imported from another tool).
+    ///
+    /// If any of the trials is invalid, no trials are told.
+    pub fn tell_many<I>(&mut self, trials: I) -> Result<(), TellError>
+    where
+        I: IntoIterator<Item = Trial>,
+    {
+        let trials = trials.into_iter().collect::<Vec<_>>();
+        for t in &trials {
+            if t.value.is_nan() {
+                return Err(TellError::NanValue);
+            }
+            if t.params.len() != self.optimizers.len() {
+                return Err(TellError::LengthMismatch {
+                    params: t.params.len(),
+                    values: self.optimizers.len(),
+                });
+            }
+            for (o, &p) in self.optimizers.iter().zip(t.params.iter()) {
+                o.validate(p)?;
+            }
+        }
+        for t in trials {
+            self.tell(t.params, t.value)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the next parameter values to be evaluated as an array.
+    ///
+    /// See [`Study::ask`] for details.
+    #[cfg(feature = "ndarray")]
+    pub fn ask_array(&mut self) -> Result<ndarray::Array1<f64>, T::Error> {
+        self.ask().map(ndarray::Array1::from)
+    }
+
+    /// Tells the evaluation results of multiple trials given as arrays.
+    ///
+    /// Each row of `params` holds the parameter values of a trial (one column for each optimizer),
+    /// and the corresponding element of `values` is its evaluation result.
+    /// If any of the trials is invalid, no trials are told.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ndarray::array;
+    /// use tpe::study::Study;
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut study = Study::new(vec![
+    ///     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+    ///     tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?),
+    /// ]);
+    /// let params = array![[0.0, 1.0], [1.0, 2.0]];
+    /// let values = array![1.0, 5.0];
+    /// study.tell_many_array(params.view(), values.view())?;
+    /// assert_eq!(study.best_trial().map(|t| t.value), Some(1.0));
+    ///
+    /// let next = study.ask_array()?;
+    /// assert_eq!(next.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn tell_many_array(
+        &mut self,
+        params: ndarray::ArrayView2<f64>,
+        values: ndarray::ArrayView1<f64>,
+    ) -> Result<(), TellError> {
+        if params.nrows() != values.len() {
+            return Err(TellError::LengthMismatch {
+                params: params.nrows(),
+                values: values.len(),
+            });
+        }
+        self.tell_many(
+            params
+                .rows()
+                .into_iter()
+                .zip(values.iter())
+                .map(|(row, &value)| Trial {
+                    params: row.to_vec(),
+                    value,
+                }),
+        )
+    }
+
+    /// Asks the next parameter values and records them by [`StudyStorage::record_ask`].
+    pub fn ask_and_store<S: StudyStorage>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<Vec<f64>, StorageError<S::Error, T::Error>> {
+        let params = self.ask().map_err(StorageError::Ask)?;
+        storage.record_ask(&params).map_err(StorageError::Storage)?;
+        Ok(params)
+    }
+
+    /// Tells the evaluation result to the optimizers and appends the trial to `storage`.
+    ///
+    /// If the trial is the best one so far, it's also recorded by [`StudyStorage::record_best`].
+    pub fn tell_and_store<S: StudyStorage>(
+        &mut self,
+        storage: &mut S,
+        params: Vec<f64>,
+        value: f64,
+    ) -> Result<(), StorageError<S::Error>> {
+        self.tell(params, value)?;
+        let i = self.trials.len() - 1;
+        storage
+            .append_trial(&self.trials[i])
+            .map_err(StorageError::Storage)?;
+        if self.best_trial == Some(i) {
+            storage
+                .record_best(&self.trials[i])
+                .map_err(StorageError::Storage)?;
+        }
+        Ok(())
+    }
+
+    /// Tells the trials loaded from `storage` (e.g., to resume an interrupted study).
+    pub fn restore<S: StudyStorage>(&mut self, storage: &S) -> Result<(), StorageError<S::Error>> {
+        let trials = storage.load_trials().map_err(StorageError::Storage)?;
+        self.tell_many(trials)?;
+        Ok(())
+    }
+
+    /// Returns the trials told so far (in the order they were told).
+    pub fn trials(&self) -> &[Trial] {
+        &self.trials
+    }
+
+    /// Returns the trial which has the lowest value.
+    pub fn best_trial(&self) -> Option<&Trial> {
+        self.best_trial.map(|i| &self.trials[i])
+    }
+
+    /// Returns the number of trials pruned by [`Study::optimize_with_pruner`].
+    ///
+    /// Note that pruned trials are not included in [`Study::trials`].
+    pub fn pruned_trials(&self) -> usize {
+        self.pruned_trials
+    }
+
+    /// Runs `n_trials` iterations of ask, evaluate, and tell, then returns the best trial.
+    pub fn optimize<F>(
+        &mut self,
+        mut objective: F,
+        n_trials: usize,
+    ) -> Result<Trial, OptimizeError<T::Error>>
+    where
+        F: FnMut(&[f64]) -> f64,
+    {
+        self.try_optimize(|params| Ok(objective(params)), n_trials)
+    }
+
+    /// Fallible version of [`Study::optimize`].
+    ///
+    /// The loop is aborted as soon as `objective` returns an error.
+    pub fn try_optimize<F, E>(
+        &mut self,
+        mut objective: F,
+        n_trials: usize,
+    ) -> Result<Trial, OptimizeError<T::Error, E>>
+    where
+        F: FnMut(&[f64]) -> Result<f64, E>,
+    {
+        for _ in 0..n_trials {
+            let params = self.ask().map_err(OptimizeError::Ask)?;
+            let value = objective(&params).map_err(OptimizeError::Objective)?;
+            self.tell(params, value)?;
+        }
+        self.best_trial().cloned().ok_or(OptimizeError::NoTrials)
+    }
+
+    /// Same as [`Study::optimize`] except that `objective` can report intermediate values to `pruner`
+    /// and stop unpromising evaluations early.
+    ///
+    /// If a trial is pruned, its last intermediate value is told to the optimizers of the active parameters
+    /// as [`Study::tell`] does (if no valid values were reported, the trial is just discarded).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tpe::pruning::{MedianPruner, TrialOutcome};
+    ///
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut study = tpe::study::Study::with_seed(
+    ///     vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)],
+    ///     0,
+    /// );
+    /// let mut pruner = MedianPruner::new();
+    /// let best = study.optimize_with_pruner(
+    ///     |p, trial| {
+    ///         let mut loss = 0.0;
+    ///         for step in 0..10 {
+    ///             loss = p[0].powi(2) + 1.0 / (step + 1) as f64;
+    ///             trial.report(step, loss);
+    ///             if trial.should_prune() {
+    ///                 return TrialOutcome::Pruned;
+    ///             }
+    ///         }
+    ///         TrialOutcome::Completed(loss)
+    ///     },
+    ///     100,
+    ///     &mut pruner,
+    /// )?;
+    /// assert!(best.value < 1.0);
+    /// assert!(study.pruned_trials() > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn optimize_with_pruner<F, P>(
+        &mut self,
+        mut objective: F,
+        n_trials: usize,
+        pruner: &mut P,
+    ) -> Result<Trial, OptimizeError<T::Error>>
+    where
+        F: FnMut(&[f64], &mut PruningTrial<P>) -> TrialOutcome,
+        P: Pruner,
+    {
+        for _ in 0..n_trials {
+            let params = self.ask().map_err(OptimizeError::Ask)?;
+            let mut trial = PruningTrial::new(&*pruner);
+            let outcome = objective(&params, &mut trial);
+            let values = trial.into_intermediate_values();
+            match outcome {
+                TrialOutcome::Completed(value) => {
+                    self.tell(params, value)?;
+                }
+                TrialOutcome::Pruned => {
+                    if let Some((_, value)) = values.last().filter(|(_, v)| !v.is_nan()) {
+                        self.tell_optimizers(&params, value)?;
+                    }
+                    self.pruned_trials += 1;
+
+                    #[cfg(feature = "metrics")]
+                    {
+                        metrics::counter!(TRIALS_PRUNED_TOTAL).increment(1);
+                        let total = self.trials.len() + self.pruned_trials;
+                        metrics::gauge!(PRUNE_RATE).set(self.pruned_trials as f64 / total as f64);
+                    }
+                }
+            }
+            pruner.finish_trial(values);
+        }
+        self.best_trial().cloned().ok_or(OptimizeError::NoTrials)
+    }
+
+    /// Asynchronous version of [`Study::optimize`].
+    ///
+    /// At most `concurrency` evaluations are in flight at the same time.
+    /// Whenever one of them completes, its result is told to the optimizers and
+    /// a new trial is asked (so the later trials can take the earlier results into account).
+    /// The parameters of the evaluations in flight are passed to [`Study::ask_with_pending`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # fn main() -> anyhow::Result<()> {
+    /// use std::num::NonZeroUsize;
+    ///
+    /// let mut study = tpe::study::Study::with_seed(
+    ///     vec![tpe::TpeOptimizer::new(tpe::parzen_estimator(), tpe::range(-5.0, 5.0)?)],
+    ///     0,
+    /// );
+    /// let concurrency = NonZeroUsize::new(4).expect("unreachable");
+    /// let best = futures::executor::block_on(study.optimize_async(
+    ///     |p| async move { p[0].powi(2) },
+    ///     100,
+    ///     concurrency,
+    /// ))?;
+    /// assert!(best.value < 1.0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn optimize_async<F, Fut>(
+        &mut self,
+        mut objective: F,
+        n_trials: usize,
+        concurrency: NonZeroUsize,
+    ) -> Result<Trial, OptimizeError<T::Error>>
+    where
+        F: FnMut(Vec<f64>) -> Fut,
+        Fut: Future<Output = f64>,
+    {
+        self.try_optimize_async(
+            |params| {
+                let future = objective(params);
+                async move { Ok(future.await) }
+            },
+            n_trials,
+            concurrency,
+        )
+        .await
+    }
+
+    /// Fallible version of [`Study::optimize_async`].
+    ///
+    /// The loop is aborted as soon as an evaluation fails.
+    /// The other evaluations in flight at that time are dropped.
+    #[cfg(feature = "async")]
+    pub async fn try_optimize_async<F, Fut, E>(
+        &mut self,
+        mut objective: F,
+        n_trials: usize,
+        concurrency: NonZeroUsize,
+    ) -> Result<Trial, OptimizeError<T::Error, E>>
+    where
+        F: FnMut(Vec<f64>) -> Fut,
+        Fut: Future<Output = Result<f64, E>>,
+    {
+        use futures::stream::{FuturesUnordered, StreamExt as _};
+
+        let mut pendings = FuturesUnordered::new();
+        let mut in_flight: Vec<(usize, Vec<f64>)> = Vec::new();
+        let mut asked = 0;
+        while asked < n_trials || !pendings.is_empty() {
+            while asked < n_trials && pendings.len() < concurrency.get() {
+                let pending = in_flight
+                    .iter()
+                    .map(|(_, params)| params.clone())
+                    .collect::<Vec<_>>();
+                let params = self
+                    .ask_with_pending(&pending)
+                    .map_err(OptimizeError::Ask)?;
+                let future = objective(params.clone());
+                let id = asked;
+                in_flight.push((id, params));
+                pendings.push(async move { (id, future.await) });
+                asked += 1;
+            }
+
+            if let Some((id, result)) = pendings.next().await {
+                let value = result.map_err(OptimizeError::Objective)?;
+                let i = in_flight
+                    .iter()
+                    .position(|(x, _)| *x == id)
+                    .expect("unreachable");
+                let (_, params) = in_flight.swap_remove(i);
+                self.tell(params, value)?;
+            }
+        }
+        self.best_trial().cloned().ok_or(OptimizeError::NoTrials)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Condition {
+    parent: usize,
+    active_values: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct Subset {
+    params: std::ops::Range<usize>,
+    k: usize,
+}
+
+/// Default hyperparameters scaled with the number of dimensions (parameters) of a search space.
+///
+/// With more dimensions, more candidates are needed to cover the space,
+/// more trials fall into the superior group (so that its density can be estimated),
+/// and more random trials are run before the optimization starts.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::study::{DimensionAwareDefaults, Study};
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let defaults = DimensionAwareDefaults::new(10);
+/// let range = tpe::range(-5.0, 5.0)?;
+/// let optimizers = (0..10)
+///     .map(|_| defaults.optimizer_builder().build(tpe::parzen_estimator(), range))
+///     .collect::<Result<Vec<_>, _>>()?;
+/// let mut study = Study::with_seed(optimizers, 0);
+/// study.set_startup_trials(defaults.startup_trials);
+///
+/// let best = study.optimize(|p| p.iter().map(|x| x * x).sum(), 50)?;
+/// assert!(best.value.is_finite());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct DimensionAwareDefaults {
+    /// Number of candidates (see [`TpeOptimizerBuilder::candidates`]).
+    ///
+    /// This is `24 * ceil(sqrt(dims))`.
+    pub candidates: usize,
+
+    /// Percentage of the superior trials (see [`TpeOptimizerBuilder::gamma`]).
+    ///
+    /// This is `min(0.1 * (1 + ln(dims)), 0.25)`.
+    pub gamma: f64,
+
+    /// Number of the startup trials (see [`Study::set_startup_trials`]).
+    ///
+    /// This is `max(10, 2 * dims)`.
+    pub startup_trials: usize,
+}
+
+impl DimensionAwareDefaults {
+    /// Makes the defaults for a search space with `dims` dimensions.
+    pub fn new(dims: usize) -> Self {
+        let dims = dims.max(1);
+        Self {
+            candidates: 24 * (dims as f64).sqrt().ceil() as usize,
+            gamma: (0.1 * (1.0 + (dims as f64).ln())).min(0.25),
+            startup_trials: (2 * dims).max(10),
+        }
+    }
+
+    /// Returns a [`TpeOptimizerBuilder`] configured with the defaults.
+    pub fn optimizer_builder(&self) -> TpeOptimizerBuilder {
+        let mut builder = TpeOptimizerBuilder::new();
+        builder.candidates(self.candidates).gamma(self.gamma);
+        builder
+    }
+}
+
+fn is_active(conditions: &[Option<Condition>], params: &[f64], i: usize) -> bool {
+    match &conditions[i] {
+        None => true,
+        Some(c) => {
+            is_active(conditions, params, c.parent)
+                && params
+                    .get(c.parent)
+                    .is_some_and(|p| c.active_values.contains(p))
+        }
+    }
+}
+
+/// Evaluated trial of a [`Study`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trial {
+    /// Parameter values (one for each optimizer).
+    pub params: Vec<f64>,
+
+    /// Evaluation result.
+    pub value: f64,
+}
+
+/// Possible errors during [`Study::set_condition`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ConditionError {
+    #[error("unknown parameter {index}")]
+    /// Unknown parameter.
+    UnknownParam {
+        /// Parameter index.
+        index: usize,
+    },
+
+    #[error("the parent parameter {parent} must precede the parameter {param}")]
+    /// The parent parameter must precede the parameter.
+    ParentNotPreceding {
+        /// Parameter index.
+        param: usize,
+        /// Parent parameter index.
+        parent: usize,
+    },
+}
+
+/// Possible errors during [`Study::optimize`] and [`Study::try_optimize`].
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum OptimizeError<A, E = std::convert::Infallible> {
+    #[error("failed to ask the next parameters")]
+    /// Failed to ask the next parameters.
+    Ask(#[source] A),
+
+    #[error("failed to tell an evaluation result")]
+    /// Failed to tell an evaluation result.
+    Tell(#[from] TellError),
+
+    #[error("the objective function failed")]
+    /// The objective function failed.
+    Objective(#[source] E),
+
+    #[error("no trials have been evaluated")]
+    /// No trials have been evaluated.
+    NoTrials,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pruning::{IntermediateValues, TrialOutcome};
+
+    struct AlwaysPrune;
+
+    impl Pruner for AlwaysPrune {
+        fn should_prune(&self, _values: &IntermediateValues) -> bool {
+            true
+        }
+
+        fn finish_trial(&mut self, _values: IntermediateValues) {}
+    }
+
+    #[test]
+    fn pruned_trials_skip_inactive_params() -> anyhow::Result<()> {
+        let mut study = Study::with_seed(
+            vec![
+                TpeOptimizer::new(crate::histogram_estimator(), crate::categorical_range(2)?),
+                TpeOptimizer::new(crate::parzen_estimator(), crate::range(0.0, 1.0)?),
+            ],
+            0,
+        );
+        study.set_condition(1, 0, vec![0.0])?;
+
+        let mut active = 0;
+        let result = study.optimize_with_pruner(
+            |p, trial| {
+                if !p[1].is_nan() {
+                    active += 1;
+                }
+                trial.report(0, p[0]);
+                assert!(trial.should_prune());
+                TrialOutcome::Pruned
+            },
+            20,
+            &mut AlwaysPrune,
+        );
+        assert!(matches!(result, Err(OptimizeError::NoTrials)));
+        assert_eq!(study.pruned_trials(), 20);
+
+        assert_eq!(study.optimizers()[0].export_trials().len(), 20);
+        let trials = study.optimizers()[1].export_trials();
+        assert!(0 < active && active < 20);
+        assert_eq!(trials.len(), active);
+        assert!(trials.iter().all(|t| !t.param.is_nan()));
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use crate::study::Trial;
+use crate::TellError;
+
+/// Storage of the trials of a [`Study`](super::Study).
+///
+/// Implement this trait to persist studies to an external backend (e.g., Redis or Postgres),
+/// and use it via [`Study::ask_and_store`](super::Study::ask_and_store), [`Study::tell_and_store`](super::Study::tell_and_store)
+/// and [`Study::restore`](super::Study::restore).
+///
+/// This crate implements it for [`MemoryStorage`], `journal::Journal` (requires the `journal` feature)
+/// and `sqlite::SqliteStorage` (requires the `sqlite` feature).
+pub trait StudyStorage {
+    /// Possible error of the storage.
+    type Error: std::error::Error;
+
+    /// Records the parameters which were asked but not yet evaluated
+    /// (e.g., to find the trials interrupted by a crash).
+    ///
+    /// The default implementation does nothing.
+    fn record_ask(&mut self, params: &[f64]) -> Result<(), Self::Error> {
+        let _ = params;
+        Ok(())
+    }
+
+    /// Appends an evaluated trial.
+    fn append_trial(&mut self, trial: &Trial) -> Result<(), Self::Error>;
+
+    /// Loads all the appended trials (in the order they were appended).
+    fn load_trials(&self) -> Result<Vec<Trial>, Self::Error>;
+
+    /// Records the best trial found so far.
+    fn record_best(&mut self, trial: &Trial) -> Result<(), Self::Error>;
+
+    /// Loads the last recorded best trial.
+    fn load_best(&self) -> Result<Option<Trial>, Self::Error>;
+}
+
+/// [`StudyStorage`] that keeps the trials in memory.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStorage {
+    trials: Vec<Trial>,
+    best: Option<Trial>,
+}
+
+impl MemoryStorage {
+    /// Makes a new empty [`MemoryStorage`] instance.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StudyStorage for MemoryStorage {
+    type Error = std::convert::Infallible;
+
+    fn append_trial(&mut self, trial: &Trial) -> Result<(), Self::Error> {
+        self.trials.push(trial.clone());
+        Ok(())
+    }
+
+    fn load_trials(&self) -> Result<Vec<Trial>, Self::Error> {
+        Ok(self.trials.clone())
+    }
+
+    fn record_best(&mut self, trial: &Trial) -> Result<(), Self::Error> {
+        self.best = Some(trial.clone());
+        Ok(())
+    }
+
+    fn load_best(&self) -> Result<Option<Trial>, Self::Error> {
+        Ok(self.best.clone())
+    }
+}
+
+/// Possible errors during [`Study::ask_and_store`](super::Study::ask_and_store),
+/// [`Study::tell_and_store`](super::Study::tell_and_store) and [`Study::restore`](super::Study::restore).
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError<E, A = std::convert::Infallible> {
+    #[error("failed to tell a trial")]
+    /// Failed to tell a trial.
+    Tell(#[from] TellError),
+
+    #[error("storage error")]
+    /// Storage error.
+    Storage(#[source] E),
+
+    #[error("failed to ask parameters")]
+    /// Failed to ask parameters.
+    Ask(#[source] A),
+}
@@ -0,0 +1,256 @@
+//! Optimization over fixed-size subsets (e.g., selecting 5 of 30 features).
+//!
+//! A subset of `n` items is encoded as `n` inclusion indicators:
+//! the `i`-th parameter is `1.0` if the `i`-th item is included, otherwise `0.0`.
+//! Each indicator is a categorical parameter with two categories, so its optimizer learns
+//! how much more often the item is included in the superior trials than in the inferior ones.
+//!
+//! [`Study::set_subset`] makes a [`Study`] select exactly `k` of the indicators in each trial
+//! (see also [`optimizers`], [`encode`] and [`decode`]),
+//! or [`SubsetOptimizer`] can be used when the subset is the only parameter.
+//!
+//! # Examples
+//!
+//! ```
+//! use tpe::subset::SubsetOptimizer;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut optim = SubsetOptimizer::with_seed(10, 3, 0)?;
+//! for _ in 0..50 {
+//!     let items = optim.ask()?;
+//!     assert_eq!(items.len(), 3);
+//!     optim.tell(&items, items.iter().sum::<usize>() as f64)?;
+//! }
+//! let best = optim.best_subset().expect("unreachable");
+//! assert!(best.iter().sum::<usize>() < 10);
+//! # Ok(())
+//! # }
+//! ```
+use crate::density_estimation::BuildDensityEstimator;
+use crate::density_estimation::EstimatorBuildError;
+use crate::study::Study;
+use crate::{categorical_range, histogram_estimator, TellError, TpeOptimizer};
+use rand::Rng;
+
+/// Makes the optimizers of the inclusion indicators of `n` items.
+///
+/// Each optimizer uses a histogram estimator over two categories.
+pub fn optimizers(n: usize) -> Vec<TpeOptimizer> {
+    (0..n)
+        .map(|_| {
+            TpeOptimizer::new(
+                histogram_estimator(),
+                categorical_range(2).expect("unreachable"),
+            )
+        })
+        .collect()
+}
+
+/// Encodes a subset of `0..n` into its inclusion indicators.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// assert_eq!(tpe::subset::encode(&[3, 0], 4)?, [1.0, 0.0, 0.0, 1.0]);
+/// assert!(tpe::subset::encode(&[0, 0], 4).is_err());
+/// # Ok(())
+/// # }
+/// ```
+pub fn encode(items: &[usize], n: usize) -> Result<Vec<f64>, SubsetError> {
+    let mut indicators = vec![0.0; n];
+    for &item in items {
+        if item >= n {
+            return Err(SubsetError::ItemOutOfRange { item, n });
+        }
+        if std::mem::replace(&mut indicators[item], 1.0) == 1.0 {
+            return Err(SubsetError::DuplicateItem { item });
+        }
+    }
+    Ok(indicators)
+}
+
+/// Decodes inclusion indicators into the included items (in ascending order).
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(tpe::subset::decode(&[1.0, 0.0, 0.0, 1.0]), [0, 3]);
+/// ```
+pub fn decode(indicators: &[f64]) -> Vec<usize> {
+    indicators
+        .iter()
+        .enumerate()
+        .filter(|&(_, &x)| x == 1.0)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Samples `k` items by the Gumbel-top-k trick, where the log weight of an item is its inclusion log odds
+// (zero during the startup trials).
+pub(crate) fn select<T, R>(
+    optimizers: &mut [TpeOptimizer<T>],
+    k: usize,
+    startup: bool,
+    rng: &mut R,
+) -> Result<Vec<f64>, T::Error>
+where
+    T: BuildDensityEstimator,
+    R: Rng + ?Sized,
+{
+    let mut scores = Vec::with_capacity(optimizers.len());
+    for (i, o) in optimizers.iter_mut().enumerate() {
+        let mut score = 0.0;
+        if !startup {
+            let scores = o.acquisition_batch(&[0.0, 1.0])?;
+            score = scores[1] - scores[0];
+            if score.is_nan() {
+                score = 0.0;
+            }
+        }
+        let u: f64 = rng.gen_range(f64::MIN_POSITIVE..1.0);
+        scores.push((score - (-u.ln()).ln(), i));
+    }
+    scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut indicators = vec![0.0; optimizers.len()];
+    for &(_, i) in scores.iter().take(k) {
+        indicators[i] = 1.0;
+    }
+    Ok(indicators)
+}
+
+/// Optimizer of a subset of `k` of `n` items backed by a [`Study`] over its inclusion indicators.
+#[derive(Debug)]
+pub struct SubsetOptimizer {
+    study: Study,
+    k: usize,
+}
+
+impl SubsetOptimizer {
+    /// Makes a new [`SubsetOptimizer`] instance seeded from the system entropy.
+    pub fn new(n: usize, k: usize) -> Result<Self, SubsetError> {
+        Self::with_study(Study::new(optimizers(n)), k)
+    }
+
+    /// Makes a new [`SubsetOptimizer`] instance with a deterministic seed.
+    pub fn with_seed(n: usize, k: usize, seed: u64) -> Result<Self, SubsetError> {
+        Self::with_study(Study::with_seed(optimizers(n), seed), k)
+    }
+
+    fn with_study(mut study: Study, k: usize) -> Result<Self, SubsetError> {
+        let n = study.optimizers().len();
+        study.set_subset(0..n, k)?;
+        Ok(Self { study, k })
+    }
+
+    /// Returns the number of the items.
+    pub fn len(&self) -> usize {
+        self.study.optimizers().len()
+    }
+
+    /// Returns `true` if the number of the items is zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of the items in a subset.
+    pub fn subset_size(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the next subset to be evaluated.
+    pub fn ask(&mut self) -> Result<Vec<usize>, EstimatorBuildError> {
+        Ok(decode(&self.study.ask()?))
+    }
+
+    /// Tells the evaluation result of a subset.
+    pub fn tell(&mut self, items: &[usize], value: f64) -> Result<(), SubsetError> {
+        if items.len() != self.k {
+            return Err(SubsetError::SizeMismatch {
+                expected: self.k,
+                actual: items.len(),
+            });
+        }
+        self.study.tell(encode(items, self.len())?, value)?;
+        Ok(())
+    }
+
+    /// Returns the best subset told so far.
+    pub fn best_subset(&self) -> Option<Vec<usize>> {
+        self.study.best_trial().map(|t| decode(&t.params))
+    }
+
+    /// Returns the underlying [`Study`].
+    pub fn study(&self) -> &Study {
+        &self.study
+    }
+
+    /// Returns the underlying [`Study`], consuming this optimizer.
+    pub fn into_study(self) -> Study {
+        self.study
+    }
+}
+
+/// Possible errors related to subsets.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum SubsetError {
+    #[error("unknown parameter {index}")]
+    /// Unknown parameter.
+    UnknownParam {
+        /// Parameter index.
+        index: usize,
+    },
+
+    #[error("the range of the parameter {index} cannot represent an inclusion indicator")]
+    /// The range of a parameter doesn't contain both `0.0` and `1.0`.
+    NotIndicator {
+        /// Parameter index.
+        index: usize,
+    },
+
+    #[error("cannot select {k} of {items} items")]
+    /// The subset size exceeds the number of the items.
+    TooManyItems {
+        /// Subset size.
+        k: usize,
+        /// Number of the items.
+        items: usize,
+    },
+
+    #[error("the parameter {index} already belongs to another subset")]
+    /// A parameter already belongs to another subset.
+    Overlapping {
+        /// Parameter index.
+        index: usize,
+    },
+
+    #[error("the item {item} is out of 0..{n}")]
+    /// An item is out of the range.
+    ItemOutOfRange {
+        /// Item.
+        item: usize,
+        /// Number of the items.
+        n: usize,
+    },
+
+    #[error("the item {item} is duplicated")]
+    /// An item is duplicated.
+    DuplicateItem {
+        /// Item.
+        item: usize,
+    },
+
+    #[error("expected a subset of {expected} items, but got {actual} items")]
+    /// The number of the items differs from the optimizer's subset size.
+    SizeMismatch {
+        /// Expected number of the items.
+        expected: usize,
+        /// Actual number of the items.
+        actual: usize,
+    },
+
+    #[error(transparent)]
+    /// Failed to tell the encoded subset.
+    Tell(#[from] TellError),
+}
@@ -0,0 +1,87 @@
+//! Monotone transforms of parameter values.
+//!
+//! A transform attached to a [`TpeOptimizer`](crate::TpeOptimizer)
+//! (via [`TpeOptimizerBuilder::transform`](crate::TpeOptimizerBuilder::transform))
+//! changes the space in which densities are estimated,
+//! while [`ask`](crate::TpeOptimizer::ask) and [`tell`](crate::TpeOptimizer::tell) keep handling the original values.
+
+/// This trait allows mapping parameter values to the space in which densities are estimated.
+///
+/// The transform must be strictly increasing and map the (warped) parameter range to a finite range.
+/// Note that it is applied on top of [`Range::warp`](crate::range::Range::warp),
+/// so it is usually used with linear ranges.
+pub trait ParamTransform: std::fmt::Debug + Send + Sync {
+    /// Maps a parameter value to the estimation space.
+    fn warp(&self, v: f64) -> f64;
+
+    /// Inverse of [`ParamTransform::warp`].
+    fn unwarp(&self, v: f64) -> f64;
+}
+
+/// Natural logarithm transform.
+///
+/// # Examples
+///
+/// ```
+/// use tpe::transform::LogTransform;
+///
+/// # fn main() -> anyhow::Result<()> {
+/// let mut optim = tpe::TpeOptimizerBuilder::new()
+///     .transform(LogTransform)
+///     .build(tpe::parzen_estimator(), tpe::range(1e-5, 1e-1)?)?;
+/// let mut rng = rand::thread_rng();
+/// for _ in 0..10 {
+///     let lr = optim.ask(&mut rng)?;
+///     assert!(1e-5 <= lr && lr < 1e-1);
+///     optim.tell(lr, (lr.log10() + 3.0).abs())?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogTransform;
+
+impl ParamTransform for LogTransform {
+    fn warp(&self, v: f64) -> f64 {
+        v.ln()
+    }
+
+    fn unwarp(&self, v: f64) -> f64 {
+        v.exp()
+    }
+}
+
+/// Square root transform (for non-negative parameters).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqrtTransform;
+
+impl ParamTransform for SqrtTransform {
+    fn warp(&self, v: f64) -> f64 {
+        v.sqrt()
+    }
+
+    fn unwarp(&self, v: f64) -> f64 {
+        v.powi(2)
+    }
+}
+
+/// Logit transform (for parameters in `[0, 1]` such as probabilities).
+///
+/// Values are clipped to `[1e-6, 1 - 1e-6]` to keep the estimation space finite.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogitTransform;
+
+impl LogitTransform {
+    const EPS: f64 = 1e-6;
+}
+
+impl ParamTransform for LogitTransform {
+    fn warp(&self, v: f64) -> f64 {
+        let v = v.clamp(Self::EPS, 1.0 - Self::EPS);
+        (v / (1.0 - v)).ln()
+    }
+
+    fn unwarp(&self, v: f64) -> f64 {
+        1.0 / (1.0 + (-v).exp())
+    }
+}